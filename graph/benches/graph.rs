@@ -0,0 +1,144 @@
+//! Times looking up nodes and drawing the graph for trees of growing size.
+//!
+//! Run with `cargo bench -p graph`. Both should stay about as fast per node as the graph grows: lookups go through
+//! the id index, and drawing only touches what the spatial index finds on the visible part of the canvas.
+
+use std::time::{Duration, Instant};
+
+use graph::{Graph, GraphInteraction, edge::GraphEdgeTrait, node::GraphNodeTrait};
+use iced::{
+    Color, Font, Pixels, Point, Rectangle, Renderer, Size, Theme, mouse,
+    widget::canvas::{Frame, Path, Program, Stroke, Text},
+};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+const NODE_SIZE: Size = Size::new(128.0, 64.0);
+const COLUMNS: usize = 300;
+
+struct BenchNode {
+    id: u128,
+    anchor: Point,
+}
+
+impl GraphNodeTrait for BenchNode {
+    fn new(anchor: Point) -> Self {
+        Self { id: 0, anchor }
+    }
+    fn id(&self) -> u128 {
+        self.id
+    }
+    fn anchor(&self) -> Point {
+        self.anchor
+    }
+    fn set_anchor(&mut self, anchor: Point) {
+        self.anchor = anchor;
+    }
+    fn size(&self) -> Size {
+        NODE_SIZE
+    }
+    fn draw_content<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
+        frame.fill_text(Text {
+            content: format!("Person {}", self.id),
+            position: self.anchor,
+            ..Default::default()
+        });
+        frame
+    }
+}
+
+struct BenchEdge {
+    start: u128,
+    end: u128,
+}
+
+impl GraphEdgeTrait for BenchEdge {
+    fn new(start: u128, end: u128) -> Self {
+        Self { start, end }
+    }
+    fn start(&self) -> u128 {
+        self.start
+    }
+    fn end(&self) -> u128 {
+        self.end
+    }
+    fn draw(&self, frame: &mut Frame, start: Rectangle, end: Rectangle, hovered: bool) {
+        let width = if hovered { 4.0 } else { 2.0 };
+        let stroke = Stroke::default().with_width(width).with_color(Color::WHITE);
+        frame.stroke(&Path::line(start.center(), end.center()), stroke);
+    }
+    fn is_in_bounds(&self, _point: Point, _start: Rectangle, _end: Rectangle) -> bool {
+        false
+    }
+}
+
+/// A binary tree of `size` nodes laid out on a grid, each node linked to its parent.
+fn tree(size: usize) -> Graph<BenchNode, BenchEdge> {
+    let mut graph = Graph::default();
+    for index in 0..size {
+        let anchor = Point::new(
+            (index % COLUMNS) as f32 * NODE_SIZE.width * 1.5,
+            (index / COLUMNS) as f32 * NODE_SIZE.height * 2.0,
+        );
+        graph.insert_node(BenchNode {
+            id: index as u128 + 1,
+            anchor,
+        });
+        if index > 0 {
+            graph.insert_edge(BenchEdge::new((index as u128 - 1) / 2 + 1, index as u128 + 1));
+        }
+    }
+    graph
+}
+
+/// The fastest of a few runs, the one least disturbed by whatever else the machine is doing.
+fn fastest(runs: usize, mut run: impl FnMut()) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let renderer = Renderer::Secondary(iced_tiny_skia::Renderer::new(Font::DEFAULT, Pixels(16.0)));
+    let bounds = Rectangle::new(Point::ORIGIN, Size::new(1280.0, 800.0));
+
+    println!(
+        "{:>8} {:>14} {:>14} {:>12}",
+        "nodes", "lookup/node", "edges_of/node", "draw"
+    );
+    for size in SIZES {
+        let mut graph = tree(size);
+        let lookup = fastest(5, || {
+            for id in 1..=size as u128 {
+                std::hint::black_box(graph.get_node(Some(id)));
+            }
+        });
+        let adjacency = fastest(5, || {
+            for id in 1..=size as u128 {
+                std::hint::black_box(graph.edges_of(id).count());
+            }
+        });
+        let draw = fastest(5, || {
+            graph.redraw();
+            let geometry = graph.draw(
+                &GraphInteraction::None,
+                &renderer,
+                &Theme::Dark,
+                bounds,
+                mouse::Cursor::Unavailable,
+            );
+            std::hint::black_box(geometry);
+        });
+        println!(
+            "{:>8} {:>12.1?} {:>14.1?} {:>12.2?}",
+            size,
+            lookup / size as u32,
+            adjacency / size as u32,
+            draw
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use iced::{Point, Rectangle, widget::canvas::Frame};
+
+/// What the graph needs of an edge: the nodes it runs between, and how it is drawn and hit between their bounds.
+pub trait GraphEdgeTrait {
+    fn new(start: u128, end: u128) -> Self;
+    fn start(&self) -> u128;
+    fn end(&self) -> u128;
+
+    /// Draws the edge between the bounds of its start and end node.
+    fn draw(&self, frame: &mut Frame, start: Rectangle, end: Rectangle, hovered: bool);
+
+    /// Whether the point lies on the edge as it is drawn between the bounds of its start and end node. Only asked for
+    /// points close to the bounds of both nodes together.
+    fn is_in_bounds(&self, point: Point, start: Rectangle, end: Rectangle) -> bool;
+
+    /// Whether the edge runs between the two nodes, whichever of them it starts at.
+    fn joins(&self, first: u128, second: u128) -> bool {
+        (self.start() == first && self.end() == second) || (self.start() == second && self.end() == first)
+    }
+
+    fn touches(&self, node_id: u128) -> bool {
+        self.start() == node_id || self.end() == node_id
+    }
+}
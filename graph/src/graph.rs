@@ -1,31 +1,33 @@
+use std::{cell::OnceCell, collections::HashMap};
+
 use iced::{
-    event::Status,
+    Color, Element, Event,
+    Length::Fill,
+    Point, Rectangle, Renderer, Size, Subscription, Theme, Vector,
+    event::{self, Status},
     keyboard::{self, Key},
     mouse::{self},
     widget::{
-        canvas::{self, Cache, Path, Stroke},
         Canvas,
+        canvas::{self, Cache, Path, Stroke},
     },
-    Color, Element,
-    Length::Fill,
-    Point, Rectangle, Renderer, Size, Theme, Vector,
 };
 
-use crate::node::GraphNodeTrait;
+use crate::{edge::GraphEdgeTrait, node::GraphNodeTrait, spatial::SpatialIndex};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum GraphInteraction {
+    #[default]
     None,
-    Panning { translation: Vector, start: Point },
+    Panning {
+        translation: Vector,
+        start: Point,
+    },
     HoverNode(u128),
+    HoverEdge(u128, u128),
     DraggingNode(u128, Point),
 }
 
-impl Default for GraphInteraction {
-    fn default() -> Self {
-        Self::None
-    }
-}
 #[derive(Debug, Clone)]
 pub enum GraphMessage {
     InsertNode(Option<u128>),
@@ -37,12 +39,22 @@ pub enum GraphMessage {
     DrawEdge(u128),
     InsertEdge(u128, u128),
     CancelDrawEdge,
+    /// Asks for a node or edge to be removed, which waits in `pending_removal` until it is confirmed or cancelled.
+    RequestRemoval(Removal),
+    CancelRemoval,
+    RemoveNode(u128),
+    RemoveEdge(u128, u128),
+    /// The edge under the cursor changed, `None` when it left every edge.
+    HoveredEdge(Option<(u128, u128)>),
+    /// Delete was pressed outside of any widget taking it, see [`keyboard_subscription`].
+    DeletePressed,
 }
 
-#[derive(Debug, Clone)]
-pub struct Edge {
-    start: u128,
-    end: u128,
+/// A node or the edges between two nodes, waiting for the user to confirm removing it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Removal {
+    Node(u128),
+    Edge(u128, u128),
 }
 
 #[derive(Debug, Clone)]
@@ -51,38 +63,57 @@ pub struct Region {
     y: f32,
 }
 
-pub struct Graph<T: GraphNodeTrait> {
-    nodes: Vec<T>,
+pub struct Graph<N: GraphNodeTrait, E: GraphEdgeTrait> {
+    nodes: Vec<N>,
+    /// Where each node is in `nodes`, by its id.
+    slots: HashMap<u128, usize>,
     tick: u128,
     bounds: Rectangle,
-    edges: Vec<Edge>,
+    edges: Vec<E>,
+    /// Where the edges each node is an end of are in `edges`, by the node's id.
+    adjacency: HashMap<u128, Vec<usize>>,
+    /// Where the nodes and edges lie on the canvas, built on first use after they changed. See [`Graph::index`].
+    index: OnceCell<SpatialIndex>,
     scaling: f32,
     translation: Vector,
     selected_node: Option<u128>,
     cache: Cache,
     drawing_edge: Option<u128>,
+    /// The edge under the cursor, which Delete removes before the selected node.
+    hovered_edge: Option<(u128, u128)>,
+    pending_removal: Option<Removal>,
 }
 
-impl<T: GraphNodeTrait> Default for Graph<T> {
+impl<N: GraphNodeTrait, E: GraphEdgeTrait> Default for Graph<N, E> {
     fn default() -> Self {
         Self {
             nodes: vec![],
+            slots: HashMap::new(),
             tick: 0,
             bounds: Rectangle::new(Point::ORIGIN, Size::new(0.0, 0.0)),
             edges: vec![],
+            adjacency: HashMap::new(),
+            index: OnceCell::new(),
             scaling: 1.0,
             translation: Vector::default(),
             selected_node: None,
             cache: Cache::default(),
             drawing_edge: None,
+            hovered_edge: None,
+            pending_removal: None,
         }
     }
 }
 
-impl<T: GraphNodeTrait> Graph<T> {
+impl<N: GraphNodeTrait, E: GraphEdgeTrait> Graph<N, E> {
     const MIN_SCALING: f32 = 0.1;
     const MAX_SCALING: f32 = 2.0;
     const GRID_SIZE: f32 = 32.0;
+    /// How far around the visible part of the canvas is still drawn, so that labels sticking out of a node or edge
+    /// just outside of it are not cut off.
+    const DRAW_MARGIN: f32 = 64.0;
+    /// How far outside the bounds of both its ends an edge is still hit-tested, enough for the width of its stroke.
+    const HIT_MARGIN: f32 = 16.0;
 
     pub fn redraw(&mut self) {
         self.cache.clear();
@@ -114,35 +145,110 @@ impl<T: GraphNodeTrait> Graph<T> {
         )
     }
 
-    pub fn get_node(&self, node_id: Option<u128>) -> Option<&T> {
-        self.nodes.iter().find(|node| node.id() == node_id.unwrap_or(0))
+    pub fn get_node(&self, node_id: Option<u128>) -> Option<&N> {
+        let slot = self.slots.get(&node_id?)?;
+        self.nodes.get(*slot)
     }
-    pub fn get_node_unsafe(&self, node_id: Option<u128>) -> &T {
+    pub fn get_node_unsafe(&self, node_id: Option<u128>) -> &N {
         self.get_node(node_id).unwrap()
     }
-    pub fn get_node_mut(&mut self, node_id: Option<u128>) -> Option<&mut T> {
-        self.nodes.iter_mut().find(|node| node.id() == node_id.unwrap_or(0))
+    /// The id of the node must not be changed through it, nodes are found by the id they were inserted with.
+    pub fn get_node_mut(&mut self, node_id: Option<u128>) -> Option<&mut N> {
+        let slot = self.slots.get(&node_id?)?;
+        self.index.take();
+        self.nodes.get_mut(*slot)
     }
-    pub fn get_node_mut_unsafe(&mut self, node_id: Option<u128>) -> &mut T {
+    pub fn get_node_mut_unsafe(&mut self, node_id: Option<u128>) -> &mut N {
         self.get_node_mut(node_id).unwrap()
     }
-    pub fn edges(&self) -> &Vec<Edge> {
+    pub fn nodes(&self) -> &Vec<N> {
+        &self.nodes
+    }
+    /// The ids of the nodes must not be changed through it, nodes are found by the id they were inserted with.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut N> {
+        self.index.take();
+        self.nodes.iter_mut()
+    }
+    pub fn edges(&self) -> &Vec<E> {
         &self.edges
     }
+    /// The ends of the edges must not be changed through it, edges are found by the nodes they were inserted between.
+    pub fn edges_mut(&mut self) -> impl Iterator<Item = &mut E> {
+        self.edges.iter_mut()
+    }
+
+    /// The edges a node is an end of.
+    pub fn edges_of(&self, node_id: u128) -> impl Iterator<Item = &E> {
+        self.adjacency
+            .get(&node_id)
+            .into_iter()
+            .flatten()
+            .map(|index| &self.edges[*index])
+    }
+
+    /// Changes the edges a node is an end of, leaving the rest alone. Their ends must not be changed.
+    pub fn update_edges_of(&mut self, node_id: u128, mut update: impl FnMut(&mut E)) {
+        for index in self.adjacency.get(&node_id).into_iter().flatten() {
+            update(&mut self.edges[*index]);
+        }
+        self.cache.clear();
+    }
 
     pub fn add_edge_between_nodes(&mut self, start_node_id: Option<u128>, end_node_id: u128) {
-        if let None = start_node_id {
+        let Some(start_node_id) = start_node_id else {
             return;
+        };
+        self.insert_edge(E::new(start_node_id, end_node_id));
+    }
+
+    /// Adds an edge unless it would run from a node to itself. Returns whether it was added.
+    pub fn insert_edge(&mut self, edge: E) -> bool {
+        if edge.start() == edge.end() {
+            return false;
+        }
+        let index = self.edges.len();
+        self.adjacency.entry(edge.start()).or_default().push(index);
+        self.adjacency.entry(edge.end()).or_default().push(index);
+        self.edges.push(edge);
+        self.index.take();
+        self.cache.clear();
+        true
+    }
+
+    /// Keeps only the edges the predicate holds for.
+    pub fn retain_edges(&mut self, keep: impl FnMut(&E) -> bool) {
+        self.edges.retain(keep);
+        self.index_edges();
+        self.index.take();
+        self.cache.clear();
+    }
+
+    /// Rebuilds the adjacency lists after any number of edges were removed and the rest moved up.
+    fn index_edges(&mut self) {
+        self.adjacency.clear();
+        for (index, edge) in self.edges.iter().enumerate() {
+            self.adjacency.entry(edge.start()).or_default().push(index);
+            self.adjacency.entry(edge.end()).or_default().push(index);
         }
-        self.edges.push(Edge {
-            start: start_node_id.unwrap(),
-            end: end_node_id,
-        });
     }
 
     pub fn bounds(&self) -> Rectangle {
         self.bounds
     }
+    pub fn translation(&self) -> Vector {
+        self.translation
+    }
+    pub fn set_translation(&mut self, translation: Vector) {
+        self.translation = translation;
+        self.redraw();
+    }
+    pub fn scaling(&self) -> f32 {
+        self.scaling
+    }
+    pub fn set_scaling(&mut self, scaling: f32) {
+        self.scaling = scaling.clamp(Self::MIN_SCALING, Self::MAX_SCALING);
+        self.redraw();
+    }
     pub fn set_selected_node(&mut self, node_id: u128) {
         self.selected_node = Some(node_id);
     }
@@ -152,20 +258,155 @@ impl<T: GraphNodeTrait> Graph<T> {
         self.selected_node = None;
     }
 
-    pub fn selected_node(&self) -> Option<&T> {
-        self.nodes.iter().find(|node| self.selected_node == Some(node.id()))
+    pub fn selected_node(&self) -> Option<&N> {
+        self.get_node(self.selected_node)
     }
 
     pub fn tick(&mut self) {
         self.tick += 1;
     }
 
-    pub fn view(&self) -> Element<GraphMessage> {
+    pub fn view(&self) -> Element<'_, GraphMessage> {
         Canvas::new(self).width(Fill).height(Fill).into()
     }
 
-    pub fn insert_node(&mut self, node: T) {
+    /// The bounds of the nodes an edge runs between, `None` when one of them is missing.
+    fn edge_ends(&self, edge: &E) -> Option<(Rectangle, Rectangle)> {
+        let start = self.get_node(Some(edge.start()))?;
+        let end = self.get_node(Some(edge.end()))?;
+        Some((start.bounds(), end.bounds()))
+    }
+
+    /// The bounds of both ends of an edge together, which is all the index knows of where the edge runs.
+    fn edge_bounds(&self, edge: &E) -> Option<Rectangle> {
+        self.edge_ends(edge).map(|(start, end)| start.union(&end))
+    }
+
+    /// Where the nodes and edges lie, so that drawing and hit-testing take time by what is near rather than by the
+    /// size of the graph. Built anew after a node or edge was added or removed or a node handed out mutably.
+    fn index(&self) -> &SpatialIndex {
+        self.index.get_or_init(|| {
+            SpatialIndex::new(
+                self.nodes.iter().map(N::bounds),
+                self.edges.iter().map(|edge| self.edge_bounds(edge)),
+            )
+        })
+    }
+
+    /// Moves a node while it is dragged. Only the node and its edges are moved in the index, it is not built anew.
+    fn drag_node(&mut self, slot: usize, anchor: Point) {
+        let edges = self.adjacency.get(&self.nodes[slot].id()).cloned().unwrap_or_default();
+        let edge_bounds = |graph: &Self| -> Vec<Option<Rectangle>> {
+            edges
+                .iter()
+                .map(|index| graph.edge_bounds(&graph.edges[*index]))
+                .collect()
+        };
+        let (from, edges_from) = (self.nodes[slot].bounds(), edge_bounds(self));
+        self.nodes[slot].set_anchor(anchor);
+        let (to, edges_to) = (self.nodes[slot].bounds(), edge_bounds(self));
+        if let Some(index) = self.index.get_mut() {
+            index.move_node(slot, from, to);
+            for ((edge, from), to) in edges.iter().zip(edges_from).zip(edges_to) {
+                index.move_edge(*edge, from, to);
+            }
+        }
+    }
+
+    /// Adds a node unless one with the same id is already in the graph. Returns whether it was added.
+    pub fn insert_node(&mut self, node: N) -> bool {
+        if self.slots.contains_key(&node.id()) {
+            return false;
+        }
+        self.slots.insert(node.id(), self.nodes.len());
         self.nodes.push(node);
+        self.index.take();
+        true
+    }
+
+    /// Removes a node together with every edge it is an end of. The last node takes the place of the removed one.
+    pub fn remove_node(&mut self, node_id: u128) -> Option<N> {
+        let index = self.slots.remove(&node_id)?;
+        let node = self.nodes.swap_remove(index);
+        self.index.take();
+        let last = self.nodes.len();
+        if let Some(moved) = self.nodes.get(index)
+            && let Some(slot) = self.slots.get_mut(&moved.id())
+            && *slot == last
+        {
+            *slot = index;
+        }
+        let mut edges = self.adjacency.get(&node_id).cloned().unwrap_or_default();
+        // From the back, so that the edge moving into a freed place is never one still to be removed.
+        edges.sort_unstable_by(|a, b| b.cmp(a));
+        for index in edges {
+            self.remove_edge_at(index);
+        }
+        if self.selected_node == Some(node_id) {
+            self.selected_node = None;
+        }
+        if self.drawing_edge == Some(node_id) {
+            self.drawing_edge = None;
+        }
+        self.pending_removal = self.pending_removal.filter(|removal| match *removal {
+            Removal::Node(id) => id != node_id,
+            Removal::Edge(first, second) => first != node_id && second != node_id,
+        });
+        self.cache.clear();
+        Some(node)
+    }
+
+    /// Removes the edges between two nodes, whichever of them they start at. Returns whether there were any.
+    pub fn remove_edge(&mut self, first: u128, second: u128) -> bool {
+        let mut edges: Vec<usize> = self
+            .adjacency
+            .get(&first)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|index| self.edges[*index].joins(first, second))
+            .collect();
+        edges.sort_unstable_by(|a, b| b.cmp(a));
+        let removed = !edges.is_empty();
+        for index in edges {
+            self.remove_edge_at(index);
+        }
+        if self.pending_removal == Some(Removal::Edge(first, second)) {
+            self.pending_removal = None;
+        }
+        self.cache.clear();
+        removed
+    }
+
+    /// Removes the edge at a position, the last edge takes its place. Only the adjacency lists of the two edges' ends
+    /// are touched.
+    fn remove_edge_at(&mut self, index: usize) -> E {
+        let edge = self.edges.swap_remove(index);
+        self.index.take();
+        for end in [edge.start(), edge.end()] {
+            if let Some(indices) = self.adjacency.get_mut(&end) {
+                indices.retain(|other| *other != index);
+                if indices.is_empty() {
+                    self.adjacency.remove(&end);
+                }
+            }
+        }
+        let last = self.edges.len();
+        if let Some(moved) = self.edges.get(index) {
+            for end in [moved.start(), moved.end()] {
+                for other in self.adjacency.get_mut(&end).into_iter().flatten() {
+                    if *other == last {
+                        *other = index;
+                    }
+                }
+            }
+        }
+        edge
+    }
+
+    /// The node or edge waiting for the user to confirm its removal.
+    pub fn pending_removal(&self) -> Option<Removal> {
+        self.pending_removal
     }
 
     pub fn update(&mut self, message: GraphMessage) {
@@ -194,15 +435,16 @@ impl<T: GraphNodeTrait> Graph<T> {
                     center = found_node.anchor();
                     center.y += found_node.size().height * 2.0;
                 }
-                let new_node = T::new(center);
+                let new_node = N::new(center);
                 self.add_edge_between_nodes(edge_node_id, new_node.id());
                 self.set_selected_node(new_node.id());
                 self.insert_node(new_node);
                 self.cache.clear();
             }
             GraphMessage::DraggingNode(id, offset) => {
-                let node = self.get_node_mut_unsafe(Some(id));
-                node.set_anchor(Self::snap_to_grid(offset));
+                if let Some(slot) = self.slots.get(&id) {
+                    self.drag_node(*slot, Self::snap_to_grid(offset));
+                }
             }
             GraphMessage::ClickOutsideNode(_) => {
                 if let Some(selected_node) = self.selected_node {
@@ -220,6 +462,35 @@ impl<T: GraphNodeTrait> Graph<T> {
                 self.add_edge_between_nodes(Some(start_id), end_id);
                 self.drawing_edge = None;
             }
+            GraphMessage::RequestRemoval(removal) => {
+                self.pending_removal = Some(removal);
+            }
+            GraphMessage::CancelRemoval => {
+                self.pending_removal = None;
+            }
+            GraphMessage::RemoveNode(id) => {
+                self.remove_node(id);
+                self.hovered_edge = None;
+                self.pending_removal = None;
+            }
+            GraphMessage::RemoveEdge(first, second) => {
+                self.remove_edge(first, second);
+                self.hovered_edge = None;
+                self.pending_removal = None;
+            }
+            GraphMessage::HoveredEdge(edge) => {
+                self.hovered_edge = edge;
+            }
+            GraphMessage::DeletePressed => {
+                // Removing is only asked for here, the host confirms it.
+                let removal = match self.hovered_edge {
+                    Some((start, end)) => Some(Removal::Edge(start, end)),
+                    None => self.selected_node.map(Removal::Node),
+                };
+                if removal.is_some() {
+                    self.pending_removal = removal;
+                }
+            }
         }
     }
 
@@ -232,7 +503,7 @@ impl<T: GraphNodeTrait> Graph<T> {
     }
 }
 
-impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
+impl<N: GraphNodeTrait, E: GraphEdgeTrait> canvas::Program<GraphMessage> for Graph<N, E> {
     type State = GraphInteraction;
 
     fn update(
@@ -267,13 +538,38 @@ impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
                         status = Status::Captured;
                     }
                     _ => {
-                        if let Some(hovered_node) = self.nodes.iter().find(|node| node.is_in_bounds(canvas_position)) {
+                        let cursor_area = Rectangle::new(canvas_position, Size::ZERO);
+                        let index = self.index();
+                        if let Some(hovered_node) = index
+                            .nodes_in(cursor_area)
+                            .into_iter()
+                            .map(|slot| &self.nodes[slot])
+                            .find(|node| node.is_in_bounds(canvas_position))
+                        {
                             *interaction = GraphInteraction::HoverNode(hovered_node.id());
                             status = Status::Captured;
+                        } else if let Some(hovered_edge) = index
+                            .edges_in(cursor_area.expand(Self::HIT_MARGIN))
+                            .into_iter()
+                            .map(|position| &self.edges[position])
+                            .find(|edge| {
+                                self.edge_ends(edge)
+                                    .is_some_and(|(start, end)| edge.is_in_bounds(canvas_position, start, end))
+                            })
+                        {
+                            *interaction = GraphInteraction::HoverEdge(hovered_edge.start(), hovered_edge.end());
+                            status = Status::Captured;
                         } else {
                             *interaction = GraphInteraction::None;
                             status = Status::Ignored;
                         }
+                        let hovered_edge = match *interaction {
+                            GraphInteraction::HoverEdge(start, end) => Some((start, end)),
+                            _ => None,
+                        };
+                        if hovered_edge != self.hovered_edge {
+                            message = Some(GraphMessage::HoveredEdge(hovered_edge));
+                        }
                     }
                 },
                 mouse::Event::WheelScrolled { delta } => match delta {
@@ -299,18 +595,15 @@ impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
                         }
                     }
                 },
-                mouse::Event::ButtonReleased(button) => match button {
-                    mouse::Button::Left | mouse::Button::Right => {
-                        if matches!(
-                            interaction,
-                            GraphInteraction::DraggingNode(..) | GraphInteraction::Panning { .. }
-                        ) {
-                            *interaction = GraphInteraction::None;
-                            status = Status::Ignored;
-                        }
+                mouse::Event::ButtonReleased(mouse::Button::Left | mouse::Button::Right) => {
+                    if matches!(
+                        interaction,
+                        GraphInteraction::DraggingNode(..) | GraphInteraction::Panning { .. }
+                    ) {
+                        *interaction = GraphInteraction::None;
+                        status = Status::Ignored;
                     }
-                    _ => {}
-                },
+                }
                 mouse::Event::ButtonPressed(button) => match button {
                     mouse::Button::Right => {
                         *interaction = GraphInteraction::Panning {
@@ -348,15 +641,13 @@ impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
                 },
                 _ => {}
             },
-            canvas::Event::Keyboard(keyboard_event) => match keyboard_event {
-                keyboard::Event::KeyPressed { key, .. } => {
-                    if let Key::Named(keyboard::key::Named::Escape) = key {
-                        message = Some(GraphMessage::CancelDrawEdge);
-                        status = Status::Captured;
-                    }
-                }
-                _ => {}
-            },
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Named(keyboard::key::Named::Escape),
+                ..
+            }) => {
+                message = Some(GraphMessage::CancelDrawEdge);
+                status = Status::Captured;
+            }
             _ => {}
         }
         self.cache.clear();
@@ -372,47 +663,39 @@ impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
         cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
         let center = Vector::new(bounds.width / 2.0, bounds.height / 2.0);
+        // Only what lies on the visible part of the canvas is drawn, found through the index however large the graph is.
+        let visible = Rectangle::new(
+            self.window_to_canvas(Point::ORIGIN, bounds),
+            Size::new(bounds.width / self.scaling, bounds.height / self.scaling),
+        )
+        .expand(Self::DRAW_MARGIN);
         vec![self.cache.draw(renderer, bounds.size(), |frame| {
-            frame.with_save(|mut frame| {
+            frame.with_save(|frame| {
                 frame.translate(center);
                 frame.scale(self.scaling);
                 frame.translate(self.translation);
-                for node in self.nodes.iter() {
+                let index = self.index();
+                for node in index.nodes_in(visible).into_iter().map(|slot| &self.nodes[slot]) {
                     let hovered = if let GraphInteraction::HoverNode(id) = *interaction {
                         id == node.id()
                     } else {
                         false
                     };
-                    node.draw(&mut frame, hovered);
+                    node.draw(frame, hovered);
                 }
-                let stroke = Stroke::default().with_width(2.0).with_color(Color::WHITE);
-
-                for edge in self.edges() {
-                    let start_node = self.get_node_unsafe(Some(edge.start));
-                    let end_node = self.get_node_unsafe(Some(edge.end));
-                    let (start, end) = if start_node.anchor().y > end_node.anchor().y {
-                        (end_node, start_node)
-                    } else {
-                        (start_node, end_node)
+                for edge in index
+                    .edges_in(visible)
+                    .into_iter()
+                    .map(|position| &self.edges[position])
+                {
+                    let Some((start, end)) = self.edge_ends(edge) else {
+                        continue;
                     };
-
-                    let start_point = Point::new(
-                        start.anchor().x + start.size().width / 2.0,
-                        start.anchor().y + start.size().height,
-                    );
-                    let end_point = Point::new(end.anchor().x + end.size().width / 2.0, end.anchor().y);
-                    let second_point = Point::new(start_point.x, start_point.y + (end_point.y - start_point.y) / 2.0);
-                    let third_point = Point::new(end_point.x, second_point.y);
-
-                    let start_path = Path::line(start_point, second_point);
-                    let second_path = Path::line(second_point, third_point);
-                    let end_path = Path::line(third_point, end_point);
-
-                    frame.stroke(&start_path, stroke);
-                    frame.stroke(&second_path, stroke);
-                    frame.stroke(&end_path, stroke);
+                    let hovered = *interaction == GraphInteraction::HoverEdge(edge.start(), edge.end());
+                    edge.draw(frame, start, end, hovered);
                 }
 
+                let stroke = Stroke::default().with_width(2.0).with_color(Color::WHITE);
                 if let Some(node) = self.get_node(self.drawing_edge) {
                     let Some(cursor_position) = cursor.position_in(bounds) else {
                         return;
@@ -433,3 +716,185 @@ impl<T: GraphNodeTrait> canvas::Program<GraphMessage> for Graph<T> {
         })]
     }
 }
+
+/// Delete as a shortcut for removing the hovered edge or the selected node. It is left to a subscription because the
+/// canvas is handed every key press, also those a focused text input next to it already took.
+pub fn keyboard_subscription() -> Subscription<GraphMessage> {
+    event::listen_with(|event, status, _window| match (event, status) {
+        (
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Named(keyboard::key::Named::Delete),
+                ..
+            }),
+            Status::Ignored,
+        ) => Some(GraphMessage::DeletePressed),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::widget::canvas::Frame;
+
+    use super::*;
+
+    struct TestNode {
+        id: u128,
+        anchor: Point,
+    }
+
+    impl GraphNodeTrait for TestNode {
+        fn new(anchor: Point) -> Self {
+            Self { id: 0, anchor }
+        }
+        fn id(&self) -> u128 {
+            self.id
+        }
+        fn anchor(&self) -> Point {
+            self.anchor
+        }
+        fn set_anchor(&mut self, anchor: Point) {
+            self.anchor = anchor;
+        }
+        fn size(&self) -> Size {
+            Size::new(100.0, 50.0)
+        }
+        fn draw_content<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
+            frame
+        }
+    }
+
+    struct TestEdge {
+        start: u128,
+        end: u128,
+    }
+
+    impl GraphEdgeTrait for TestEdge {
+        fn new(start: u128, end: u128) -> Self {
+            Self { start, end }
+        }
+        fn start(&self) -> u128 {
+            self.start
+        }
+        fn end(&self) -> u128 {
+            self.end
+        }
+        fn draw(&self, _frame: &mut Frame, _start: Rectangle, _end: Rectangle, _hovered: bool) {}
+        fn is_in_bounds(&self, _point: Point, _start: Rectangle, _end: Rectangle) -> bool {
+            false
+        }
+    }
+
+    /// Five nodes in a row, each linked to the next one and to the first one.
+    fn graph() -> Graph<TestNode, TestEdge> {
+        let mut graph = Graph::default();
+        for id in 1..=5 {
+            let anchor = Point::new(id as f32 * 200.0, 0.0);
+            assert!(graph.insert_node(TestNode { id, anchor }));
+        }
+        for id in 2..=5 {
+            graph.insert_edge(TestEdge::new(id - 1, id));
+        }
+        for id in 3..=5 {
+            graph.insert_edge(TestEdge::new(1, id));
+        }
+        graph
+    }
+
+    fn ends(edges: impl Iterator<Item = impl std::borrow::Borrow<TestEdge>>) -> Vec<(u128, u128)> {
+        let mut ends: Vec<(u128, u128)> = edges.map(|edge| (edge.borrow().start, edge.borrow().end)).collect();
+        ends.sort_unstable();
+        ends
+    }
+
+    /// Every node is found by its id, and the adjacency lists and the index agree with the nodes and edges.
+    fn assert_consistent(graph: &Graph<TestNode, TestEdge>) {
+        for node in graph.nodes() {
+            assert_eq!(graph.get_node(Some(node.id)).map(|found| found.id), Some(node.id));
+            let touching = graph.edges().iter().filter(|edge| edge.touches(node.id));
+            assert_eq!(ends(graph.edges_of(node.id)), ends(touching));
+        }
+        assert_eq!(graph.slots.len(), graph.nodes().len());
+        let everywhere = Rectangle::new(Point::new(-1e6, -1e6), Size::new(2e6, 2e6));
+        assert_eq!(
+            graph.index().nodes_in(everywhere),
+            (0..graph.nodes().len()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            graph.index().edges_in(everywhere),
+            (0..graph.edges().len()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn removes_first_middle_and_last_node() {
+        for removed in [1, 3, 5] {
+            let mut graph = graph();
+            assert_consistent(&graph);
+            assert_eq!(graph.remove_node(removed).map(|node| node.id), Some(removed));
+            assert!(graph.get_node(Some(removed)).is_none());
+            assert_eq!(graph.edges_of(removed).count(), 0);
+            assert!(graph.edges().iter().all(|edge| !edge.touches(removed)));
+            assert_eq!(graph.nodes().len(), 4);
+            assert_consistent(&graph);
+            assert!(graph.remove_node(removed).is_none());
+        }
+    }
+
+    #[test]
+    fn removes_every_node_in_turn() {
+        let mut graph = graph();
+        for removed in [3, 1, 5, 2, 4] {
+            graph.remove_node(removed);
+            assert_consistent(&graph);
+        }
+        assert!(graph.nodes().is_empty());
+        assert!(graph.edges().is_empty());
+        assert!(graph.adjacency.is_empty());
+    }
+
+    #[test]
+    fn removes_edges_either_way_round() {
+        let mut graph = graph();
+        assert!(graph.remove_edge(2, 1));
+        assert!(!graph.remove_edge(1, 2));
+        assert_consistent(&graph);
+        assert!(graph.remove_edge(1, 5));
+        assert_consistent(&graph);
+        assert_eq!(ends(graph.edges_of(1)), vec![(1, 3), (1, 4)]);
+    }
+
+    #[test]
+    fn reindexes_retained_edges() {
+        let mut graph = graph();
+        graph.retain_edges(|edge| edge.start != 1);
+        assert_consistent(&graph);
+        assert_eq!(ends(graph.edges_of(1)), vec![]);
+        assert_eq!(ends(graph.edges_of(3)), vec![(2, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn refuses_duplicate_nodes_and_self_edges() {
+        let mut graph = graph();
+        assert!(!graph.insert_node(TestNode {
+            id: 3,
+            anchor: Point::ORIGIN,
+        }));
+        assert!(!graph.insert_edge(TestEdge::new(2, 2)));
+        assert_eq!(graph.nodes().len(), 5);
+        assert_eq!(graph.edges().len(), 7);
+        assert_consistent(&graph);
+    }
+
+    #[test]
+    fn moves_dragged_node_in_index() {
+        let mut graph = graph();
+        assert_consistent(&graph);
+        graph.update(GraphMessage::DraggingNode(3, Point::new(0.0, 2048.0)));
+        assert_consistent(&graph);
+        let around = |x: f32, y: f32| Rectangle::new(Point::new(x, y), Size::new(10.0, 10.0));
+        assert_eq!(graph.index().nodes_in(around(0.0, 2048.0)), vec![2]);
+        assert_eq!(graph.index().nodes_in(around(600.0, 0.0)), vec![]);
+        assert_eq!(graph.index().edges_in(around(150.0, 1000.0)), vec![1, 2, 4]);
+    }
+}
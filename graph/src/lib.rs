@@ -1,7 +1,10 @@
+pub mod edge;
 pub mod graph;
 pub mod node;
+mod spatial;
 
-pub use graph::Edge;
 pub use graph::Graph;
 pub use graph::GraphInteraction;
 pub use graph::GraphMessage;
+pub use graph::Removal;
+pub use graph::keyboard_subscription;
@@ -1,4 +1,4 @@
-use iced::{widget::canvas::Frame, Color, Point, Size};
+use iced::{Color, Point, Rectangle, Size, widget::canvas::Frame};
 
 pub trait GraphNodeTrait {
     fn new(anchor: Point) -> Self;
@@ -8,17 +8,26 @@ pub trait GraphNodeTrait {
     fn size(&self) -> Size;
     fn draw_content<'a>(&self, frame: &'a mut Frame) -> &'a Frame;
 
+    /// The background of the node.
+    fn color(&self) -> Color {
+        Color::WHITE
+    }
+
     fn draw<'a>(&self, frame: &'a mut Frame, hovered: bool) -> Vec<&'a Frame> {
         let color = if hovered {
-            Color { a: 0.5, ..Color::WHITE }
+            Color { a: 0.5, ..self.color() }
         } else {
-            Color::WHITE
+            self.color()
         };
         frame.fill_rectangle(self.anchor(), self.size(), color);
         self.draw_content(frame);
         vec![frame]
     }
 
+    fn bounds(&self) -> Rectangle {
+        Rectangle::new(self.anchor(), self.size())
+    }
+
     fn is_in_bounds(&self, point: Point) -> bool {
         let anchor = self.anchor();
         let size = self.size();
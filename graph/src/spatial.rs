@@ -0,0 +1,73 @@
+use iced::{Point, Rectangle};
+use rstar::{
+    AABB, RTree,
+    primitives::{self, GeomWithData},
+};
+
+/// The bounds of a node or edge on the canvas, together with its position in the graph's nodes or edges.
+type Entry = GeomWithData<primitives::Rectangle<[f32; 2]>, usize>;
+
+/// Where the nodes and edges of a graph lie on the canvas, so that drawing and hit-testing only look at those near
+/// the visible part or the cursor rather than at all of them.
+pub(crate) struct SpatialIndex {
+    nodes: RTree<Entry>,
+    edges: RTree<Entry>,
+}
+
+impl SpatialIndex {
+    /// Indexes the bounds of every node and of every edge by their position, edges without bounds are left out.
+    pub(crate) fn new(nodes: impl Iterator<Item = Rectangle>, edges: impl Iterator<Item = Option<Rectangle>>) -> Self {
+        let nodes = nodes
+            .enumerate()
+            .map(|(position, bounds)| entry(bounds, position))
+            .collect();
+        let edges = edges
+            .enumerate()
+            .filter_map(|(position, bounds)| Some(entry(bounds?, position)))
+            .collect();
+        Self {
+            nodes: RTree::bulk_load(nodes),
+            edges: RTree::bulk_load(edges),
+        }
+    }
+
+    /// The positions of the nodes whose bounds touch the area, in the order they are in the graph.
+    pub(crate) fn nodes_in(&self, area: Rectangle) -> Vec<usize> {
+        positions(&self.nodes, area)
+    }
+
+    /// The positions of the edges whose bounds touch the area, in the order they are in the graph.
+    pub(crate) fn edges_in(&self, area: Rectangle) -> Vec<usize> {
+        positions(&self.edges, area)
+    }
+
+    pub(crate) fn move_node(&mut self, position: usize, from: Rectangle, to: Rectangle) {
+        self.nodes.remove(&entry(from, position));
+        self.nodes.insert(entry(to, position));
+    }
+
+    pub(crate) fn move_edge(&mut self, position: usize, from: Option<Rectangle>, to: Option<Rectangle>) {
+        if let Some(from) = from {
+            self.edges.remove(&entry(from, position));
+        }
+        if let Some(to) = to {
+            self.edges.insert(entry(to, position));
+        }
+    }
+}
+
+fn entry(bounds: Rectangle, position: usize) -> Entry {
+    let Point { x, y } = bounds.position();
+    let corner = [x + bounds.width, y + bounds.height];
+    GeomWithData::new(primitives::Rectangle::from_corners([x, y], corner), position)
+}
+
+fn positions(tree: &RTree<Entry>, area: Rectangle) -> Vec<usize> {
+    let envelope = AABB::from_corners([area.x, area.y], [area.x + area.width, area.y + area.height]);
+    let mut positions: Vec<usize> = tree
+        .locate_in_envelope_intersecting(&envelope)
+        .map(|entry| entry.data)
+        .collect();
+    positions.sort_unstable();
+    positions
+}
@@ -13,7 +13,7 @@ use futures::executor::block_on;
 use futures::Stream;
 use futures::{channel::mpsc::Sender, sink::SinkExt};
 use iced::stream;
-use log::error;
+use log::{debug, error};
 use objc::{
     declare::ClassDecl,
     runtime::{Object, Sel},
@@ -22,7 +22,17 @@ use once_cell::sync::OnceCell;
 
 #[derive(Debug, Clone)]
 pub enum Event {
+    OpenProject,
+    SaveProject,
+    SaveProjectAs,
     OpenFile,
+    MergeFile,
+    ExportGedcom551,
+    ExportGedcom555,
+    ExportGedcom70,
+    ExportGedzip,
+    ExportGramps,
+    StandardizePlaces,
 }
 
 static EVENT_SENDER: OnceCell<Mutex<Sender<Event>>> = OnceCell::new();
@@ -30,13 +40,11 @@ static EVENT_SENDER: OnceCell<Mutex<Sender<Event>>> = OnceCell::new();
 pub fn setup_menu_bar() -> impl Stream<Item = Event> {
     stream::channel(10, |output| async move {
         EVENT_SENDER.set(Mutex::new(output)).unwrap();
-        #[allow(dead_code)]
-        extern "C" fn open_file_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
-            println!("Open file action triggered");
+        fn send_event(event: Event) {
             if let Some(mutex) = EVENT_SENDER.get() {
-                println!("Sending open file event");
+                debug!("Sending {:?} event", event);
                 if let Ok(mut sender) = mutex.lock() {
-                    match block_on(sender.send(Event::OpenFile)) {
+                    match block_on(sender.send(event)) {
                         Err(e) => {
                             error!("Failed to send event: {e}");
                         }
@@ -46,16 +54,122 @@ pub fn setup_menu_bar() -> impl Stream<Item = Event> {
             }
         }
 
+        #[allow(dead_code)]
+        extern "C" fn open_project_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Open project action triggered");
+            send_event(Event::OpenProject);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn save_project_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Save project action triggered");
+            send_event(Event::SaveProject);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn save_project_as_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Save project as action triggered");
+            send_event(Event::SaveProjectAs);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn open_file_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Open file action triggered");
+            send_event(Event::OpenFile);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn merge_file_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Merge file action triggered");
+            send_event(Event::MergeFile);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn export_gedcom_551_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Export GEDCOM 5.5.1 action triggered");
+            send_event(Event::ExportGedcom551);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn export_gedcom_555_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Export GEDCOM 5.5.5 action triggered");
+            send_event(Event::ExportGedcom555);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn export_gedcom_70_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Export GEDCOM 7.0 action triggered");
+            send_event(Event::ExportGedcom70);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn export_gedzip_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Export GEDZIP action triggered");
+            send_event(Event::ExportGedzip);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn export_gramps_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Export Gramps XML action triggered");
+            send_event(Event::ExportGramps);
+        }
+
+        #[allow(dead_code)]
+        extern "C" fn standardize_places_action(_this: &Object, _sel: Sel, _sender: *mut Object) {
+            debug!("Standardize places action triggered");
+            send_event(Event::StandardizePlaces);
+        }
+
         #[allow(dead_code)]
         fn register_menu_handler_class() -> *mut Object {
             unsafe {
                 let superclass = class!(NSObject);
                 let mut decl = ClassDecl::new("RustMenuHandler", superclass).unwrap();
 
+                decl.add_method(
+                    sel!(openProjectAction:),
+                    open_project_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(saveProjectAction:),
+                    save_project_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(saveProjectAsAction:),
+                    save_project_as_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
                 decl.add_method(
                     sel!(openFileAction:),
                     open_file_action as extern "C" fn(&Object, Sel, *mut Object),
                 );
+                decl.add_method(
+                    sel!(mergeFileAction:),
+                    merge_file_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(exportGedcom551Action:),
+                    export_gedcom_551_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(exportGedcom555Action:),
+                    export_gedcom_555_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(exportGedcom70Action:),
+                    export_gedcom_70_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(exportGedzipAction:),
+                    export_gedzip_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(exportGrampsAction:),
+                    export_gramps_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
+                decl.add_method(
+                    sel!(standardizePlacesAction:),
+                    standardize_places_action as extern "C" fn(&Object, Sel, *mut Object),
+                );
 
                 let cls = decl.register();
                 let instance: *mut Object = msg_send![cls, new];
@@ -89,6 +203,23 @@ pub fn setup_menu_bar() -> impl Stream<Item = Event> {
                 menu_bar.addItem_(file_menu_item);
 
                 let file_menu = NSMenu::new(nil).autorelease();
+                for (title, action, key) in [
+                    ("Open Project", sel!(openProjectAction:), "o"),
+                    ("Save Project", sel!(saveProjectAction:), "s"),
+                    ("Save Project As", sel!(saveProjectAsAction:), "S"),
+                ] {
+                    let item = NSMenuItem::alloc(nil)
+                        .initWithTitle_action_keyEquivalent_(
+                            NSString::alloc(nil).init_str(title),
+                            action,
+                            NSString::alloc(nil).init_str(key),
+                        )
+                        .autorelease();
+                    NSMenuItem::setTarget_(item, handler);
+                    file_menu.addItem_(item);
+                }
+                file_menu.addItem_(NSMenuItem::separatorItem(nil));
+
                 let import_title = NSString::alloc(nil).init_str("Import");
                 let import_action = sel!(openFileAction:);
                 let import_item = NSMenuItem::alloc(nil)
@@ -103,9 +234,57 @@ pub fn setup_menu_bar() -> impl Stream<Item = Event> {
 
                 file_menu.addItem_(import_item);
 
+                let merge_item = NSMenuItem::alloc(nil)
+                    .initWithTitle_action_keyEquivalent_(
+                        NSString::alloc(nil).init_str("Import into Current Tree"),
+                        sel!(mergeFileAction:),
+                        NSString::alloc(nil).init_str("I"),
+                    )
+                    .autorelease();
+                NSMenuItem::setTarget_(merge_item, handler);
+                file_menu.addItem_(merge_item);
+
+                let export_menu_item = NSMenuItem::new(nil).autorelease();
+                export_menu_item.setTitle_(NSString::alloc(nil).init_str("Export"));
+                let export_menu = NSMenu::new(nil).autorelease();
+                for (title, action, key) in [
+                    ("GEDCOM 5.5.1", sel!(exportGedcom551Action:), "e"),
+                    ("GEDCOM 5.5.5", sel!(exportGedcom555Action:), ""),
+                    ("GEDCOM 7.0", sel!(exportGedcom70Action:), ""),
+                    ("GEDZIP", sel!(exportGedzipAction:), ""),
+                    ("Gramps XML", sel!(exportGrampsAction:), ""),
+                ] {
+                    let item = NSMenuItem::alloc(nil)
+                        .initWithTitle_action_keyEquivalent_(
+                            NSString::alloc(nil).init_str(title),
+                            action,
+                            NSString::alloc(nil).init_str(key),
+                        )
+                        .autorelease();
+                    NSMenuItem::setTarget_(item, handler);
+                    export_menu.addItem_(item);
+                }
+                export_menu_item.setSubmenu_(export_menu);
+                file_menu.addItem_(export_menu_item);
+
                 let file_menu_title = NSString::alloc(nil).init_str("File");
                 file_menu_item.setTitle_(file_menu_title);
                 file_menu_item.setSubmenu_(file_menu);
+
+                let tools_menu_item = NSMenuItem::new(nil).autorelease();
+                menu_bar.addItem_(tools_menu_item);
+                let tools_menu = NSMenu::new(nil).autorelease();
+                let standardize_places_item = NSMenuItem::alloc(nil)
+                    .initWithTitle_action_keyEquivalent_(
+                        NSString::alloc(nil).init_str("Standardize Places"),
+                        sel!(standardizePlacesAction:),
+                        NSString::alloc(nil).init_str(""),
+                    )
+                    .autorelease();
+                NSMenuItem::setTarget_(standardize_places_item, handler);
+                tools_menu.addItem_(standardize_places_item);
+                tools_menu_item.setTitle_(NSString::alloc(nil).init_str("Tools"));
+                tools_menu_item.setSubmenu_(tools_menu);
             }
         }
 
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use graph::{node::GraphNodeTrait, Graph};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gedcom_io::{parser::Structure, GedcomVersion},
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode,
+};
+
+/// What an associated person was to the person or event, the `ROLE` of a 7.0 `ASSO` or the `RELA` of 5.5.x.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssociationRole {
+    Godparent,
+    #[default]
+    Witness,
+    Neighbour,
+    Friend,
+    Clergy,
+    /// Whoever performed the ceremony, e.g. a registrar.
+    Officiator,
+    /// Any other role, named by the user or by the imported file.
+    Other(String),
+}
+
+impl AssociationRole {
+    /// The roles offered in the side panel, `Other` stands for every custom one.
+    pub const ALL: [AssociationRole; 7] = [
+        AssociationRole::Godparent,
+        AssociationRole::Witness,
+        AssociationRole::Neighbour,
+        AssociationRole::Friend,
+        AssociationRole::Clergy,
+        AssociationRole::Officiator,
+        AssociationRole::Other(String::new()),
+    ];
+
+    /// The `ROLE` values of 7.0, 5.5.x writes the role as free text instead.
+    const GEDCOM_ROLES: [(&'static str, AssociationRole); 6] = [
+        ("GODP", AssociationRole::Godparent),
+        ("WITN", AssociationRole::Witness),
+        ("NGHBR", AssociationRole::Neighbour),
+        ("FRIEND", AssociationRole::Friend),
+        ("CLERGY", AssociationRole::Clergy),
+        ("OFFICIATOR", AssociationRole::Officiator),
+    ];
+
+    /// Reads the `ROLE` of 7.0, where a role it does not know is `OTHER` with a `PHRASE`, or the `RELA` of 5.5.x.
+    pub fn from_structure(structure: &Structure) -> Self {
+        if let Some(role) = structure.child("ROLE") {
+            let value = role.value.as_deref().unwrap_or_default().trim();
+            if let Some((_, known)) = Self::GEDCOM_ROLES
+                .iter()
+                .find(|(other, _)| other.eq_ignore_ascii_case(value))
+            {
+                return known.clone();
+            }
+            let name = match role.child_value("PHRASE") {
+                Some(phrase) => phrase,
+                None if value.eq_ignore_ascii_case("OTHER") => "",
+                None => value,
+            };
+            return AssociationRole::Other(name.to_string());
+        }
+        let name = structure.child_value("RELA").unwrap_or_default().trim();
+        match name.to_lowercase().as_str() {
+            "godparent" | "godfather" | "godmother" => AssociationRole::Godparent,
+            "witness" => AssociationRole::Witness,
+            "neighbour" | "neighbor" => AssociationRole::Neighbour,
+            "friend" => AssociationRole::Friend,
+            "clergy" => AssociationRole::Clergy,
+            "officiator" => AssociationRole::Officiator,
+            _ => AssociationRole::Other(name.to_string()),
+        }
+    }
+
+    /// The `ROLE` value and, for roles 7.0 does not know, the `PHRASE` naming it. 5.5.x gets the name as `RELA`.
+    pub fn gedcom_value(&self, version: GedcomVersion) -> (String, Option<String>) {
+        let known = Self::GEDCOM_ROLES
+            .iter()
+            .find(|(_, role)| role == self)
+            .map(|(value, _)| *value);
+        match (known, version) {
+            (Some(value), GedcomVersion::V70) => (value.to_string(), None),
+            (None, GedcomVersion::V70) if *self == AssociationRole::Other(String::new()) => ("OTHER".to_string(), None),
+            (None, GedcomVersion::V70) => ("OTHER".to_string(), Some(self.to_string())),
+            (_, GedcomVersion::V551 | GedcomVersion::V555) => (self.to_string(), None),
+        }
+    }
+}
+
+impl fmt::Display for AssociationRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssociationRole::Godparent => write!(f, "Godparent"),
+            AssociationRole::Witness => write!(f, "Witness"),
+            AssociationRole::Neighbour => write!(f, "Neighbour"),
+            AssociationRole::Friend => write!(f, "Friend"),
+            AssociationRole::Clergy => write!(f, "Clergy"),
+            AssociationRole::Officiator => write!(f, "Officiator"),
+            AssociationRole::Other(name) if !name.is_empty() => write!(f, "{}", name),
+            AssociationRole::Other(_) => write!(f, "Other"),
+        }
+    }
+}
+
+/// A link to a person outside the family, e.g. a godparent or a witness, held by the person or by one of its events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Association {
+    /// The id of the associated node.
+    pub person: u128,
+    pub role: AssociationRole,
+    /// The `ASSO` structure this association was imported from, so that its notes and citations survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Association {
+    pub fn new(person: u128) -> Self {
+        Self {
+            person,
+            role: AssociationRole::default(),
+            gedcom_record: None,
+        }
+    }
+}
+
+/// What an association is attached to.
+#[derive(Debug, Clone, Copy)]
+pub enum AssociationTarget {
+    Person(u128),
+    /// An event of a person, by its index.
+    Event((u128, usize)),
+}
+
+/// Draws every association of a person and its events as an edge between the two nodes, labelled with the role.
+pub fn draw_associations(graph: &mut Graph<GenealogicalNode, GenealogicalEdge>) {
+    let links: Vec<GenealogicalEdge> = graph
+        .nodes()
+        .iter()
+        .flat_map(|node| {
+            let person = node.associations().iter().map(|association| (association, None));
+            let events = node.events().iter().flat_map(|event| {
+                event
+                    .associations
+                    .iter()
+                    .map(move |association| (association, Some(&event.event_type)))
+            });
+            person.chain(events).map(move |(association, event_type)| {
+                let label = match event_type {
+                    Some(event_type) => format!("{} ({})", association.role, event_type),
+                    None => association.role.to_string(),
+                };
+                GenealogicalEdge::association(node.id(), association.person, label)
+            })
+        })
+        .collect();
+    graph.retain_edges(|edge| !edge.is_association());
+    for link in links {
+        graph.insert_edge(link);
+    }
+}
+
+/// Drops the associations with a removed person.
+pub fn remove_associations_with(graph: &mut Graph<GenealogicalNode, GenealogicalEdge>, person: u128) {
+    for node in graph.nodes_mut() {
+        node.associations_mut()
+            .retain(|association| association.person != person);
+        for index in 0..node.events().len() {
+            if let Some(event) = node.event_mut(index) {
+                event.associations.retain(|association| association.person != person);
+            }
+        }
+    }
+}
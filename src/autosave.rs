@@ -0,0 +1,123 @@
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use graph::Graph;
+use log::warn;
+
+use crate::{
+    family::Families, gedcom_io::preserved::PreservedRecords, genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode, media::Media, note::Notes, place::Places, project::Project, source::Sources,
+};
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+const RECOVERY_FILE_NAME: &str = "recovery.igproj";
+
+/// Periodically writes the open document to the recovery location while it has changes that are not saved.
+///
+/// The recovery file only exists while there is unsaved work, so finding one on launch means the previous session
+/// ended without saving.
+pub struct Autosave {
+    last_run: Instant,
+    /// The hash of the document as it was last opened or saved as a project.
+    saved: u64,
+    /// The hash of the document as it was last written to the recovery file.
+    recovered: Option<u64>,
+}
+
+impl Autosave {
+    pub fn new(
+        graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+        preserved: &PreservedRecords,
+        families: &Families,
+        places: &Places,
+        sources: &Sources,
+        media: &Media,
+        notes: &Notes,
+    ) -> Self {
+        Self {
+            last_run: Instant::now(),
+            saved: fingerprint(&Project::new(graph, preserved, families, places, sources, media, notes).to_json()),
+            recovered: None,
+        }
+    }
+
+    /// Called on every tick, does nothing until the interval has passed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick(
+        &mut self,
+        graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+        preserved: &PreservedRecords,
+        families: &Families,
+        places: &Places,
+        sources: &Sources,
+        media: &Media,
+        notes: &Notes,
+    ) {
+        if self.last_run.elapsed() < AUTOSAVE_INTERVAL {
+            return;
+        }
+        self.last_run = Instant::now();
+        let Some(path) = recovery_path() else {
+            return;
+        };
+
+        let json = Project::new(graph, preserved, families, places, sources, media, notes).to_json();
+        let hash = fingerprint(&json);
+        if hash == self.saved {
+            self.discard();
+        } else if self.recovered != Some(hash) {
+            if let Some(directory) = path.parent() {
+                let _ = fs::create_dir_all(directory);
+            }
+            match Project::write_json(&path, &json) {
+                Ok(()) => self.recovered = Some(hash),
+                Err(error) => warn!("Autosave failed: {}", error),
+            }
+        }
+    }
+
+    /// The document was saved or replaced by an opened project, so there is nothing left to recover.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mark_saved(
+        &mut self,
+        graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+        preserved: &PreservedRecords,
+        families: &Families,
+        places: &Places,
+        sources: &Sources,
+        media: &Media,
+        notes: &Notes,
+    ) {
+        self.saved = fingerprint(&Project::new(graph, preserved, families, places, sources, media, notes).to_json());
+        self.discard();
+    }
+
+    pub fn discard(&mut self) {
+        self.recovered = None;
+        if let Some(path) = recovery_path().filter(|path| path.exists()) {
+            if let Err(error) = fs::remove_file(&path) {
+                warn!("Could not remove {}: {}", path.display(), error);
+            }
+        }
+    }
+}
+
+/// Tells documents apart without keeping a copy of each around.
+fn fingerprint(json: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    json.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn recovery_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|directory| directory.join("iced-graph").join(RECOVERY_FILE_NAME))
+}
+
+/// When the session left behind by a previous run was last autosaved, if there is one.
+pub fn recovered_session() -> Option<SystemTime> {
+    fs::metadata(recovery_path()?).ok()?.modified().ok()
+}
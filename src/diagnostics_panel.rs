@@ -0,0 +1,53 @@
+use iced::{
+    widget::{button, column, container, row, scrollable, text, Column, Container},
+    Background, Border, Color,
+    Length::Fill,
+};
+
+use crate::{
+    gedcom_io::diagnostics::{Diagnostic, Severity},
+    Message,
+};
+
+const ERROR_COLOR: Color = Color::from_rgb(0.75, 0.1, 0.1);
+const WARNING_COLOR: Color = Color::from_rgb(0.7, 0.45, 0.0);
+
+pub fn diagnostics_panel<'a>(diagnostics: &'a [Diagnostic]) -> Container<'a, Message> {
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    let header = row![
+        text(format!("Finished with {} errors and {} warnings", errors, warnings))
+            .color(Color::BLACK)
+            .width(Fill),
+        button("Dismiss").on_press(Message::DismissDiagnostics),
+    ]
+    .spacing(10);
+
+    let entries: Column<'a, Message> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let (label, color) = match diagnostic.severity {
+                Severity::Error => ("Error", ERROR_COLOR),
+                Severity::Warning => ("Warning", WARNING_COLOR),
+            };
+            text(format!("{}: {}", label, diagnostic)).color(color).into()
+        })
+        .collect::<Column<'a, Message>>()
+        .spacing(5);
+
+    container(column![header, scrollable(entries).width(Fill).height(160)].spacing(10))
+        .width(Fill)
+        .padding(10)
+        .style(|_| container::Style {
+            background: Some(Background::Color(Color::WHITE)),
+            border: Border {
+                radius: 5.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+}
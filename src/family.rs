@@ -0,0 +1,433 @@
+use std::{collections::HashMap, fmt};
+
+use graph::{edge::GraphEdgeTrait, Graph};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    gedcom_io::{parser::Structure, GedcomVersion},
+    genealogical_edge::{family_edges_of, EdgeKind, GenealogicalEdge},
+    genealogical_node::GenealogicalNode,
+    relationship::{RelationshipEvent, RelationshipKind},
+};
+
+/// How a child belongs to a family, the `PEDI` of a GEDCOM `FAMC` link.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pedigree {
+    #[default]
+    Birth,
+    Adopted,
+    Foster,
+    /// The child of one partner only.
+    Step,
+    /// Sealed to the parents in a temple ordinance.
+    Sealing,
+    /// Any other relation, named by the user or by the `PEDI` of a GEDCOM `FAMC` link.
+    Other(String),
+}
+
+impl Pedigree {
+    /// The pedigrees offered in the side panel, `Other` stands for every custom one.
+    pub const ALL: [Pedigree; 6] = [
+        Pedigree::Birth,
+        Pedigree::Adopted,
+        Pedigree::Foster,
+        Pedigree::Step,
+        Pedigree::Sealing,
+        Pedigree::Other(String::new()),
+    ];
+
+    /// The values 5.5.x writes in lower case and 7.0 in upper case, the rest are user defined.
+    const GEDCOM_VALUES: [(&'static str, Pedigree); 4] = [
+        ("birth", Pedigree::Birth),
+        ("adopted", Pedigree::Adopted),
+        ("foster", Pedigree::Foster),
+        ("sealing", Pedigree::Sealing),
+    ];
+
+    /// Reads a `PEDI`, where 7.0 names a relation it does not know as `OTHER` with a `PHRASE`.
+    pub fn from_structure(structure: &Structure) -> Self {
+        let value = structure.value.as_deref().unwrap_or_default().trim();
+        let name = match structure.child_value("PHRASE") {
+            Some(phrase) if value.eq_ignore_ascii_case("OTHER") => phrase,
+            None if value.eq_ignore_ascii_case("OTHER") => "",
+            _ => value,
+        };
+        Self::GEDCOM_VALUES
+            .iter()
+            .find(|(value, _)| value.eq_ignore_ascii_case(name))
+            .map(|(_, pedigree)| pedigree.clone())
+            .unwrap_or_else(|| match name {
+                _ if name.eq_ignore_ascii_case("step") => Pedigree::Step,
+                _ => Pedigree::Other(name.to_string()),
+            })
+    }
+
+    /// The `PEDI` value and, for relations 7.0 does not know, the `PHRASE` naming it.
+    pub fn gedcom_value(&self, version: GedcomVersion) -> (String, Option<String>) {
+        let known = Self::GEDCOM_VALUES
+            .iter()
+            .find(|(_, pedigree)| pedigree == self)
+            .map(|(value, _)| *value);
+        // Neither version knows stepchildren, most programs read this user defined value.
+        let user_defined = match self {
+            Pedigree::Step => "step",
+            Pedigree::Other(name) => name,
+            _ => known.unwrap_or_default(),
+        };
+        match (known, version) {
+            (Some(value), GedcomVersion::V70) => (value.to_uppercase(), None),
+            (None, GedcomVersion::V70) if user_defined.is_empty() => ("OTHER".to_string(), None),
+            (None, GedcomVersion::V70) => ("OTHER".to_string(), Some(user_defined.to_string())),
+            (_, _) => (user_defined.to_string(), None),
+        }
+    }
+}
+
+impl fmt::Display for Pedigree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pedigree::Birth => write!(f, "Birth"),
+            Pedigree::Adopted => write!(f, "Adopted"),
+            Pedigree::Foster => write!(f, "Foster"),
+            Pedigree::Step => write!(f, "Stepchild"),
+            Pedigree::Sealing => write!(f, "Sealing"),
+            Pedigree::Other(name) if !name.is_empty() => write!(f, "{}", name),
+            Pedigree::Other(_) => write!(f, "Other"),
+        }
+    }
+}
+
+/// A child of a family and how it belongs to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildLink {
+    pub child: u128,
+    pub pedigree: Pedigree,
+}
+
+/// A couple or a single parent together with their children, a `FAM` record in GEDCOM.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Family {
+    id: u128,
+    pub xref: Option<String>,
+    pub partners: Vec<u128>,
+    pub children: Vec<ChildLink>,
+    #[serde(default)]
+    pub kind: RelationshipKind,
+    /// Engagements, marriages, divorces and the like, in the order they happened.
+    #[serde(default)]
+    pub events: Vec<RelationshipEvent>,
+    /// The record this family was imported from, so that its events, sources and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Family {
+    pub fn new(partners: Vec<u128>) -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            xref: None,
+            partners,
+            children: vec![],
+            kind: RelationshipKind::Unknown,
+            events: vec![],
+            gedcom_record: None,
+        }
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    pub fn pedigree(&self, child: u128) -> Option<&Pedigree> {
+        self.children
+            .iter()
+            .find(|link| link.child == child)
+            .map(|link| &link.pedigree)
+    }
+
+    /// Adds a child by birth, unless it already belongs to the family.
+    pub fn add_child(&mut self, child: u128) {
+        if self.pedigree(child).is_none() {
+            self.children.push(ChildLink {
+                child,
+                pedigree: Pedigree::Birth,
+            });
+        }
+    }
+
+    /// What the edge between the partners says about them, e.g. `m. 1850, div. 1872`.
+    pub fn label(&self) -> Option<String> {
+        if !self.events.is_empty() {
+            let events: Vec<String> = self.events.iter().map(|event| event.label(self.kind)).collect();
+            return Some(events.join(", "));
+        }
+        match self.kind {
+            RelationshipKind::Unknown => None,
+            kind => Some(kind.to_string()),
+        }
+    }
+
+    /// Whether too little is left of it to be a family, no partner or a single one without children.
+    fn is_empty(&self) -> bool {
+        self.partners.is_empty() || (self.partners.len() == 1 && self.children.is_empty())
+    }
+}
+
+/// Every family of the tree. The edges of the graph are how families are drawn, the families say what the edges mean.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Families {
+    families: Vec<Family>,
+}
+
+impl Families {
+    pub fn get(&self, id: u128) -> Option<&Family> {
+        self.families.iter().find(|family| family.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u128) -> Option<&mut Family> {
+        self.families.iter_mut().find(|family| family.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Family> {
+        self.families.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Family> {
+        self.families.iter_mut()
+    }
+
+    pub fn insert(&mut self, family: Family) -> u128 {
+        let id = family.id;
+        self.families.push(family);
+        id
+    }
+
+    pub fn xrefs(&self) -> impl Iterator<Item = &String> {
+        self.families.iter().filter_map(|family| family.xref.as_ref())
+    }
+
+    /// The family of exactly these partners.
+    pub fn find_partners(&self, partners: &[u128]) -> Option<u128> {
+        self.families
+            .iter()
+            .find(|family| same_members(&family.partners, partners))
+            .map(|family| family.id)
+    }
+
+    /// The couples a person is a partner in, each with the other partner.
+    pub fn partnerships_of(&self, person: u128) -> impl Iterator<Item = (u128, &Family)> {
+        self.families
+            .iter()
+            .filter(move |family| family.partners.len() == 2 && family.partners.contains(&person))
+            .filter_map(move |family| {
+                let partner = family.partners.iter().find(|partner| **partner != person)?;
+                Some((*partner, family))
+            })
+    }
+
+    /// The families a person is a child of.
+    pub fn parents_of(&self, child: u128) -> impl Iterator<Item = &Family> {
+        self.families
+            .iter()
+            .filter(move |family| family.pedigree(child).is_some())
+    }
+
+    /// Adds the family a newly drawn edge stands for, unless a family already accounts for it. Returns the family the
+    /// edge belongs to, `None` for an edge that stands for no family tie.
+    pub fn add_edge(&mut self, start: u128, end: u128, kind: &EdgeKind) -> Option<u128> {
+        match *kind {
+            EdgeKind::Partners(_) => Some(match self.find_partners(&[start, end]) {
+                Some(id) => id,
+                None => self.insert(Family::new(vec![start, end])),
+            }),
+            EdgeKind::Parent { parent, .. } => {
+                let child = if parent == start { end } else { start };
+                Some(self.link_child(parent, child))
+            }
+            EdgeKind::Unknown | EdgeKind::Association(_) => None,
+        }
+    }
+
+    /// Makes a child a child of the parent, in the family of the parent with a partner who already is a parent of the
+    /// child alone, or else in a family of the parent alone. Moving to the couple's family keeps the pedigree.
+    fn link_child(&mut self, parent: u128, child: u128) -> u128 {
+        if let Some(family) = self
+            .families
+            .iter()
+            .find(|family| family.partners.contains(&parent) && family.pedigree(child).is_some())
+        {
+            return family.id;
+        }
+        let couple = self
+            .families
+            .iter()
+            .filter(|family| family.partners.len() == 2 && family.partners.contains(&parent))
+            .find_map(|couple| {
+                let partner = couple.partners.iter().find(|partner| **partner != parent)?;
+                let single = self
+                    .families
+                    .iter()
+                    .position(|family| family.partners == [*partner] && family.pedigree(child).is_some())?;
+                Some((couple.id, single))
+            });
+        let Some((id, single)) = couple else {
+            let id = match self.find_partners(&[parent]) {
+                Some(id) => id,
+                None => self.insert(Family::new(vec![parent])),
+            };
+            if let Some(family) = self.get_mut(id) {
+                family.add_child(child);
+            }
+            return id;
+        };
+        let family = &mut self.families[single];
+        let index = family
+            .children
+            .iter()
+            .position(|link| link.child == child)
+            .unwrap_or_default();
+        let link = family.children.remove(index);
+        if family.is_empty() {
+            self.families.remove(single);
+        }
+        if let Some(family) = self.get_mut(id) {
+            family.children.push(link);
+        }
+        id
+    }
+
+    /// Tells every edge which family tie it stands for: partners are labelled with how they are related, and a parent
+    /// is told whether the child belongs to one of that parent's families by birth.
+    pub fn style_edges(&self, graph: &mut Graph<GenealogicalNode, GenealogicalEdge>) {
+        let by_partner = self.by_partner();
+        for edge in graph.edges_mut().filter(|edge| !edge.is_association()) {
+            if let Some(kind) = self.edge_kind(&by_partner, edge.start(), edge.end()) {
+                edge.kind = kind;
+            }
+        }
+        graph.redraw();
+    }
+
+    /// Tells the edges between the members of a family which tie they stand for, after only that family changed.
+    pub fn style_family_edges(&self, id: u128, graph: &mut Graph<GenealogicalNode, GenealogicalEdge>) {
+        let Some(family) = self.get(id) else {
+            return;
+        };
+        let by_partner = self.by_partner();
+        // Every family edge has a partner at one end, the other end is a partner or child of the same family.
+        for partner in family.partners.iter().copied() {
+            graph.update_edges_of(partner, |edge| {
+                let other = if edge.start() == partner {
+                    edge.end()
+                } else {
+                    edge.start()
+                };
+                if edge.is_association() || !(family.partners.contains(&other) || family.pedigree(other).is_some()) {
+                    return;
+                }
+                if let Some(kind) = self.edge_kind(&by_partner, edge.start(), edge.end()) {
+                    edge.kind = kind;
+                }
+            });
+        }
+    }
+
+    /// The positions of the families each person is a partner in, so that styling looks them up by the ends of an
+    /// edge instead of going through every family for every edge.
+    fn by_partner(&self) -> HashMap<u128, Vec<usize>> {
+        let mut by_partner: HashMap<u128, Vec<usize>> = HashMap::new();
+        for (index, family) in self.families.iter().enumerate() {
+            for partner in family.partners.iter() {
+                by_partner.entry(*partner).or_default().push(index);
+            }
+        }
+        by_partner
+    }
+
+    /// The tie an edge between two people stands for, `None` when no family accounts for it and it stays what it was
+    /// drawn as.
+    fn edge_kind(&self, by_partner: &HashMap<u128, Vec<usize>>, start: u128, end: u128) -> Option<EdgeKind> {
+        let families_of = |person: u128| {
+            by_partner
+                .get(&person)
+                .into_iter()
+                .flatten()
+                .map(|index| &self.families[*index])
+        };
+        if let Some(family) = families_of(start).find(|family| same_members(&family.partners, &[start, end])) {
+            return Some(EdgeKind::Partners(family.label()));
+        }
+        let parents: Vec<(u128, &Pedigree)> = families_of(start)
+            .filter_map(|family| family.pedigree(end).map(|pedigree| (start, pedigree)))
+            .chain(families_of(end).filter_map(|family| family.pedigree(start).map(|pedigree| (end, pedigree))))
+            .collect();
+        let (parent, _) = parents.first()?;
+        Some(EdgeKind::Parent {
+            parent: *parent,
+            by_birth: parents.iter().any(|(_, pedigree)| **pedigree == Pedigree::Birth),
+        })
+    }
+
+    /// Takes a removed person out of every family, dropping the families too little is left of.
+    pub fn remove_person(&mut self, person: u128) {
+        for family in self.families.iter_mut() {
+            family.partners.retain(|partner| *partner != person);
+            family.children.retain(|link| link.child != person);
+        }
+        self.families.retain(|family| !family.is_empty());
+    }
+
+    /// Undoes what the removed edge between two people stood for.
+    ///
+    /// A child whose edge to a parent is gone leaves that parent's family. When another partner of the family is still
+    /// linked to the child, the child moves to a family of that partner alone and keeps its pedigree. Partners without
+    /// children are no family any more once the edge between them is gone.
+    pub fn unlink(&mut self, first: u128, second: u128, graph: &Graph<GenealogicalNode, GenealogicalEdge>) {
+        let linked =
+            |a: u128, b: u128| family_edges_of(graph, a).any(|edge| same_members(&[edge.start(), edge.end()], &[a, b]));
+        let mut moved: Vec<(Vec<u128>, ChildLink)> = vec![];
+        for family in self.families.iter_mut() {
+            for (parent, child) in [(first, second), (second, first)] {
+                if !family.partners.contains(&parent) {
+                    continue;
+                }
+                let Some(index) = family.children.iter().position(|link| link.child == child) else {
+                    continue;
+                };
+                let link = family.children.remove(index);
+                let others: Vec<u128> = family
+                    .partners
+                    .iter()
+                    .copied()
+                    .filter(|partner| *partner != parent && linked(*partner, child))
+                    .collect();
+                if !others.is_empty() {
+                    moved.push((others, link));
+                }
+            }
+        }
+        for (partners, link) in moved {
+            let id = match self.find_partners(&partners) {
+                Some(id) => id,
+                None => self.insert(Family::new(partners)),
+            };
+            if let Some(family) = self.get_mut(id).filter(|family| family.pedigree(link.child).is_none()) {
+                family.children.push(link);
+            }
+        }
+        self.families.retain(|family| {
+            let childless_couple = family.children.is_empty() && same_members(&family.partners, &[first, second]);
+            !family.is_empty() && !childless_couple
+        });
+    }
+
+    /// Splits off the families, e.g. to merge them into another tree.
+    pub fn into_families(self) -> Vec<Family> {
+        self.families
+    }
+}
+
+/// Whether both name the same people, in any order, e.g. the partners of a relationship.
+pub fn same_members(a: &[u128], b: &[u128]) -> bool {
+    a.len() == b.len() && a.iter().all(|id| b.contains(id))
+}
@@ -0,0 +1,719 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use super::GedcomVersion;
+
+const GREGORIAN_MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+/// Tishri to Elul, `ADS` (Adar Sheni) only exists in leap years.
+const HEBREW_MONTHS: [&str; 13] = [
+    "TSH", "CSH", "KSL", "TVT", "SHV", "ADR", "ADS", "NSN", "IYR", "SVN", "TMZ", "AAV", "ELL",
+];
+/// Vendémiaire to Fructidor, followed by the five or six complementary days.
+const FRENCH_MONTHS: [&str; 13] = [
+    "VEND", "BRUM", "FRIM", "NIVO", "PLUV", "VENT", "GERM", "FLOR", "PRAI", "MESS", "THER", "FRUC", "COMP",
+];
+/// Full English month names accepted when typing a date, e.g. `12 March 1850`.
+const MONTH_NAMES: [&str; 12] = [
+    "JANUARY",
+    "FEBRUARY",
+    "MARCH",
+    "APRIL",
+    "MAY",
+    "JUNE",
+    "JULY",
+    "AUGUST",
+    "SEPTEMBER",
+    "OCTOBER",
+    "NOVEMBER",
+    "DECEMBER",
+];
+const BCE_MARKERS: [&str; 3] = ["B.C.", "BC", "BCE"];
+
+/// Julian day number of 1 Vendémiaire of year I, 22 September 1792.
+const FRENCH_EPOCH: i64 = 2375840;
+/// Julian day number the Hebrew calendar counts its elapsed days from.
+const HEBREW_EPOCH: i64 = 347997;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calendar {
+    Gregorian,
+    Julian,
+    Hebrew,
+    FrenchRepublican,
+}
+
+impl Calendar {
+    const ALL: [Calendar; 4] = [
+        Calendar::Gregorian,
+        Calendar::Julian,
+        Calendar::Hebrew,
+        Calendar::FrenchRepublican,
+    ];
+
+    /// The 5.5.x escape that introduces a date in this calendar.
+    fn escape(&self) -> &'static str {
+        match self {
+            Calendar::Gregorian => "@#DGREGORIAN@",
+            Calendar::Julian => "@#DJULIAN@",
+            Calendar::Hebrew => "@#DHEBREW@",
+            Calendar::FrenchRepublican => "@#DFRENCH R@",
+        }
+    }
+
+    /// The 7.0 keyword that replaced the escape.
+    fn keyword(&self) -> &'static str {
+        match self {
+            Calendar::Gregorian => "GREGORIAN",
+            Calendar::Julian => "JULIAN",
+            Calendar::Hebrew => "HEBREW",
+            Calendar::FrenchRepublican => "FRENCH_R",
+        }
+    }
+
+    fn from_token(token: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|calendar| calendar.keyword() == token || calendar.escape() == token)
+    }
+
+    fn months(&self) -> &'static [&'static str] {
+        match self {
+            Calendar::Gregorian | Calendar::Julian => &GREGORIAN_MONTHS,
+            Calendar::Hebrew => &HEBREW_MONTHS,
+            Calendar::FrenchRepublican => &FRENCH_MONTHS,
+        }
+    }
+
+    /// Hebrew months keep their numbers in common years, Adar Sheni is just skipped.
+    fn month_count(&self) -> u8 {
+        match self {
+            Calendar::Gregorian | Calendar::Julian => 12,
+            Calendar::Hebrew | Calendar::FrenchRepublican => 13,
+        }
+    }
+
+    fn days_in_month(&self, year: i32, month: u8) -> u8 {
+        match self {
+            Calendar::Gregorian | Calendar::Julian => match month {
+                2 if self.leap_year(year) => 29,
+                2 => 28,
+                4 | 6 | 9 | 11 => 30,
+                _ => 31,
+            },
+            Calendar::Hebrew => {
+                let year_length = hebrew_new_year(year as i64 + 1) - hebrew_new_year(year as i64);
+                match month {
+                    // Heshvan is long and Kislev short in complete and deficient years respectively.
+                    2 if year_length % 10 == 5 => 30,
+                    3 if year_length % 10 == 3 => 29,
+                    6 if hebrew_leap_year(year as i64) => 30,
+                    1 | 3 | 5 | 8 | 10 | 12 => 30,
+                    _ => 29,
+                }
+            }
+            Calendar::FrenchRepublican => match month {
+                13 if self.leap_year(year) => 6,
+                13 => 5,
+                _ => 30,
+            },
+        }
+    }
+
+    fn leap_year(&self, year: i32) -> bool {
+        match self {
+            Calendar::Gregorian => year % 4 == 0 && (year % 100 != 0 || year % 400 == 0),
+            Calendar::Julian => year.rem_euclid(4) == 0,
+            Calendar::Hebrew => hebrew_leap_year(year as i64),
+            // Years III, VII and XI were sextile while the calendar was in use, later years follow the Romme rule.
+            Calendar::FrenchRepublican if year <= 14 => [3, 7, 11].contains(&year),
+            Calendar::FrenchRepublican => year % 4 == 0 && (year % 100 != 0 || year % 400 == 0),
+        }
+    }
+
+    /// Julian day number of a day of this calendar, counting Hebrew months from Tishri.
+    fn day_number(&self, year: i32, month: u8, day: u8) -> i64 {
+        let (year, month, day) = (year as i64, month as i64, day as i64);
+        match self {
+            Calendar::Gregorian | Calendar::Julian => {
+                let shift = (14 - month) / 12;
+                let year = year + 4800 - shift;
+                let month = month + 12 * shift - 3;
+                let days = day + (153 * month + 2) / 5 + 365 * year + year.div_euclid(4);
+                match self {
+                    Calendar::Gregorian => days - year.div_euclid(100) + year.div_euclid(400) - 32045,
+                    _ => days - 32083,
+                }
+            }
+            Calendar::Hebrew => {
+                let preceding: i64 = (1..month as u8)
+                    .filter(|month| *month != 7 || hebrew_leap_year(year))
+                    .map(|month| self.days_in_month(year as i32, month) as i64)
+                    .sum();
+                hebrew_new_year(year) + preceding + day - 1
+            }
+            Calendar::FrenchRepublican => {
+                // Counted rather than summed year by year, an imported year can be as large as an i32 allows.
+                let romme = |year: i64| year / 4 - year / 100 + year / 400;
+                let elapsed = (year - 1).max(0);
+                let sextiles = [3, 7, 11].into_iter().filter(|sextile| *sextile < year).count() as i64
+                    + (romme(elapsed) - romme(14)).max(0);
+                FRENCH_EPOCH + 365 * elapsed + sextiles + 30 * (month - 1) + day - 1
+            }
+        }
+    }
+}
+
+fn hebrew_leap_year(year: i64) -> bool {
+    (7 * year + 1).rem_euclid(19) < 7
+}
+
+/// Julian day number of 1 Tishri, from the molad of Tishri and the postponement rules.
+fn hebrew_new_year(year: i64) -> i64 {
+    let months_elapsed = 235 * ((year - 1) / 19) + 12 * ((year - 1) % 19) + (7 * ((year - 1) % 19) + 1) / 19;
+    let parts_elapsed = 204 + 793 * (months_elapsed % 1080);
+    let hours_elapsed = 5 + 12 * months_elapsed + 793 * (months_elapsed / 1080) + parts_elapsed / 1080;
+    let conjunction_day = 1 + 29 * months_elapsed + hours_elapsed / 24;
+    let conjunction_parts = 1080 * (hours_elapsed % 24) + parts_elapsed % 1080;
+    let mut day = conjunction_day;
+    if conjunction_parts >= 19440
+        || (conjunction_day % 7 == 2 && conjunction_parts >= 9924 && !hebrew_leap_year(year))
+        || (conjunction_day % 7 == 1 && conjunction_parts >= 16789 && hebrew_leap_year(year - 1))
+    {
+        day += 1;
+    }
+    if [0, 3, 5].contains(&(day % 7)) {
+        day += 1;
+    }
+    HEBREW_EPOCH + day
+}
+
+/// The Gregorian year a Julian day number falls in.
+fn gregorian_year(day_number: i64) -> i32 {
+    let a = day_number + 32044;
+    let b = (4 * a + 3) / 146097;
+    let c = a - 146097 * b / 4;
+    let d = (4 * c + 3) / 1461;
+    let e = c - 1461 * d / 4;
+    let m = (5 * e + 2) / 153;
+    (100 * b + d - 4800 + m / 10) as i32
+}
+
+/// A single, possibly partial, date in one of the GEDCOM calendars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarDate {
+    pub calendar: Calendar,
+    pub day: Option<u8>,
+    /// Counted from one in the order of the calendar's months.
+    pub month: Option<u8>,
+    /// The year as written, `bce` tells which era it is in.
+    pub year: i32,
+    /// The new-style year of a dual year such as `1699/00`, for dates between January and March before 1752.
+    pub dual_year: Option<i32>,
+    pub bce: bool,
+}
+
+impl CalendarDate {
+    /// A date of the common era, `None` when the month or day does not exist in the calendar.
+    pub fn new(calendar: Calendar, year: i32, month: Option<u8>, day: Option<u8>) -> Option<Self> {
+        let valid_month = month.is_none_or(|month| (1..=calendar.month_count()).contains(&month))
+            && (calendar != Calendar::Hebrew || month != Some(7) || hebrew_leap_year(year as i64));
+        let valid_day = match (month, day) {
+            (_, None) => true,
+            (Some(month), Some(day)) => (1..=calendar.days_in_month(year, month)).contains(&day),
+            (None, Some(_)) => false,
+        };
+        (year > 0 && valid_month && valid_day).then_some(Self {
+            calendar,
+            day,
+            month,
+            year,
+            dual_year: None,
+            bce: false,
+        })
+    }
+
+    fn parse(tokens: &[&str]) -> Result<Self, String> {
+        let (calendar, tokens) = tokens
+            .split_first()
+            .and_then(|(token, rest)| Some((Calendar::from_token(token)?, rest)))
+            .unwrap_or((Calendar::Gregorian, tokens));
+        let (bce, tokens) = match tokens.split_last() {
+            Some((token, rest)) if BCE_MARKERS.contains(token) => (true, rest),
+            _ => (false, tokens),
+        };
+        let (day, month, year) = match *tokens {
+            [year] => (None, None, year),
+            [month, year] => (None, Some(month), year),
+            [day, month, year] => (Some(day), Some(month), year),
+            _ => return Err(format!("{} is not a date", tokens.join(" "))),
+        };
+
+        let (year, dual_year) = match year.split_once('/') {
+            Some((year, dual))
+                if matches!(calendar, Calendar::Gregorian | Calendar::Julian) && !bce && dual.len() == 2 =>
+            {
+                (year, Some(dual))
+            }
+            Some(_) => return Err(format!("{} is not a valid dual year", year)),
+            None => (year, None),
+        };
+        let year: i32 = year
+            .parse()
+            .ok()
+            .filter(|year| *year > 0)
+            .ok_or_else(|| format!("{} is not a year", year))?;
+        let dual_year = match dual_year {
+            Some(dual) => {
+                let dual: i32 = dual.parse().map_err(|_| format!("{} is not a dual year", dual))?;
+                let dual_year = (year - year % 100)
+                    .checked_add(if dual > year % 100 { dual } else { dual + 100 })
+                    .ok_or_else(|| format!("{} is not a dual year", dual))?;
+                Some(dual_year)
+            }
+            None => None,
+        };
+        let mut date = Self {
+            calendar,
+            day: None,
+            month: None,
+            year,
+            dual_year,
+            bce,
+        };
+
+        if let Some(month) = month {
+            let number = calendar
+                .months()
+                .iter()
+                .position(|name| *name == month)
+                .map(|index| index as u8 + 1)
+                .ok_or_else(|| format!("{} is not a month of the {:?} calendar", month, calendar))?;
+            if calendar == Calendar::Hebrew && number == 7 && !hebrew_leap_year(date.reckoning_year() as i64) {
+                return Err(format!("{} is not a leap year, it has no ADS", year));
+            }
+            date.month = Some(number);
+        }
+        if let Some(day) = day {
+            let month = date.month.unwrap_or_default();
+            date.day = Some(
+                day.parse()
+                    .ok()
+                    .filter(|day| (1..=calendar.days_in_month(date.reckoning_year(), month)).contains(day))
+                    .ok_or_else(|| format!("{} is not a day of {}", day, calendar.months()[month as usize - 1]))?,
+            );
+        }
+        Ok(date)
+    }
+
+    /// The year the calendar arithmetic works with: astronomical numbering and the new style of a dual year.
+    fn reckoning_year(&self) -> i32 {
+        if self.bce {
+            1 - self.year
+        } else {
+            self.dual_year.unwrap_or(self.year)
+        }
+    }
+
+    /// The first and the last day the date can stand for, as Julian day numbers.
+    fn day_range(&self) -> (i64, i64) {
+        let year = self.reckoning_year();
+        let calendar = self.calendar;
+        match (self.month, self.day) {
+            (Some(month), Some(day)) => {
+                let day = calendar.day_number(year, month, day);
+                (day, day)
+            }
+            (Some(month), None) => {
+                let first = calendar.day_number(year, month, 1);
+                (first, first + calendar.days_in_month(year, month) as i64 - 1)
+            }
+            _ => {
+                let last_month = calendar.month_count();
+                let last = calendar.day_number(year, last_month, calendar.days_in_month(year, last_month));
+                (calendar.day_number(year, 1, 1), last)
+            }
+        }
+    }
+
+    /// The Gregorian year of the date, converting from the Hebrew and French calendars. Years before the common era
+    /// are negative.
+    pub fn gregorian_year(&self) -> i32 {
+        match self.calendar {
+            Calendar::Gregorian | Calendar::Julian if self.bce => -self.year,
+            Calendar::Gregorian | Calendar::Julian => self.reckoning_year(),
+            Calendar::Hebrew | Calendar::FrenchRepublican => gregorian_year(self.day_range().0),
+        }
+    }
+
+    pub fn to_gedcom(&self, version: GedcomVersion) -> String {
+        let mut parts = vec![];
+        if self.calendar != Calendar::Gregorian {
+            parts.push(match version {
+                GedcomVersion::V70 => self.calendar.keyword(),
+                GedcomVersion::V551 | GedcomVersion::V555 => self.calendar.escape(),
+            });
+        }
+        let day = self.day.map(|day| day.to_string());
+        parts.extend(day.as_deref());
+        parts.extend(self.month.map(|month| self.calendar.months()[month as usize - 1]));
+        // 7.0 has no dual years, it is left with the old-style year and the full form goes into a phrase.
+        let year = match self.dual_year {
+            Some(dual_year) if version != GedcomVersion::V70 => format!("{}/{:02}", self.year, dual_year % 100),
+            _ => self.year.to_string(),
+        };
+        parts.push(&year);
+        if self.bce {
+            parts.push(match version {
+                GedcomVersion::V70 => "BCE",
+                GedcomVersion::V551 | GedcomVersion::V555 => "B.C.",
+            });
+        }
+        parts.join(" ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approximation {
+    About,
+    Calculated,
+    Estimated,
+}
+
+impl Approximation {
+    fn keyword(&self) -> &'static str {
+        match self {
+            Approximation::About => "ABT",
+            Approximation::Calculated => "CAL",
+            Approximation::Estimated => "EST",
+        }
+    }
+}
+
+/// A GEDCOM date value: a date, an approximation, a range, a period or free text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GedcomDate {
+    Date(CalendarDate),
+    Approximate(Approximation, CalendarDate),
+    Before(CalendarDate),
+    After(CalendarDate),
+    /// `BET ... AND ...`, sometime within the range.
+    Between(CalendarDate, CalendarDate),
+    /// `FROM ... TO ...`, lasting over the period. Either end may be left open.
+    Period(Option<CalendarDate>, Option<CalendarDate>),
+    /// `INT ... (phrase)`, a date read out of the phrase as it was recorded.
+    Interpreted(CalendarDate, String),
+    /// `(phrase)`, a date that could not be put in any of the other forms.
+    Phrase(String),
+}
+
+impl GedcomDate {
+    /// Reads what a user typed, accepting `12.3.1850`, `1850-03-12` and month names besides the GEDCOM forms.
+    pub fn parse_input(input: &str) -> Result<Self, String> {
+        if let Ok(date) = input.parse() {
+            return Ok(date);
+        }
+        let (words, phrase) = match input.find('(') {
+            Some(start) => input.split_at(start),
+            None => (input, ""),
+        };
+        let mut gedcom = vec![];
+        for word in words.split_whitespace() {
+            let word = word.trim_end_matches(',').to_uppercase();
+            let numbers: Vec<&str> = word.split(['.', '/', '-']).filter(|part| !part.is_empty()).collect();
+            let numeric = numbers.len() > 1 && numbers.iter().all(|part| part.chars().all(|c| c.is_ascii_digit()));
+            if !numeric {
+                let month = MONTH_NAMES
+                    .iter()
+                    .position(|name| word.len() >= 3 && name.starts_with(&word))
+                    .map(|index| GREGORIAN_MONTHS[index].to_string());
+                gedcom.push(month.unwrap_or(word));
+                continue;
+            }
+            let number = |part: &str| part.parse::<usize>().ok();
+            let (day, month, year) = match numbers[..] {
+                // ISO order when the year comes first, otherwise day before month.
+                [year, month, day] if year.len() == 4 => (number(day), number(month), year),
+                [day, month, year] => (number(day), number(month), year),
+                [year, month] if year.len() == 4 => (None, number(month), year),
+                [month, year] => (None, number(month), year),
+                _ => return Err(format!("{} is not a date", word)),
+            };
+            let month = month
+                .filter(|month| (1..=12).contains(month))
+                .ok_or_else(|| format!("{} has no valid month", word))?;
+            if let Some(day) = day {
+                gedcom.push(day.to_string());
+            }
+            gedcom.push(GREGORIAN_MONTHS[month - 1].to_string());
+            gedcom.push(year.to_string());
+        }
+        // The English `March 12, 1850` puts the day after the month.
+        for index in 1..gedcom.len().saturating_sub(1) {
+            let is_month = GREGORIAN_MONTHS.contains(&gedcom[index - 1].as_str());
+            if is_month && gedcom[index].len() <= 2 && gedcom[index].chars().all(|c| c.is_ascii_digit()) {
+                gedcom.swap(index - 1, index);
+            }
+        }
+        format!("{} {}", gedcom.join(" "), phrase).parse()
+    }
+
+    /// The date the value is anchored on, the start of a range or period.
+    fn first_date(&self) -> Option<&CalendarDate> {
+        match self {
+            GedcomDate::Date(date)
+            | GedcomDate::Approximate(_, date)
+            | GedcomDate::Before(date)
+            | GedcomDate::After(date)
+            | GedcomDate::Between(date, _)
+            | GedcomDate::Interpreted(date, _) => Some(date),
+            GedcomDate::Period(from, to) => from.as_ref().or(to.as_ref()),
+            GedcomDate::Phrase(_) => None,
+        }
+    }
+
+    /// The Gregorian year of the value, for showing life years and matching people.
+    pub fn year(&self) -> Option<i32> {
+        self.first_date().map(CalendarDate::gregorian_year)
+    }
+
+    pub fn has_dual_year(&self) -> bool {
+        let dates = match self {
+            GedcomDate::Between(from, to) => [Some(from), Some(to)],
+            GedcomDate::Period(from, to) => [from.as_ref(), to.as_ref()],
+            _ => [self.first_date(), None],
+        };
+        dates.into_iter().flatten().any(|date| date.dual_year.is_some())
+    }
+
+    pub fn phrase(&self) -> Option<&str> {
+        match self {
+            GedcomDate::Interpreted(_, phrase) | GedcomDate::Phrase(phrase) => Some(phrase),
+            _ => None,
+        }
+    }
+
+    /// The earliest and latest day the value allows, as Julian day numbers, open ended where it says so.
+    pub fn day_range(&self) -> (Option<i64>, Option<i64>) {
+        let range = |date: &CalendarDate| date.day_range();
+        match self {
+            GedcomDate::Date(date) | GedcomDate::Approximate(_, date) | GedcomDate::Interpreted(date, _) => {
+                let (first, last) = range(date);
+                (Some(first), Some(last))
+            }
+            GedcomDate::Before(date) => (None, Some(range(date).0 - 1)),
+            GedcomDate::After(date) => (Some(range(date).1 + 1), None),
+            GedcomDate::Between(from, to) => (Some(range(from).0), Some(range(to).1)),
+            GedcomDate::Period(from, to) => (
+                from.as_ref().map(|from| range(from).0),
+                to.as_ref().map(|to| range(to).1),
+            ),
+            GedcomDate::Phrase(_) => (None, None),
+        }
+    }
+
+    /// A key that puts dates in chronological order, `None` for phrases that cannot be placed.
+    pub fn sort_key(&self) -> Option<i64> {
+        let (first, last) = self.day_range();
+        first.or(last)
+    }
+
+    pub fn to_gedcom(&self, version: GedcomVersion) -> String {
+        let date = |date: &CalendarDate| date.to_gedcom(version);
+        match self {
+            GedcomDate::Date(value) => date(value),
+            GedcomDate::Approximate(approximation, value) => format!("{} {}", approximation.keyword(), date(value)),
+            GedcomDate::Before(value) => format!("BEF {}", date(value)),
+            GedcomDate::After(value) => format!("AFT {}", date(value)),
+            GedcomDate::Between(from, to) => format!("BET {} AND {}", date(from), date(to)),
+            GedcomDate::Period(from, to) => {
+                let from = from.as_ref().map(|from| format!("FROM {}", date(from)));
+                let to = to.as_ref().map(|to| format!("TO {}", date(to)));
+                [from, to].into_iter().flatten().collect::<Vec<_>>().join(" ")
+            }
+            // 7.0 moved phrases into a `PHRASE` substructure and dropped `INT`.
+            GedcomDate::Interpreted(value, _) if version == GedcomVersion::V70 => date(value),
+            GedcomDate::Interpreted(value, phrase) => format!("INT {} ({})", date(value), phrase),
+            GedcomDate::Phrase(_) if version == GedcomVersion::V70 => String::new(),
+            GedcomDate::Phrase(phrase) => format!("({})", phrase),
+        }
+    }
+}
+
+impl FromStr for GedcomDate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (words, phrase) = match value.find('(') {
+            Some(start) => {
+                let phrase = value[start + 1..]
+                    .strip_suffix(')')
+                    .ok_or_else(|| format!("The phrase in {} is not closed", value))?;
+                (&value[..start], Some(phrase.to_string()))
+            }
+            None => (value, None),
+        };
+        let words = words.to_uppercase().replace("@#DFRENCH R@", "@#DFRENCH_R@");
+        let tokens: Vec<&str> = words
+            .split_whitespace()
+            .map(|token| if token == "@#DFRENCH_R@" { "@#DFRENCH R@" } else { token })
+            .collect();
+
+        let date = match (tokens.split_first(), phrase) {
+            (None, Some(phrase)) => return Ok(GedcomDate::Phrase(phrase)),
+            (None, None) => return Err("The date is empty".to_string()),
+            (Some((&"INT", rest)), Some(phrase)) => GedcomDate::Interpreted(CalendarDate::parse(rest)?, phrase),
+            (Some(_), Some(_)) => return Err(format!("Only an INT date can have a phrase: {}", value)),
+            (Some((&"ABT", rest)), None) => GedcomDate::Approximate(Approximation::About, CalendarDate::parse(rest)?),
+            (Some((&"CAL", rest)), None) => {
+                GedcomDate::Approximate(Approximation::Calculated, CalendarDate::parse(rest)?)
+            }
+            (Some((&"EST", rest)), None) => {
+                GedcomDate::Approximate(Approximation::Estimated, CalendarDate::parse(rest)?)
+            }
+            (Some((&"BEF", rest)), None) => GedcomDate::Before(CalendarDate::parse(rest)?),
+            (Some((&"AFT", rest)), None) => GedcomDate::After(CalendarDate::parse(rest)?),
+            (Some((&"BET", rest)), None) => {
+                let and = rest
+                    .iter()
+                    .position(|token| *token == "AND")
+                    .ok_or_else(|| format!("BET without AND: {}", value))?;
+                GedcomDate::Between(
+                    CalendarDate::parse(&rest[..and])?,
+                    CalendarDate::parse(&rest[and + 1..])?,
+                )
+            }
+            (Some((&"FROM", rest)), None) => match rest.iter().position(|token| *token == "TO") {
+                Some(to) => GedcomDate::Period(
+                    Some(CalendarDate::parse(&rest[..to])?),
+                    Some(CalendarDate::parse(&rest[to + 1..])?),
+                ),
+                None => GedcomDate::Period(Some(CalendarDate::parse(rest)?), None),
+            },
+            (Some((&"TO", rest)), None) => GedcomDate::Period(None, Some(CalendarDate::parse(rest)?)),
+            (Some(_), None) => GedcomDate::Date(CalendarDate::parse(&tokens)?),
+        };
+        Ok(date)
+    }
+}
+
+/// Writes the 5.5.x form, which is also how dates are kept while editing.
+impl fmt::Display for GedcomDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_gedcom(GedcomVersion::V551))
+    }
+}
+
+/// Dates are ordered when one certainly lies before the other. Overlapping ranges and phrases are not comparable.
+impl PartialOrd for GedcomDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        let (first, last) = self.day_range();
+        let (other_first, other_last) = other.day_range();
+        match (first, last, other_first, other_last) {
+            (_, Some(last), Some(other_first), _) if last < other_first => Some(Ordering::Less),
+            (Some(first), _, _, Some(other_last)) if first > other_last => Some(Ordering::Greater),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> GedcomDate {
+        value.parse().unwrap()
+    }
+
+    fn gregorian(year: i32, month: u8, day: u8) -> i64 {
+        Calendar::Gregorian.day_number(year, month, day)
+    }
+
+    #[test]
+    fn converts_known_days() {
+        assert_eq!(Calendar::FrenchRepublican.day_number(1, 1, 1), gregorian(1792, 9, 22));
+        assert_eq!(Calendar::FrenchRepublican.day_number(8, 2, 18), gregorian(1799, 11, 9));
+        // Year III was sextile, so year IV began a day later in the Gregorian year.
+        assert_eq!(
+            Calendar::FrenchRepublican.day_number(3, 13, 6) + 1,
+            gregorian(1795, 9, 23)
+        );
+        assert_eq!(Calendar::FrenchRepublican.day_number(4, 1, 1), gregorian(1795, 9, 23));
+        assert_eq!(Calendar::Hebrew.day_number(5784, 1, 1), gregorian(2023, 9, 16));
+        assert_eq!(Calendar::Hebrew.day_number(5785, 1, 1), gregorian(2024, 10, 3));
+        assert_eq!(Calendar::Hebrew.day_number(5784, 8, 15), gregorian(2024, 4, 23));
+        assert_eq!(Calendar::Julian.day_number(1582, 10, 4) + 1, gregorian(1582, 10, 15));
+    }
+
+    #[test]
+    fn reads_years_of_other_calendars() {
+        assert_eq!(date("@#DFRENCH R@ 1 VEND 1").year(), Some(1792));
+        assert_eq!(date("@#DHEBREW@ TSH 5784").year(), Some(2023));
+        assert_eq!(date("@#DJULIAN@ 1 JAN 1700").year(), Some(1700));
+        assert!("@#DHEBREW@ 1 ADS 5783".parse::<GedcomDate>().is_err());
+        assert!("@#DHEBREW@ 1 ADS 5784".parse::<GedcomDate>().is_ok());
+    }
+
+    #[test]
+    fn huge_years_do_not_overflow() {
+        assert!(date("@#DFRENCH R@ 1 VEND 2000000000").sort_key().is_some());
+        assert!(date("@#DHEBREW@ 2147483647").sort_key().is_some());
+        assert!(date("@#DHEBREW@ 29 ELL 2147483647").sort_key().is_some());
+        assert!("2147483647/99".parse::<GedcomDate>().is_err());
+    }
+
+    #[test]
+    fn parses_ranges_and_approximations() {
+        let from = CalendarDate::new(Calendar::Gregorian, 1850, None, None).unwrap();
+        let to = CalendarDate::new(Calendar::Gregorian, 1852, Some(3), None).unwrap();
+        assert_eq!(
+            date("BET 1850 AND MAR 1852"),
+            GedcomDate::Between(from.clone(), to.clone())
+        );
+        assert_eq!(
+            date("FROM 1850 TO MAR 1852"),
+            GedcomDate::Period(Some(from.clone()), Some(to.clone()))
+        );
+        assert_eq!(date("TO MAR 1852"), GedcomDate::Period(None, Some(to)));
+        assert_eq!(
+            date("abt 1850"),
+            GedcomDate::Approximate(Approximation::About, from.clone())
+        );
+        assert_eq!(
+            date("INT 1850 (about then)"),
+            GedcomDate::Interpreted(from, "about then".to_string())
+        );
+        assert!("BET 1850".parse::<GedcomDate>().is_err());
+        assert!("ABT 1850 (about then)".parse::<GedcomDate>().is_err());
+    }
+
+    #[test]
+    fn reads_dual_years_and_typed_dates() {
+        let GedcomDate::Date(dual) = date("1 FEB 1699/00") else {
+            panic!("not a date");
+        };
+        assert_eq!(dual.dual_year, Some(1700));
+        assert_eq!(dual.to_gedcom(GedcomVersion::V551), "1 FEB 1699/00");
+        assert_eq!(GedcomDate::parse_input("12.3.1850"), Ok(date("12 MAR 1850")));
+        assert_eq!(GedcomDate::parse_input("1850-03-12"), Ok(date("12 MAR 1850")));
+        assert_eq!(GedcomDate::parse_input("March 12, 1850"), Ok(date("12 MAR 1850")));
+    }
+
+    #[test]
+    fn sorts_ranges_around_the_date() {
+        let keys: Vec<Option<i64>> = ["BEF 1850", "1850", "ABT 1850", "BET 1850 AND 1852", "AFT 1850", "1851"]
+            .into_iter()
+            .map(|value| date(value).sort_key())
+            .collect();
+        assert!(keys[0] < keys[1]);
+        assert_eq!(keys[1], keys[2]);
+        assert_eq!(keys[1], keys[3]);
+        assert!(keys[3] < keys[4]);
+        assert_eq!(keys[4], keys[5]);
+        assert_eq!(date("(unknown)").sort_key(), None);
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found while reading a file, pointing at the offending line when there is one.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use super::diagnostics::Diagnostic;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Ansel,
+    Windows1252,
+}
+
+/// Decodes the raw bytes of a GEDCOM file into text.
+///
+/// A byte order mark wins, otherwise the `HEAD.CHAR` tag decides. Anything that does not decode cleanly falls back
+/// to Windows-1252 so the file can still be loaded, with a warning describing what happened.
+pub fn decode(bytes: &[u8]) -> (String, Vec<Diagnostic>) {
+    let mut diagnostics = vec![];
+    let (encoding, bytes) = if let Some(bytes) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        (Encoding::Utf8, bytes)
+    } else if let Some(bytes) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        (Encoding::Utf16Le, bytes)
+    } else if let Some(bytes) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        (Encoding::Utf16Be, bytes)
+    } else {
+        (declared_encoding(bytes, &mut diagnostics), bytes)
+    };
+
+    let content = match encoding {
+        Encoding::Utf8 => match String::from_utf8(bytes.to_vec()) {
+            Ok(content) => content,
+            Err(error) => {
+                let line = line_of_offset(bytes, error.utf8_error().valid_up_to());
+                diagnostics.push(Diagnostic::warning(
+                    Some(line),
+                    "File is not valid UTF-8, decoded it as Windows-1252 instead",
+                ));
+                decode_windows_1252(bytes)
+            }
+        },
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes, &mut diagnostics),
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes, &mut diagnostics),
+        Encoding::Ansel => decode_ansel(bytes),
+        Encoding::Windows1252 => decode_windows_1252(bytes),
+    };
+    (content, diagnostics)
+}
+
+/// Looks for `1 CHAR` near the start of the file. UTF-16 without a byte order mark is recognised by its zero bytes.
+fn declared_encoding(bytes: &[u8], diagnostics: &mut Vec<Diagnostic>) -> Encoding {
+    match bytes {
+        [0, b'0', ..] => return Encoding::Utf16Be,
+        [b'0', 0, ..] => return Encoding::Utf16Le,
+        _ => {}
+    }
+
+    let header = bytes
+        .split(|byte| *byte == b'\n')
+        .take(100)
+        .enumerate()
+        .find_map(|(index, line)| {
+            let line = String::from_utf8_lossy(line);
+            let value = line.trim().strip_prefix("1 CHAR")?.trim().to_uppercase();
+            Some((index + 1, value))
+        });
+    let Some((line, value)) = header else {
+        // 7.0 files have no `CHAR` and are always UTF-8.
+        return Encoding::Utf8;
+    };
+
+    match value.as_str() {
+        "UTF-8" | "UTF8" => Encoding::Utf8,
+        "ANSEL" => Encoding::Ansel,
+        "ANSI" | "WINDOWS-1252" | "CP1252" | "ISO-8859-1" | "LATIN1" => Encoding::Windows1252,
+        "ASCII" if bytes.is_ascii() => Encoding::Utf8,
+        "ASCII" => {
+            diagnostics.push(Diagnostic::warning(
+                Some(line),
+                "File is declared as ASCII but contains other characters, decoded it as Windows-1252",
+            ));
+            Encoding::Windows1252
+        }
+        "UNICODE" | "UTF-16" => {
+            diagnostics.push(Diagnostic::warning(
+                Some(line),
+                "File is declared as UTF-16 but is not laid out as UTF-16, decoded it as UTF-8",
+            ));
+            Encoding::Utf8
+        }
+        _ => {
+            diagnostics.push(Diagnostic::warning(
+                Some(line),
+                format!("Unsupported character set {}, decoded it as UTF-8", value),
+            ));
+            Encoding::Utf8
+        }
+    }
+}
+
+fn line_of_offset(bytes: &[u8], offset: usize) -> usize {
+    bytes[..offset].iter().filter(|byte| **byte == b'\n').count() + 1
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16, diagnostics: &mut Vec<Diagnostic>) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]]));
+    let content: String = char::decode_utf16(units)
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if !bytes.len().is_multiple_of(2) || content.contains(char::REPLACEMENT_CHARACTER) {
+        diagnostics.push(Diagnostic::warning(
+            None,
+            "File contains invalid UTF-16, unreadable characters were replaced",
+        ));
+    }
+    content
+}
+
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    const HIGH_CONTROL_RANGE: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}', '\u{90}', '‘',
+        '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+    ];
+    bytes
+        .iter()
+        .map(|byte| match byte {
+            0x80..=0x9F => HIGH_CONTROL_RANGE[(byte - 0x80) as usize],
+            _ => *byte as char,
+        })
+        .collect()
+}
+
+/// ANSEL writes combining diacritics before the letter they modify, Unicode expects them after it.
+fn decode_ansel(bytes: &[u8]) -> String {
+    let mut content = String::with_capacity(bytes.len());
+    let mut pending_marks: Vec<char> = vec![];
+    for byte in bytes {
+        if let Some(mark) = ansel_combining_mark(*byte) {
+            pending_marks.push(mark);
+            continue;
+        }
+        content.push(ansel_character(*byte));
+        content.extend(pending_marks.drain(..));
+    }
+    content.extend(pending_marks);
+    content
+}
+
+fn ansel_character(byte: u8) -> char {
+    match byte {
+        0xA1 => 'Ł',
+        0xA2 => 'Ø',
+        0xA3 => 'Đ',
+        0xA4 => 'Þ',
+        0xA5 => 'Æ',
+        0xA6 => 'Œ',
+        0xA7 => 'ʹ',
+        0xA8 => '·',
+        0xA9 => '♭',
+        0xAA => '®',
+        0xAB => '±',
+        0xAC => 'Ơ',
+        0xAD => 'Ư',
+        0xAE => 'ʼ',
+        0xB0 => 'ʻ',
+        0xB1 => 'ł',
+        0xB2 => 'ø',
+        0xB3 => 'đ',
+        0xB4 => 'þ',
+        0xB5 => 'æ',
+        0xB6 => 'œ',
+        0xB7 => 'ʺ',
+        0xB8 => 'ı',
+        0xB9 => '£',
+        0xBA => 'ð',
+        0xBC => 'ơ',
+        0xBD => 'ư',
+        0xC0 => '°',
+        0xC1 => 'ℓ',
+        0xC2 => '℗',
+        0xC3 => '©',
+        0xC4 => '♯',
+        0xC5 => '¿',
+        0xC6 => '¡',
+        0xC7 => 'ß',
+        0xC8 => '€',
+        0x00..=0x7F => byte as char,
+        _ => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+fn ansel_combining_mark(byte: u8) -> Option<char> {
+    let mark = match byte {
+        0xE0 => '\u{0309}',
+        0xE1 => '\u{0300}',
+        0xE2 => '\u{0301}',
+        0xE3 => '\u{0302}',
+        0xE4 => '\u{0303}',
+        0xE5 => '\u{0304}',
+        0xE6 => '\u{0306}',
+        0xE7 => '\u{0307}',
+        0xE8 => '\u{0308}',
+        0xE9 => '\u{030C}',
+        0xEA => '\u{030A}',
+        0xEB => '\u{FE20}',
+        0xEC => '\u{FE21}',
+        0xED => '\u{0315}',
+        0xEE => '\u{030B}',
+        0xEF => '\u{0310}',
+        0xF0 => '\u{0327}',
+        0xF1 => '\u{0328}',
+        0xF2 => '\u{0323}',
+        0xF3 => '\u{0324}',
+        0xF4 => '\u{0325}',
+        0xF5 => '\u{0333}',
+        0xF6 => '\u{0332}',
+        0xF7 => '\u{0326}',
+        0xF8 => '\u{031C}',
+        0xF9 => '\u{032E}',
+        0xFA => '\u{FE22}',
+        0xFB => '\u{FE23}',
+        0xFE => '\u{0313}',
+        _ => return None,
+    };
+    Some(mark)
+}
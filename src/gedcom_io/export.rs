@@ -0,0 +1,1021 @@
+use std::collections::{HashMap, HashSet};
+
+use graph::{node::GraphNodeTrait, Graph};
+
+use crate::{
+    association::Association,
+    family::{Families, Family, Pedigree},
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::{GenealogicalNode, Sex},
+    life_event::{EventType, LifeEvent},
+    media::{Media, MediaLink, MediaObject},
+    name::PersonalName,
+    note::{Note, NoteText, Notes, SharedNote},
+    place::{Place, Places},
+    relationship::{RelationshipEventType, RelationshipKind},
+    repository::{address_text, Repository, RepositoryLink},
+    source::{Citation, Sources},
+};
+
+use super::{
+    date::GedcomDate,
+    parser::{is_pointer, Structure},
+    preserved::PreservedRecords,
+    GedcomVersion,
+};
+
+const SOURCE_SYSTEM: &str = "iced-graph";
+const SUBMITTER_XREF: &str = "@U1@";
+/// 5.5.x caps a whole line at 255 characters, leave room for the level, tag and delimiters. 7.0 has no limit.
+const MAX_VALUE_LENGTH: usize = 200;
+
+/// Substructures of an imported `HEAD` that the exporter writes itself.
+const GENERATED_HEADER_TAGS: [&str; 4] = ["GEDC", "CHAR", "SOUR", "SUBM"];
+/// Substructures of an imported `INDI` that are regenerated from the node, everything else is written back as is.
+const GENERATED_INDIVIDUAL_TAGS: [&str; 4] = ["NAME", "SEX", "FAMC", "FAMS"];
+/// Substructures of an imported event that are regenerated from the `LifeEvent`.
+const GENERATED_EVENT_TAGS: [&str; 4] = ["TYPE", "DATE", "PLAC", "AGE"];
+/// Substructures of an imported `SOUR` record that are regenerated from the `Source`.
+const GENERATED_SOURCE_TAGS: [&str; 5] = ["TITL", "AUTH", "PUBL", "ABBR", "TEXT"];
+/// Substructures of an imported citation that are regenerated from the `Citation`, its `DATA` only in part.
+const GENERATED_CITATION_TAGS: [&str; 3] = ["PAGE", "DATA", "QUAY"];
+/// Substructures of an imported `OBJE` record that are regenerated from the `MediaObject`, its first `FILE` in part.
+const GENERATED_MEDIA_TAGS: [&str; 4] = ["FILE", "FORM", "TITL", "_PRIM"];
+/// Substructures of an imported `REPO` record that are regenerated from the `Repository`, their first occurrence.
+const GENERATED_REPOSITORY_TAGS: [&str; 5] = ["NAME", "ADDR", "PHON", "EMAIL", "WWW"];
+/// Substructures of an imported `FAM` that are regenerated from the `Family`, besides its relationship events.
+const GENERATED_FAMILY_TAGS: [&str; 3] = ["HUSB", "WIFE", "CHIL"];
+
+/// Serializes the whole graph into a GEDCOM file of the given version.
+///
+/// Every family becomes a `FAM` record, families without a member left in the graph are skipped. Whatever the
+/// nodes and families do not model is taken from the imported records and written back verbatim.
+#[allow(clippy::too_many_arguments)]
+pub fn export_tree(
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    preserved: &PreservedRecords,
+    families: &Families,
+    places: &Places,
+    sources: &Sources,
+    media: &Media,
+    notes: &Notes,
+    version: GedcomVersion,
+) -> String {
+    let mut used_xrefs: HashSet<String> = preserved.xrefs().cloned().collect();
+    let xrefs = keep_or_generate_xrefs(
+        graph.nodes().iter().map(|node| (node.id(), node.xref())).collect(),
+        "I",
+        &mut used_xrefs,
+    );
+    let source_xrefs = keep_or_generate_xrefs(
+        sources
+            .iter()
+            .map(|source| (source.id(), source.xref.clone()))
+            .collect(),
+        "S",
+        &mut used_xrefs,
+    );
+    let repository_xrefs = keep_or_generate_xrefs(
+        sources
+            .repositories()
+            .map(|repository| (repository.id(), repository.xref.clone()))
+            .collect(),
+        "R",
+        &mut used_xrefs,
+    );
+    let media_xrefs = keep_or_generate_xrefs(
+        media.iter().map(|object| (object.id(), object.xref.clone())).collect(),
+        "O",
+        &mut used_xrefs,
+    );
+    let note_xrefs = keep_or_generate_xrefs(
+        notes.iter().map(|note| (note.id(), note.xref.clone())).collect(),
+        "N",
+        &mut used_xrefs,
+    );
+    let links = LinkWriter {
+        citations: CitationWriter {
+            sources,
+            xrefs: &source_xrefs,
+        },
+        media: MediaLinkWriter {
+            media,
+            xrefs: &media_xrefs,
+        },
+        notes: NoteWriter {
+            notes,
+            xrefs: &note_xrefs,
+        },
+        associations: AssociationWriter { xrefs: &xrefs },
+    };
+    let families: Vec<&Family> = families
+        .iter()
+        .filter(|family| {
+            family
+                .partners
+                .iter()
+                .chain(family.children.iter().map(|link| &link.child))
+                .any(|id| graph.get_node(Some(*id)).is_some())
+        })
+        .collect();
+    let family_xrefs = keep_or_generate_xrefs(
+        families
+            .iter()
+            .map(|family| (family.id(), family.xref.clone()))
+            .collect(),
+        "F",
+        &mut used_xrefs,
+    );
+
+    // Keep pointing at the original submitter when the imported file had one.
+    let submitter = preserved
+        .header
+        .as_ref()
+        .and_then(|header| header.child_value("SUBM"))
+        .filter(|xref| {
+            preserved
+                .records
+                .iter()
+                .any(|record| record.xref.as_deref() == Some(*xref))
+        });
+
+    let mut writer = GedcomWriter::new(version);
+    if version == GedcomVersion::V555 {
+        // 5.5.5 makes the byte order mark mandatory.
+        writer.content.push('\u{FEFF}');
+    }
+    write_header(
+        &mut writer,
+        version,
+        preserved.header.as_ref(),
+        submitter.unwrap_or(SUBMITTER_XREF),
+    );
+
+    for node in graph.nodes() {
+        write_individual(&mut writer, node, &xrefs, (&families, &family_xrefs), places, &links);
+    }
+    for family in families.iter() {
+        write_family(
+            &mut writer,
+            family,
+            &family_xrefs[&family.id()],
+            graph,
+            &xrefs,
+            places,
+            &links,
+        );
+    }
+    for source in sources.iter() {
+        writer.record(&source_xrefs[&source.id()], "SOUR");
+        for (tag, value) in GENERATED_SOURCE_TAGS.iter().zip([
+            &source.title,
+            &source.author,
+            &source.publication,
+            &source.abbreviation,
+            &source.text,
+        ]) {
+            if let Some(value) = value {
+                writer.line(1, tag, Some(value));
+            }
+        }
+        for link in source.repositories.iter() {
+            write_repository_link(&mut writer, link, &repository_xrefs);
+        }
+        links.media.write(&mut writer, &source.media, None);
+        links.notes.write(&mut writer, 1, &source.notes);
+        for structure in source.gedcom_record.iter().flat_map(|record| record.children.iter()) {
+            if !GENERATED_SOURCE_TAGS.contains(&structure.tag.as_str())
+                && !is_repository_link(structure, sources)
+                && !links.media.regenerates(structure)
+                && !links.notes.regenerates(structure)
+            {
+                writer.structure(1, structure);
+            }
+        }
+    }
+    for repository in sources.repositories() {
+        write_repository(&mut writer, &repository_xrefs[&repository.id()], repository);
+    }
+    for object in media.iter() {
+        write_media_object(&mut writer, &media_xrefs[&object.id()], object);
+    }
+    for note in notes.iter() {
+        write_shared_note(&mut writer, &note_xrefs[&note.id()], note);
+    }
+    for record in preserved.records.iter() {
+        writer.structure(0, record);
+    }
+
+    if submitter.is_none() {
+        writer.record(SUBMITTER_XREF, "SUBM");
+        writer.line(1, "NAME", Some(SOURCE_SYSTEM));
+    }
+    writer.line(0, "TRLR", None);
+    writer.content
+}
+
+fn write_header(writer: &mut GedcomWriter, version: GedcomVersion, imported: Option<&Structure>, submitter: &str) {
+    writer.line(0, "HEAD", None);
+    writer.line(1, "GEDC", None);
+    writer.line(2, "VERS", Some(version.as_str()));
+    // 7.0 dropped the form and character set, its files are always lineage-linked UTF-8.
+    if version != GedcomVersion::V70 {
+        writer.line(2, "FORM", Some("LINEAGE-LINKED"));
+    }
+    if version == GedcomVersion::V555 {
+        writer.line(3, "VERS", Some(version.as_str()));
+    }
+    if version != GedcomVersion::V70 {
+        writer.line(1, "CHAR", Some("UTF-8"));
+    }
+    writer.line(1, "SOUR", Some(SOURCE_SYSTEM));
+    writer.line(2, "NAME", Some(SOURCE_SYSTEM));
+    writer.pointer(1, "SUBM", submitter);
+
+    for structure in imported.iter().flat_map(|header| header.children.iter()) {
+        if !GENERATED_HEADER_TAGS.contains(&structure.tag.as_str()) {
+            writer.structure(1, structure);
+        }
+    }
+}
+
+fn write_individual(
+    writer: &mut GedcomWriter,
+    node: &GenealogicalNode,
+    xrefs: &HashMap<u128, String>,
+    (families, family_xrefs): (&[&Family], &HashMap<u128, String>),
+    places: &Places,
+    links: &LinkWriter,
+) {
+    writer.record(&xrefs[&node.id()], "INDI");
+    let record = node.gedcom_record();
+
+    // The preferred name goes first, the one other programs show.
+    let preferred = node.preferred_name();
+    for name in preferred.into_iter().chain(
+        node.names()
+            .iter()
+            .filter(|name| !preferred.is_some_and(|preferred| std::ptr::eq(*name, preferred))),
+    ) {
+        write_name(writer, name);
+    }
+
+    // A person imported without a SEX line gets none back.
+    if let Some(sex) = node.sex() {
+        let sex = match sex {
+            Sex::Male => "M",
+            Sex::Female => "F",
+            Sex::Intersex if writer.version == GedcomVersion::V70 => "X",
+            // 5.5.x knows no other value.
+            Sex::Intersex | Sex::Unknown => "U",
+        };
+        writer.line(1, "SEX", Some(sex));
+    }
+
+    for family in families.iter() {
+        let tag = match family.pedigree(node.id()) {
+            Some(_) => "FAMC",
+            None if family.partners.contains(&node.id()) => "FAMS",
+            None => continue,
+        };
+        writer.pointer(1, tag, &family_xrefs[&family.id()]);
+        // Carry over what else the imported link said about it.
+        let imported_link = family.xref.as_deref().and_then(|xref| {
+            record?
+                .children_with_tag(tag)
+                .find(|link| link.value.as_deref() == Some(xref))
+        });
+        let imported_pedigree = imported_link.and_then(|link| link.child("PEDI"));
+        if let Some(pedigree) = family.pedigree(node.id()) {
+            // A birth is what a link without a pedigree means, it is only written when the imported link did.
+            if *pedigree != Pedigree::Birth || imported_pedigree.is_some() {
+                let (value, phrase) = pedigree.gedcom_value(writer.version);
+                writer.line(2, "PEDI", Some(&value));
+                if let Some(phrase) = phrase {
+                    writer.line(3, "PHRASE", Some(&phrase));
+                }
+            }
+        }
+        for structure in imported_link.iter().flat_map(|link| link.children.iter()) {
+            if structure.tag != "PEDI" {
+                writer.structure(2, structure);
+            }
+        }
+    }
+
+    for event in node.events() {
+        write_event(writer, event, places, links);
+    }
+    links.citations.write(writer, 1, node.citations());
+    links.media.write(writer, node.media(), node.portrait());
+    links.notes.write(writer, 1, node.notes());
+    links.associations.write(writer, 1, node.associations());
+
+    for structure in record.iter().flat_map(|record| record.children.iter()) {
+        if !GENERATED_INDIVIDUAL_TAGS.contains(&structure.tag.as_str())
+            && EventType::from_structure(structure).is_none()
+            && !links.citations.regenerates(structure)
+            && !links.media.regenerates(structure)
+            && !links.notes.regenerates(structure)
+            && !links.associations.regenerates(structure)
+        {
+            writer.structure(1, structure);
+        }
+    }
+}
+
+/// Writes a name with its type and parts, keeping the imported form of a name that was not edited.
+fn write_name(writer: &mut GedcomWriter, name: &PersonalName) {
+    let imported = name.gedcom_record.as_ref();
+    // An untouched name keeps its original form, which may put the parts in an order of its own.
+    let value = match imported {
+        Some(imported) if PersonalName::from_structure(imported).same_parts(name) => imported.value.clone(),
+        _ => Some(name.gedcom_value()),
+    };
+    writer.line(1, "NAME", value.as_deref());
+    if let Some(name_type) = &name.name_type {
+        let (value, phrase) = name_type.gedcom_value(writer.version);
+        writer.line(2, "TYPE", Some(&value));
+        if let Some(phrase) = phrase {
+            writer.line(3, "PHRASE", Some(&phrase));
+        }
+    }
+    for (tag, part) in name.parts() {
+        if let Some(part) = part.as_deref().filter(|part| !part.is_empty()) {
+            writer.line(2, tag, Some(part));
+        }
+    }
+    for structure in imported.iter().flat_map(|imported| imported.children.iter()) {
+        if structure.tag != "TYPE" && !name.parts().iter().any(|(tag, _)| *tag == structure.tag) {
+            writer.structure(2, structure);
+        }
+    }
+}
+
+fn write_repository(writer: &mut GedcomWriter, xref: &str, repository: &Repository) {
+    writer.record(xref, "REPO");
+    let imported = repository.gedcom_record.as_ref();
+    // 7.0 requires a name, earlier versions expect one as well.
+    writer.line(1, "NAME", Some(&repository.name()));
+    if let Some(address) = &repository.address {
+        writer.line(1, "ADDR", Some(address));
+        // The structured parts only still hold when the address is the one they were imported with.
+        let imported_address = imported
+            .and_then(|imported| imported.child("ADDR"))
+            .filter(|imported| address_text(imported).as_ref() == Some(address));
+        for structure in imported_address.iter().flat_map(|imported| imported.children.iter()) {
+            writer.structure(2, structure);
+        }
+    }
+    for (tag, value) in [
+        ("PHON", &repository.phone),
+        ("EMAIL", &repository.email),
+        ("WWW", &repository.website),
+    ] {
+        if let Some(value) = value {
+            writer.line(1, tag, Some(value));
+        }
+    }
+    // Further phone numbers, addresses and the like are kept, only the first of each is modelled.
+    let mut seen = HashSet::new();
+    for structure in imported.iter().flat_map(|imported| imported.children.iter()) {
+        let tag = structure.tag.as_str();
+        if !GENERATED_REPOSITORY_TAGS.contains(&tag) || !seen.insert(tag) {
+            writer.structure(1, structure);
+        }
+    }
+}
+
+/// Writes the pointer from a source to a repository holding it, with the call number the source is filed under.
+fn write_repository_link(writer: &mut GedcomWriter, link: &RepositoryLink, xrefs: &HashMap<u128, String>) {
+    let Some(xref) = xrefs.get(&link.repository) else {
+        return;
+    };
+    writer.pointer(1, "REPO", xref);
+    let imported = link.gedcom_record.as_ref();
+    let imported_call_number =
+        imported.and_then(|imported| imported.children.iter().position(|child| child.tag == "CALN"));
+    if let Some(call_number) = &link.call_number {
+        writer.line(2, "CALN", Some(call_number));
+        // Keep the medium the source is kept on, e.g. microfilm.
+        let imported_call_number = imported_call_number.and_then(|index| imported?.children.get(index));
+        for structure in imported_call_number.iter().flat_map(|caln| caln.children.iter()) {
+            writer.structure(3, structure);
+        }
+    }
+    for (index, structure) in imported
+        .iter()
+        .flat_map(|imported| imported.children.iter())
+        .enumerate()
+    {
+        if Some(index) != imported_call_number {
+            writer.structure(2, structure);
+        }
+    }
+}
+
+/// Whether an imported structure is a pointer to a repository of the tree, which is written from the links instead.
+fn is_repository_link(structure: &Structure, sources: &Sources) -> bool {
+    structure.tag == "REPO"
+        && structure
+            .value
+            .as_deref()
+            .is_some_and(|xref| is_pointer(xref) && sources.find_repository_xref(xref).is_some())
+}
+
+/// Writes a media object as a record. 5.5.x gives the format as a file extension, 7.0 as a media type.
+fn write_media_object(writer: &mut GedcomWriter, xref: &str, object: &MediaObject) {
+    writer.record(xref, "OBJE");
+    let imported = object.gedcom_record.as_ref();
+    let imported_file = imported.and_then(|imported| imported.child("FILE"));
+    writer.line(1, "FILE", Some(&object.file));
+    let format = match writer.version {
+        GedcomVersion::V70 => object.media_type(),
+        GedcomVersion::V551 | GedcomVersion::V555 => object.extension_format(),
+    };
+    if let Some(format) = format {
+        writer.line(2, "FORM", Some(&format));
+        // Keep the kind of medium, e.g. a photo or a book.
+        let imported_form = imported_file
+            .and_then(|file| file.child("FORM"))
+            .or_else(|| imported.and_then(|imported| imported.child("FORM")));
+        for structure in imported_form.iter().flat_map(|form| form.children.iter()) {
+            writer.structure(3, structure);
+        }
+    }
+    if let Some(title) = &object.title {
+        writer.line(2, "TITL", Some(title));
+    }
+    for structure in imported_file.iter().flat_map(|file| file.children.iter()) {
+        if !["FORM", "TITL"].contains(&structure.tag.as_str()) {
+            writer.structure(2, structure);
+        }
+    }
+    let first_file_index = imported.and_then(|imported| imported.children.iter().position(|child| child.tag == "FILE"));
+    for (index, structure) in imported
+        .iter()
+        .flat_map(|imported| imported.children.iter())
+        .enumerate()
+    {
+        if Some(index) != first_file_index && !GENERATED_MEDIA_TAGS.contains(&structure.tag.as_str()) {
+            writer.structure(1, structure);
+        }
+    }
+}
+
+/// Writes a shared note as a record, `NOTE` before 7.0 and `SNOTE` since.
+fn write_shared_note(writer: &mut GedcomWriter, xref: &str, note: &SharedNote) {
+    let tag = writer.shared_note_tag();
+    writer.text(0, Some(xref), tag, Some(&note.text));
+    for structure in note.gedcom_record.iter().flat_map(|record| record.children.iter()) {
+        writer.structure(1, structure);
+    }
+}
+
+fn write_event(writer: &mut GedcomWriter, event: &LifeEvent, places: &Places, links: &LinkWriter) {
+    let imported = event.gedcom_record.as_ref();
+    let value = if event.event_type.described_by_value() {
+        event.description.clone()
+    } else {
+        imported.and_then(|imported| imported.value.clone())
+    };
+    writer.line(1, event.event_type.gedcom_tag(), value.as_deref());
+    let event_kind = match &event.event_type {
+        EventType::Other(name) => Some(name.clone()).filter(|name| !name.is_empty()),
+        event_type if event_type.described_by_value() => imported
+            .and_then(|imported| imported.child_value("TYPE"))
+            .map(String::from),
+        _ => event.description.clone(),
+    };
+    if let Some(event_kind) = event_kind {
+        writer.line(2, "TYPE", Some(&event_kind));
+    }
+    if let Some(date) = &event.date {
+        write_date(writer, 2, date);
+    }
+    if let Some(place) = event.place.and_then(|id| places.get(id)) {
+        write_place(
+            writer,
+            place,
+            imported.and_then(|imported| imported.child("PLAC")),
+            places,
+        );
+    }
+    if let Some(age) = &event.age {
+        writer.line(2, "AGE", Some(age));
+    }
+    links.citations.write(writer, 2, &event.citations);
+    links.notes.write(writer, 2, &event.notes);
+    links.associations.write(writer, 2, &event.associations);
+    for structure in imported.iter().flat_map(|imported| imported.children.iter()) {
+        if !GENERATED_EVENT_TAGS.contains(&structure.tag.as_str())
+            && !links.citations.regenerates(structure)
+            && !links.notes.regenerates(structure)
+            && !links.associations.regenerates(structure)
+        {
+            writer.structure(2, structure);
+        }
+    }
+}
+
+/// Writes the shared place record inline, GEDCOM has no place records of its own.
+fn write_place(writer: &mut GedcomWriter, place: &Place, imported: Option<&Structure>, places: &Places) {
+    writer.line(2, "PLAC", Some(place.name().trim()));
+    if let Some(form) = place.form() {
+        writer.line(3, "FORM", Some(&form));
+    }
+    if let Some((latitude, longitude)) = place.coordinates() {
+        writer.line(3, "MAP", None);
+        writer.line(4, "LATI", Some(&gedcom_coordinate(latitude, 'N', 'S')));
+        writer.line(4, "LONG", Some(&gedcom_coordinate(longitude, 'E', 'W')));
+    }
+    // Notes, translations and the like belong to the spelling they were imported with.
+    let same_place = imported
+        .and_then(|imported| imported.value.as_deref())
+        .and_then(|name| places.find(name))
+        == Some(place.id());
+    for structure in imported
+        .filter(|_| same_place)
+        .iter()
+        .flat_map(|imported| imported.children.iter())
+    {
+        if !["FORM", "MAP"].contains(&structure.tag.as_str()) {
+            writer.structure(3, structure);
+        }
+    }
+}
+
+fn gedcom_coordinate(degrees: f64, positive: char, negative: char) -> String {
+    let hemisphere = if degrees < 0.0 { negative } else { positive };
+    format!("{}{}", hemisphere, degrees.abs())
+}
+
+/// Writes a date in the form of the target version, or as it was written when it cannot be read.
+fn write_date(writer: &mut GedcomWriter, level: u8, date: &str) {
+    let Ok(parsed) = date.parse::<GedcomDate>() else {
+        writer.line(level, "DATE", Some(date));
+        return;
+    };
+    // Written as is, the `@` of a calendar escape must not be doubled.
+    writer.push_line(level, None, "DATE", &parsed.to_gedcom(writer.version));
+    if writer.version == GedcomVersion::V70 {
+        // What 7.0 cannot say in the value itself goes into a phrase, including dual years.
+        let phrase = match parsed.phrase() {
+            Some(phrase) => Some(phrase.to_string()),
+            None if parsed.has_dual_year() => {
+                let words = parsed.to_string();
+                Some(
+                    words
+                        .split(' ')
+                        .filter(|word| !word.starts_with("@#D"))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                )
+            }
+            None => None,
+        };
+        if let Some(phrase) = phrase {
+            writer.line(level + 1, "PHRASE", Some(&phrase));
+        }
+    }
+}
+
+fn write_family(
+    writer: &mut GedcomWriter,
+    family: &Family,
+    xref: &str,
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    xrefs: &HashMap<u128, String>,
+    places: &Places,
+    links: &LinkWriter,
+) {
+    writer.record(xref, "FAM");
+
+    // 5.5.x only knows HUSB and WIFE, fall back to the partner order when sex does not decide it. Same-sex couples
+    // keep the order they were entered in, 7.0 reads neither tag as saying anything about sex.
+    let mut partners: Vec<u128> = family
+        .partners
+        .iter()
+        .copied()
+        .filter(|id| xrefs.contains_key(id))
+        .collect();
+    partners.sort_by_key(|id| match graph.get_node(Some(*id)).and_then(|node| node.sex()) {
+        Some(Sex::Male) => 0,
+        Some(Sex::Intersex) | Some(Sex::Unknown) | None => 1,
+        Some(Sex::Female) => 2,
+    });
+    for (partner, tag) in partners.iter().zip(["HUSB", "WIFE"]) {
+        writer.pointer(1, tag, &xrefs[partner]);
+    }
+    for xref in family.children.iter().filter_map(|link| xrefs.get(&link.child)) {
+        writer.pointer(1, "CHIL", xref);
+    }
+    write_relationship(writer, family, places);
+    let LinkWriter { citations, notes, .. } = links;
+    citations.write(writer, 1, citations.sources.relationship(&family.partners));
+    notes.write(writer, 1, notes.notes.relationship(&family.partners));
+
+    for structure in family.gedcom_record.iter().flat_map(|record| record.children.iter()) {
+        if !GENERATED_FAMILY_TAGS.contains(&structure.tag.as_str())
+            && !RelationshipEventType::is_gedcom_tag(&structure.tag)
+            && !RelationshipKind::is_partnership_event(structure)
+            && !citations.regenerates(structure)
+            && !notes.regenerates(structure)
+        {
+            writer.structure(1, structure);
+        }
+    }
+}
+
+/// Writes the events of a family together with its kind, which GEDCOM only tells through them.
+fn write_relationship(writer: &mut GedcomWriter, family: &Family, places: &Places) {
+    let has_union = family
+        .events
+        .iter()
+        .any(|event| event.event_type == RelationshipEventType::Union);
+    match family.kind {
+        RelationshipKind::Marriage | RelationshipKind::CivilUnion if !has_union => {
+            writer.line(1, "MARR", Some("Y"));
+            if family.kind == RelationshipKind::CivilUnion {
+                writer.line(2, "TYPE", Some(RelationshipKind::CIVIL_UNION_TYPE));
+            }
+        }
+        RelationshipKind::Partnership => {
+            writer.line(1, "EVEN", None);
+            writer.line(2, "TYPE", Some(RelationshipKind::PARTNERSHIP_TYPE));
+        }
+        _ => {}
+    }
+
+    for event in family.events.iter() {
+        let imported = event.gedcom_record.as_ref();
+        // `Y` says that the event happened when nothing else about it is known.
+        let value = (event.date.is_none() && event.place.is_none()).then_some("Y");
+        writer.line(1, event.event_type.gedcom_tag(), value);
+        // The type of a marriage tells a civil union apart, whatever else the imported one said is kept.
+        let event_kind = match (&event.event_type, family.kind) {
+            (RelationshipEventType::Union, RelationshipKind::CivilUnion) => Some(RelationshipKind::CIVIL_UNION_TYPE),
+            _ => imported
+                .and_then(|imported| imported.child_value("TYPE"))
+                .filter(|kind| !kind.to_lowercase().contains("civil")),
+        };
+        if let Some(event_kind) = event_kind {
+            writer.line(2, "TYPE", Some(event_kind));
+        }
+        if let Some(date) = &event.date {
+            write_date(writer, 2, date);
+        }
+        if let Some(place) = event.place.and_then(|id| places.get(id)) {
+            write_place(
+                writer,
+                place,
+                imported.and_then(|imported| imported.child("PLAC")),
+                places,
+            );
+        }
+        // Sources and notes of these events are not modelled, they are written back as imported.
+        for structure in imported.iter().flat_map(|imported| imported.children.iter()) {
+            if !GENERATED_EVENT_TAGS.contains(&structure.tag.as_str()) {
+                writer.structure(2, structure);
+            }
+        }
+    }
+}
+
+/// Keeps the xref a node, family or source was imported with and generates free ones for the rest.
+fn keep_or_generate_xrefs(
+    records: Vec<(u128, Option<String>)>,
+    prefix: &str,
+    used: &mut HashSet<String>,
+) -> HashMap<u128, String> {
+    let mut xrefs: HashMap<u128, String> = HashMap::new();
+    for (id, xref) in records.iter() {
+        if let Some(xref) = xref {
+            if used.insert(xref.clone()) {
+                xrefs.insert(*id, xref.clone());
+            }
+        }
+    }
+
+    let mut next = 1;
+    for (id, _) in records.iter() {
+        xrefs.entry(*id).or_insert_with(|| free_xref(prefix, &mut next, used));
+    }
+    xrefs
+}
+
+/// The writers for what records point at, kept together as most records can have all of them.
+struct LinkWriter<'a> {
+    citations: CitationWriter<'a>,
+    media: MediaLinkWriter<'a>,
+    notes: NoteWriter<'a>,
+    associations: AssociationWriter<'a>,
+}
+
+/// Writes citations pointing at the exported source records.
+struct CitationWriter<'a> {
+    sources: &'a Sources,
+    xrefs: &'a HashMap<u128, String>,
+}
+
+impl CitationWriter<'_> {
+    fn write(&self, writer: &mut GedcomWriter, level: u8, citations: &[Citation]) {
+        for citation in citations.iter() {
+            let Some(xref) = self.xrefs.get(&citation.source) else {
+                continue;
+            };
+            let imported = citation.gedcom_record.as_ref();
+            writer.pointer(level, "SOUR", xref);
+            if let Some(page) = &citation.page {
+                writer.line(level + 1, "PAGE", Some(page));
+            }
+            // Keep the date of the entry and whatever else the imported data said next to the text.
+            let imported_data = imported
+                .and_then(|imported| imported.child("DATA"))
+                .map(|data| {
+                    data.children
+                        .iter()
+                        .filter(|child| child.tag != "TEXT")
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            if citation.text.is_some() || !imported_data.is_empty() {
+                writer.line(level + 1, "DATA", None);
+                for structure in imported_data {
+                    writer.structure(level + 2, structure);
+                }
+                if let Some(text) = &citation.text {
+                    writer.line(level + 2, "TEXT", Some(text));
+                }
+            }
+            if let Some(quality) = citation.quality {
+                writer.line(level + 1, "QUAY", Some(quality.gedcom_value()));
+            }
+            for structure in imported.iter().flat_map(|imported| imported.children.iter()) {
+                if !GENERATED_CITATION_TAGS.contains(&structure.tag.as_str()) {
+                    writer.structure(level + 1, structure);
+                }
+            }
+        }
+    }
+
+    /// Whether an imported structure is a citation of a source record, which is written from the citations instead.
+    fn regenerates(&self, structure: &Structure) -> bool {
+        structure.tag == "SOUR"
+            && structure
+                .value
+                .as_deref()
+                .is_some_and(|xref| is_pointer(xref) && self.sources.find_xref(xref).is_some())
+    }
+}
+
+/// Writes links to the exported media object records.
+struct MediaLinkWriter<'a> {
+    media: &'a Media,
+    xrefs: &'a HashMap<u128, String>,
+}
+
+impl MediaLinkWriter<'_> {
+    /// Writes the portrait first, the link most programs take the primary picture from.
+    fn write(&self, writer: &mut GedcomWriter, links: &[MediaLink], portrait: Option<u128>) {
+        let (primary, others): (Vec<&MediaLink>, Vec<&MediaLink>) =
+            links.iter().partition(|link| Some(link.object) == portrait);
+        for link in primary.into_iter().chain(others) {
+            let Some(xref) = self.xrefs.get(&link.object) else {
+                continue;
+            };
+            writer.pointer(1, "OBJE", xref);
+            // 5.5.1 has no way of its own to mark it, but most programs reading it understand this extension.
+            if writer.version == GedcomVersion::V551 && Some(link.object) == portrait {
+                writer.line(2, "_PRIM", Some("Y"));
+            }
+            for structure in link.gedcom_record.iter().flat_map(|imported| imported.children.iter()) {
+                if structure.tag != "_PRIM" {
+                    writer.structure(2, structure);
+                }
+            }
+        }
+    }
+
+    /// Whether an imported structure is a media link written from the links instead, a pointer to a known object or
+    /// an inline object, which was imported as an object of its own.
+    fn regenerates(&self, structure: &Structure) -> bool {
+        structure.tag == "OBJE"
+            && match structure.value.as_deref().filter(|value| is_pointer(value)) {
+                Some(xref) => self.media.find_xref(xref).is_some(),
+                None => structure.child("FILE").is_some(),
+            }
+    }
+}
+
+/// Writes notes, pointing at the exported shared note records where they are shared.
+struct NoteWriter<'a> {
+    notes: &'a Notes,
+    xrefs: &'a HashMap<u128, String>,
+}
+
+impl NoteWriter<'_> {
+    fn write(&self, writer: &mut GedcomWriter, level: u8, notes: &[Note]) {
+        for note in notes.iter() {
+            match &note.text {
+                NoteText::Inline(text) => writer.line(level, "NOTE", Some(text)),
+                NoteText::Shared(id) => {
+                    let Some(xref) = self.xrefs.get(id) else {
+                        continue;
+                    };
+                    let tag = writer.shared_note_tag();
+                    writer.pointer(level, tag, xref);
+                }
+            }
+            for structure in note.gedcom_record.iter().flat_map(|imported| imported.children.iter()) {
+                writer.structure(level + 1, structure);
+            }
+        }
+    }
+
+    /// Whether an imported structure is a note written from the notes instead, any note written out or a pointer to
+    /// a known shared note.
+    fn regenerates(&self, structure: &Structure) -> bool {
+        matches!(structure.tag.as_str(), "NOTE" | "SNOTE")
+            && match structure.value.as_deref().filter(|value| is_pointer(value)) {
+                Some(xref) => self.notes.find_xref(xref).is_some(),
+                None => true,
+            }
+    }
+}
+
+/// Writes associations pointing at the exported individual records.
+struct AssociationWriter<'a> {
+    xrefs: &'a HashMap<u128, String>,
+}
+
+impl AssociationWriter<'_> {
+    fn write(&self, writer: &mut GedcomWriter, level: u8, associations: &[Association]) {
+        // 5.5.x only has associations of a person, those of an event use the extension most programs read.
+        let tag = match writer.version {
+            GedcomVersion::V551 | GedcomVersion::V555 if level > 1 => "_ASSO",
+            _ => "ASSO",
+        };
+        for association in associations.iter() {
+            let Some(xref) = self.xrefs.get(&association.person) else {
+                continue;
+            };
+            writer.pointer(level, tag, xref);
+            let (value, phrase) = association.role.gedcom_value(writer.version);
+            match writer.version {
+                GedcomVersion::V70 => {
+                    writer.line(level + 1, "ROLE", Some(&value));
+                    if let Some(phrase) = phrase {
+                        writer.line(level + 2, "PHRASE", Some(&phrase));
+                    }
+                }
+                GedcomVersion::V551 | GedcomVersion::V555 => writer.line(level + 1, "RELA", Some(&value)),
+            }
+            for structure in association
+                .gedcom_record
+                .iter()
+                .flat_map(|imported| imported.children.iter())
+            {
+                if !["ROLE", "RELA"].contains(&structure.tag.as_str()) {
+                    writer.structure(level + 1, structure);
+                }
+            }
+        }
+    }
+
+    /// Whether an imported structure is an association with an exported individual, which is written from the
+    /// associations instead.
+    fn regenerates(&self, structure: &Structure) -> bool {
+        matches!(structure.tag.as_str(), "ASSO" | "_ASSO")
+            && structure
+                .value
+                .as_deref()
+                .is_some_and(|xref| is_pointer(xref) && self.xrefs.values().any(|other| other == xref))
+    }
+}
+
+pub(super) fn free_xref(prefix: &str, next: &mut usize, used: &mut HashSet<String>) -> String {
+    loop {
+        let candidate = format!("@{}{}@", prefix, next);
+        *next += 1;
+        if used.insert(candidate.clone()) {
+            return candidate;
+        }
+    }
+}
+
+struct GedcomWriter {
+    version: GedcomVersion,
+    content: String,
+}
+
+impl GedcomWriter {
+    fn new(version: GedcomVersion) -> Self {
+        Self {
+            version,
+            content: String::new(),
+        }
+    }
+
+    fn record(&mut self, xref: &str, tag: &str) {
+        self.text(0, Some(xref), tag, None);
+    }
+
+    fn pointer(&mut self, level: u8, tag: &str, xref: &str) {
+        self.push_line(level, None, tag, xref);
+    }
+
+    /// 5.5.x points at a shared note with `NOTE`, 7.0 with `SNOTE`.
+    fn shared_note_tag(&self) -> &'static str {
+        match self.version {
+            GedcomVersion::V70 => "SNOTE",
+            GedcomVersion::V551 | GedcomVersion::V555 => "NOTE",
+        }
+    }
+
+    /// Writes an imported structure and all of its substructures back unchanged, apart from the tag of a pointer to a
+    /// shared note, which follows the version.
+    fn structure(&mut self, level: u8, structure: &Structure) {
+        match structure.value.as_deref() {
+            Some(value) if is_pointer(value) && matches!(structure.tag.as_str(), "NOTE" | "SNOTE") => {
+                self.pointer(level, self.shared_note_tag(), value)
+            }
+            Some(value) if is_pointer(value) => self.pointer(level, &structure.tag, value),
+            value => self.text(level, structure.xref.as_deref(), &structure.tag, value),
+        }
+        for child in structure.children.iter() {
+            self.structure(level + 1, child);
+        }
+    }
+
+    fn line(&mut self, level: u8, tag: &str, value: Option<&str>) {
+        self.text(level, None, tag, value);
+    }
+
+    /// Writes a text line, continuing embedded newlines with `CONT` and, before 7.0, overlong values with `CONC`.
+    fn text(&mut self, level: u8, xref: Option<&str>, tag: &str, value: Option<&str>) {
+        let Some(value) = value else {
+            self.push_line(level, xref, tag, "");
+            return;
+        };
+
+        for (index, text) in value.split('\n').enumerate() {
+            let (level, xref, tag) = if index == 0 {
+                (level, xref, tag)
+            } else {
+                (level + 1, None, "CONT")
+            };
+            let text = self.escape(text);
+            if self.version == GedcomVersion::V70 {
+                self.push_line(level, xref, tag, &text);
+                continue;
+            }
+            let mut chunks = split_value(&text).into_iter();
+            self.push_line(level, xref, tag, chunks.next().unwrap_or_default());
+            for chunk in chunks {
+                self.push_line(level + 1, None, "CONC", chunk);
+            }
+        }
+    }
+
+    /// 5.5.x doubles every `@` in text, 7.0 only a leading one so it cannot be mistaken for a pointer.
+    fn escape(&self, text: &str) -> String {
+        match self.version {
+            GedcomVersion::V70 if text.starts_with('@') => format!("@{}", text),
+            GedcomVersion::V70 => text.to_string(),
+            GedcomVersion::V551 | GedcomVersion::V555 => text.replace('@', "@@"),
+        }
+    }
+
+    fn push_line(&mut self, level: u8, xref: Option<&str>, tag: &str, value: &str) {
+        self.content.push_str(&level.to_string());
+        if let Some(xref) = xref {
+            self.content.push(' ');
+            self.content.push_str(xref);
+        }
+        self.content.push(' ');
+        self.content.push_str(tag);
+        if !value.is_empty() {
+            self.content.push(' ');
+            self.content.push_str(value);
+        }
+        self.content.push('\n');
+    }
+}
+
+/// Splits a value into chunks that fit on one line without breaking next to a space, which readers may trim.
+fn split_value(value: &str) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = value;
+    while let Some((limit, _)) = rest.char_indices().nth(MAX_VALUE_LENGTH) {
+        let split = (1..=limit)
+            .rev()
+            .filter(|index| rest.is_char_boundary(*index))
+            .find(|index| !rest[..*index].ends_with(' ') && !rest[*index..].starts_with(' '))
+            .unwrap_or(limit);
+        chunks.push(&rest[..split]);
+        rest = &rest[split..];
+    }
+    chunks.push(rest);
+    chunks
+}
@@ -0,0 +1,58 @@
+use std::{
+    fs::{self, File},
+    io::{self, Cursor, Read, Write},
+    path::Path,
+};
+
+use zip::{result::ZipResult, write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use crate::media::Media;
+
+/// The dataset inside a GEDZIP archive, every other entry is a media file it references.
+const DATASET_ENTRY: &str = "gedcom.ged";
+
+/// Extracts the raw GEDCOM dataset from a GEDZIP archive and unpacks the media files next to it into `directory`.
+pub fn read_gedzip(archive: &[u8], directory: &Path) -> ZipResult<Vec<u8>> {
+    let mut archive = ZipArchive::new(Cursor::new(archive))?;
+    let mut content = vec![];
+    archive.by_name(DATASET_ENTRY)?.read_to_end(&mut content)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        // An entry whose name would leave the directory, like `../file`, is not unpacked.
+        let Some(name) = entry
+            .enclosed_name()
+            .filter(|_| entry.is_file() && entry.name() != DATASET_ENTRY)
+        else {
+            continue;
+        };
+        let target = directory.join(name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(target)?)?;
+    }
+    Ok(content)
+}
+
+/// Packs a GEDCOM 7.0 dataset into a GEDZIP archive together with the media files it references by a relative path.
+///
+/// Files given by an absolute path or a URL, and files that cannot be read, stay references to outside the archive.
+pub fn write_gedzip(content: &str, media: &Media) -> ZipResult<Vec<u8>> {
+    let mut archive = ZipWriter::new(Cursor::new(vec![]));
+    archive.start_file(DATASET_ENTRY, SimpleFileOptions::default())?;
+    archive.write_all(content.as_bytes())?;
+    let mut written = vec![];
+    for object in media.iter() {
+        let relative = Path::new(&object.file).is_relative() && !object.file.contains("://");
+        if !relative || object.file == DATASET_ENTRY || written.contains(&object.file) {
+            continue;
+        }
+        let Some(bytes) = media.path(object.id()).and_then(|path| fs::read(path).ok()) else {
+            continue;
+        };
+        archive.start_file(object.file.as_str(), SimpleFileOptions::default())?;
+        archive.write_all(&bytes)?;
+        written.push(object.file.clone());
+    }
+    Ok(archive.finish()?.into_inner())
+}
@@ -0,0 +1,583 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use graph::Graph;
+
+use crate::{
+    association::AssociationRole,
+    family::{Families, Pedigree},
+    gedcom_io::{
+        date::{Approximation, CalendarDate, GedcomDate},
+        export::export_tree,
+        parser::{is_pointer, parse, Structure},
+        preserved::PreservedRecords,
+        GedcomVersion,
+    },
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode,
+    media::Media,
+    name::{NameType, PersonalName},
+    note::Notes,
+    place::Places,
+    relationship::RelationshipKind,
+    source::Sources,
+};
+
+use super::{
+    cformat, event_type, gramps_coordinate, gramps_date_value, xml::Element, ADDRESS_PARTS, DESCRIBED_BY_VALUE,
+    PEDIGREES,
+};
+
+const GRAMPS_XML_VERSION: &str = "1.7.1";
+
+/// The objects of a Gramps database, collected while walking the GEDCOM records.
+#[derive(Default)]
+struct Database {
+    events: Vec<Element>,
+    people: Vec<Element>,
+    families: Vec<Element>,
+    citations: Vec<Element>,
+    sources: Vec<Element>,
+    places: Vec<Element>,
+    objects: Vec<Element>,
+    repositories: Vec<Element>,
+    notes: Vec<Element>,
+    /// Place handles by their `PLAC` text, so that every place is written once.
+    place_handles: HashMap<String, String>,
+    /// `PEDI` of every child and family pair, read from the `FAMC` links.
+    pedigrees: HashMap<(String, String), Pedigree>,
+}
+
+/// Serializes the whole graph into a gzip-compressed Gramps XML database.
+///
+/// The tree is exported to GEDCOM first, so families are written exactly as they are for a GEDCOM export, and the
+/// resulting records are then mapped onto Gramps objects.
+pub fn export_gramps(
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    preserved: &PreservedRecords,
+    families: &Families,
+    places: &Places,
+    sources: &Sources,
+    media: &Media,
+    notes: &Notes,
+) -> io::Result<Vec<u8>> {
+    let (records, _) = parse(&export_tree(
+        graph,
+        preserved,
+        families,
+        places,
+        sources,
+        media,
+        notes,
+        GedcomVersion::V70,
+    ));
+
+    let mut database = Database::default();
+    for record in records.iter().filter(|record| record.tag == "INDI") {
+        for link in record.children_with_tag("FAMC") {
+            if let (Some(child), Some(family), Some(pedigree)) = (&record.xref, &link.value, link.child("PEDI")) {
+                database
+                    .pedigrees
+                    .insert((child.clone(), family.clone()), Pedigree::from_structure(pedigree));
+            }
+        }
+    }
+    for record in records.iter() {
+        match record.tag.as_str() {
+            "INDI" => database.person(record),
+            "FAM" => database.family(record),
+            "SOUR" => database.source(record),
+            "OBJE" => database.media_object(record),
+            "REPO" => database.repository(record),
+            "NOTE" | "SNOTE" => database.shared_note(record),
+            _ => {}
+        }
+    }
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE database PUBLIC \"-//Gramps//DTD Gramps XML {0}//EN\"\n\
+         \"http://gramps-project.org/xml/{0}/grampsxml.dtd\">\n",
+        GRAMPS_XML_VERSION
+    );
+    database.into_element().write(&mut xml, 0);
+
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(xml.as_bytes())?;
+    encoder.finish()
+}
+
+impl Database {
+    fn into_element(self) -> Element {
+        let header = Element::new("header").with_child(
+            Element::new("created")
+                .with_attribute("date", &today())
+                .with_attribute("version", "iced-graph"),
+        );
+        let mut database = Element::new("database")
+            .with_attribute(
+                "xmlns",
+                &format!("http://gramps-project.org/xml/{}/", GRAMPS_XML_VERSION),
+            )
+            .with_child(header);
+        // The DTD fixes the order of the sections.
+        for (name, objects) in [
+            ("events", self.events),
+            ("people", self.people),
+            ("families", self.families),
+            ("citations", self.citations),
+            ("sources", self.sources),
+            ("places", self.places),
+            ("objects", self.objects),
+            ("repositories", self.repositories),
+            ("notes", self.notes),
+        ] {
+            if !objects.is_empty() {
+                let mut section = Element::new(name);
+                section.children = objects;
+                database.push(section);
+            }
+        }
+        database
+    }
+
+    fn person(&mut self, record: &Structure) {
+        let mut person = object("person", record);
+        let gender = match record.child_value("SEX") {
+            Some("M") => "M",
+            Some("F") => "F",
+            Some("X") => "X",
+            _ => "U",
+        };
+        person.push(Element::new("gender").with_text(gender));
+        for (index, name) in record.children_with_tag("NAME").enumerate() {
+            person.push(self.name(name, index > 0));
+        }
+        for structure in record.children.iter() {
+            if let Some(event) = self.event(structure) {
+                person.push(
+                    Element::new("eventref")
+                        .with_attribute("hlink", &event)
+                        .with_attribute("role", "Primary"),
+                );
+            }
+        }
+        push_media_links(&mut person, record);
+        for (tag, element) in [("FAMC", "childof"), ("FAMS", "parentin")] {
+            for link in record.children_with_tag(tag) {
+                if let Some(family) = link.value.as_deref() {
+                    person.push(Element::new(element).with_attribute("hlink", &handle(family)));
+                }
+            }
+        }
+        // Gramps links people to a person only, associations of events are left out.
+        for link in record.children_with_tag("ASSO") {
+            if let Some(associate) = link.value.as_deref() {
+                let mut person_ref = Element::new("personref")
+                    .with_attribute("hlink", &handle(associate))
+                    .with_attribute("rel", &AssociationRole::from_structure(link).to_string());
+                self.push_notes_and_citations(&mut person_ref, link);
+                person.push(person_ref);
+            }
+        }
+        self.push_notes_and_citations(&mut person, record);
+        self.people.push(person);
+    }
+
+    fn name(&self, name: &Structure, alternate: bool) -> Element {
+        let name = PersonalName::from_structure(name);
+        // Gramps has no maiden names of its own, a maiden name is the name someone was born with.
+        let name_type = match &name.name_type {
+            None | Some(NameType::Birth) | Some(NameType::Maiden) => "Birth Name".to_string(),
+            Some(NameType::Aka) => "Also Known As".to_string(),
+            Some(NameType::Married) => "Married Name".to_string(),
+            Some(NameType::Other(other)) if other.is_empty() => "Unknown".to_string(),
+            Some(name_type) => name_type.to_string(),
+        };
+        let mut element = Element::new("name").with_attribute("type", &name_type);
+        if alternate {
+            element = element.with_attribute("alt", "1");
+        }
+        if let Some(given) = &name.given {
+            element.push(Element::new("first").with_text(given));
+        }
+        let mut surname_element = Element::new("surname").with_text(name.surname.as_deref().unwrap_or_default());
+        if let Some(prefix) = &name.surname_prefix {
+            surname_element = surname_element.with_attribute("prefix", prefix);
+        }
+        element.push(surname_element);
+        for (part, child) in [
+            (&name.suffix, "suffix"),
+            (&name.prefix, "title"),
+            (&name.nickname, "nick"),
+        ] {
+            if let Some(value) = part {
+                element.push(Element::new(child).with_text(value));
+            }
+        }
+        element
+    }
+
+    fn family(&mut self, record: &Structure) {
+        let mut family = object("family", record);
+        let relationship = match RelationshipKind::from_record(record) {
+            RelationshipKind::Marriage => "Married",
+            RelationshipKind::CivilUnion => "Civil Union",
+            RelationshipKind::Partnership => "Unmarried",
+            RelationshipKind::Unknown => "Unknown",
+        };
+        family.push(Element::new("rel").with_attribute("type", relationship));
+        for (tag, element) in [("HUSB", "father"), ("WIFE", "mother")] {
+            if let Some(partner) = record.child_value(tag) {
+                family.push(Element::new(element).with_attribute("hlink", &handle(partner)));
+            }
+        }
+        // The partnership marker is told by the relationship type above rather than being an event.
+        for structure in record
+            .children
+            .iter()
+            .filter(|structure| !RelationshipKind::is_partnership_event(structure))
+        {
+            if let Some(event) = self.event(structure) {
+                family.push(
+                    Element::new("eventref")
+                        .with_attribute("hlink", &event)
+                        .with_attribute("role", "Family"),
+                );
+            }
+        }
+        for child in record
+            .children_with_tag("CHIL")
+            .filter_map(|child| child.value.as_deref())
+        {
+            let pedigree = record
+                .xref
+                .as_ref()
+                .and_then(|family| self.pedigrees.get(&(child.to_string(), family.clone())))
+                .map(gramps_pedigree)
+                .unwrap_or_else(|| "Birth".to_string());
+            family.push(
+                Element::new("childref")
+                    .with_attribute("hlink", &handle(child))
+                    .with_attribute("mrel", &pedigree)
+                    .with_attribute("frel", &pedigree),
+            );
+        }
+        self.push_notes_and_citations(&mut family, record);
+        self.families.push(family);
+    }
+
+    /// Writes the structure as an event if it is one and returns its handle.
+    fn event(&mut self, structure: &Structure) -> Option<String> {
+        let tag = structure.tag.as_str();
+        let (gramps_type, description) = match (tag, event_type(tag)) {
+            ("EVEN", _) => (
+                structure.child_value("TYPE").unwrap_or("Event"),
+                structure.value.as_deref(),
+            ),
+            (_, Some(gramps_type)) if DESCRIBED_BY_VALUE.contains(&tag) => (gramps_type, structure.value.as_deref()),
+            (_, Some(gramps_type)) => (gramps_type, structure.child_value("TYPE")),
+            (_, None) => return None,
+        };
+
+        let event_handle = format!("_E{}", self.events.len() + 1);
+        let mut event = Element::new("event")
+            .with_attribute("handle", &event_handle)
+            .with_attribute("id", &format!("E{:04}", self.events.len()))
+            .with_child(Element::new("type").with_text(gramps_type));
+        if let Some(date) = structure.child("DATE") {
+            event.push(gramps_date(date));
+        }
+        if let Some(place) = structure.child("PLAC") {
+            let place_handle = self.place(place);
+            event.push(Element::new("place").with_attribute("hlink", &place_handle));
+        }
+        if let Some(description) = description {
+            event.push(Element::new("description").with_text(description));
+        }
+        self.push_notes_and_citations(&mut event, structure);
+        self.events.push(event);
+        Some(event_handle)
+    }
+
+    fn place(&mut self, place: &Structure) -> String {
+        let title = place.value.clone().unwrap_or_default();
+        if let Some(handle) = self.place_handles.get(&title) {
+            return handle.clone();
+        }
+        let place_handle = format!("_P{}", self.places.len() + 1);
+        let mut element = Element::new("placeobj")
+            .with_attribute("handle", &place_handle)
+            .with_attribute("id", &format!("P{:04}", self.places.len()))
+            .with_attribute("type", "Unknown")
+            .with_child(Element::new("ptitle").with_text(&title))
+            .with_child(Element::new("pname").with_attribute("value", &title));
+        if let Some(map) = place.child("MAP") {
+            if let (Some(latitude), Some(longitude)) = (map.child_value("LATI"), map.child_value("LONG")) {
+                element.push(
+                    Element::new("coord")
+                        .with_attribute("long", &gramps_coordinate(longitude))
+                        .with_attribute("lat", &gramps_coordinate(latitude)),
+                );
+            }
+        }
+        self.places.push(element);
+        self.place_handles.insert(title, place_handle.clone());
+        place_handle
+    }
+
+    fn source(&mut self, record: &Structure) {
+        let mut source = object("source", record);
+        for (tag, element) in [
+            ("TITL", "stitle"),
+            ("AUTH", "sauthor"),
+            ("PUBL", "spubinfo"),
+            ("ABBR", "sabbrev"),
+        ] {
+            if let Some(value) = record.child_value(tag) {
+                source.push(Element::new(element).with_text(value));
+            }
+        }
+        self.push_notes(&mut source, record);
+        push_media_links(&mut source, record);
+        for repository in record.children_with_tag("REPO") {
+            let Some(xref) = repository.value.as_deref() else {
+                continue;
+            };
+            let mut reference = Element::new("reporef")
+                .with_attribute("hlink", &handle(xref))
+                .with_attribute("medium", "Book");
+            if let Some(call_number) = repository.child_value("CALN") {
+                reference = reference.with_attribute("callno", call_number);
+            }
+            source.push(reference);
+        }
+        self.sources.push(source);
+    }
+
+    fn media_object(&mut self, record: &Structure) {
+        let Some(file) = record.child("FILE") else {
+            return;
+        };
+        let mut object = object("object", record).with_child(
+            Element::new("file")
+                .with_attribute("src", file.value.as_deref().unwrap_or_default())
+                .with_attribute("mime", file.child_value("FORM").unwrap_or_default())
+                .with_attribute("description", file.child_value("TITL").unwrap_or_default()),
+        );
+        self.push_notes(&mut object, record);
+        self.objects.push(object);
+    }
+
+    fn repository(&mut self, record: &Structure) {
+        let mut repository = object("repository", record)
+            .with_child(Element::new("rname").with_text(record.child_value("NAME").unwrap_or_default()))
+            .with_child(Element::new("type").with_text("Library"));
+        let phone = record.child_value("PHON");
+        if let Some(address) = record.child("ADDR") {
+            repository.push(gramps_address(address, phone));
+        } else if let Some(phone) = phone {
+            repository.push(Element::new("address").with_child(Element::new("phone").with_text(phone)));
+        }
+        for (tag, url_type) in [("EMAIL", "E-mail"), ("WWW", "Web Home")] {
+            if let Some(href) = record.child_value(tag) {
+                repository.push(
+                    Element::new("url")
+                        .with_attribute("type", url_type)
+                        .with_attribute("href", href),
+                );
+            }
+        }
+        self.repositories.push(repository);
+    }
+
+    fn shared_note(&mut self, record: &Structure) {
+        let note = object("note", record)
+            .with_attribute("type", "General")
+            .with_child(Element::new("text").with_text(record.value.as_deref().unwrap_or_default()));
+        self.notes.push(note);
+    }
+
+    fn push_notes_and_citations(&mut self, element: &mut Element, structure: &Structure) {
+        self.push_notes(element, structure);
+        for source in structure.children_with_tag("SOUR") {
+            let citation_handle = format!("_C{}", self.citations.len() + 1);
+            let mut citation = Element::new("citation")
+                .with_attribute("handle", &citation_handle)
+                .with_attribute("id", &format!("C{:04}", self.citations.len()));
+            if let Some(page) = source.child_value("PAGE") {
+                citation.push(Element::new("page").with_text(page));
+            }
+            citation.push(Element::new("confidence").with_text(source.child_value("QUAY").unwrap_or("2")));
+            let source_handle = match source.value.as_deref() {
+                Some(xref) if is_pointer(xref) => handle(xref),
+                // A source given only as text becomes a source of its own.
+                value => {
+                    let source_handle = format!("_S{}", self.sources.len() + 1);
+                    self.sources.push(
+                        Element::new("source")
+                            .with_attribute("handle", &source_handle)
+                            .with_attribute("id", &format!("S{:04}", self.sources.len()))
+                            .with_child(Element::new("stitle").with_text(value.unwrap_or_default())),
+                    );
+                    source_handle
+                }
+            };
+            citation.push(Element::new("sourceref").with_attribute("hlink", &source_handle));
+            self.citations.push(citation);
+            element.push(Element::new("citationref").with_attribute("hlink", &citation_handle));
+        }
+    }
+
+    /// Turns inline notes into note objects and points at shared ones.
+    fn push_notes(&mut self, element: &mut Element, structure: &Structure) {
+        for note in structure
+            .children
+            .iter()
+            .filter(|child| child.tag == "NOTE" || child.tag == "SNOTE")
+        {
+            let text = note.value.as_deref().unwrap_or_default();
+            let note_handle = if is_pointer(text) {
+                handle(text)
+            } else {
+                // Not `_N…`, which the handles of shared notes derived from their xrefs usually are.
+                let note_handle = format!("_NOTE{}", self.notes.len() + 1);
+                self.notes.push(
+                    Element::new("note")
+                        .with_attribute("handle", &note_handle)
+                        .with_attribute("id", &format!("N{:04}", self.notes.len()))
+                        .with_attribute("type", "General")
+                        .with_child(Element::new("text").with_text(text)),
+                );
+                note_handle
+            };
+            element.push(Element::new("noteref").with_attribute("hlink", &note_handle));
+        }
+    }
+}
+
+/// Points at the media objects the record links to, the first being the one Gramps shows as its picture.
+fn push_media_links(element: &mut Element, record: &Structure) {
+    for link in record.children_with_tag("OBJE") {
+        if let Some(xref) = link.value.as_deref().filter(|value| is_pointer(value)) {
+            element.push(Element::new("objref").with_attribute("hlink", &handle(xref)));
+        }
+    }
+}
+
+/// A top-level object whose handle and id are derived from the record's xref.
+fn object(name: &str, record: &Structure) -> Element {
+    let xref = record.xref.as_deref().unwrap_or_default();
+    Element::new(name)
+        .with_attribute("handle", &handle(xref))
+        .with_attribute("id", xref.trim_matches('@'))
+}
+
+/// Gramps knows no sealing, it and other relations are written as custom child reference types.
+fn gramps_pedigree(pedigree: &Pedigree) -> String {
+    let (value, _) = pedigree.gedcom_value(GedcomVersion::V551);
+    PEDIGREES
+        .iter()
+        .find(|(_, other)| other.eq_ignore_ascii_case(&value))
+        .map(|(gramps, _)| gramps.to_string())
+        .unwrap_or_else(|| pedigree.to_string())
+}
+
+/// Writes the structured parts of an address, an address given only as text goes on the street line.
+fn gramps_address(address: &Structure, phone: Option<&str>) -> Element {
+    let mut element = Element::new("address");
+    let structured = ADDRESS_PARTS.iter().any(|(_, tag)| address.child(tag).is_some());
+    for (part, tag) in ADDRESS_PARTS {
+        let value = match structured {
+            true => address.child_value(tag).map(String::from),
+            false if tag == "ADR1" => address
+                .value
+                .as_deref()
+                .map(|value| value.lines().collect::<Vec<_>>().join(", ")),
+            false => None,
+        };
+        if let Some(value) = value {
+            element.push(Element::new(part).with_text(&value));
+        }
+    }
+    if let Some(phone) = phone {
+        element.push(Element::new("phone").with_text(phone));
+    }
+    element
+}
+
+fn handle(xref: &str) -> String {
+    format!("_{}", xref.trim_matches('@'))
+}
+
+/// Maps a GEDCOM date onto the Gramps date elements, keeping what does not fit as text.
+fn gramps_date(date: &Structure) -> Element {
+    let text = date.value.as_deref().unwrap_or_default();
+    let phrase = date.child_value("PHRASE");
+    let value = |date: &CalendarDate| {
+        let element = Element::new("dateval").with_attribute("val", &gramps_date_value(date));
+        (element, date.calendar)
+    };
+    let range = |name: &str, start: &CalendarDate, stop: &CalendarDate| {
+        let element = Element::new(name)
+            .with_attribute("start", &gramps_date_value(start))
+            .with_attribute("stop", &gramps_date_value(stop));
+        (element, start.calendar)
+    };
+    let (element, calendar) = match text.parse::<GedcomDate>() {
+        Ok(GedcomDate::Date(date)) => value(&date),
+        Ok(GedcomDate::Approximate(approximation, date)) => {
+            let (element, calendar) = value(&date);
+            let element = match approximation {
+                Approximation::About => element.with_attribute("type", "about"),
+                Approximation::Calculated => element.with_attribute("quality", "calculated"),
+                Approximation::Estimated => element.with_attribute("quality", "estimated"),
+            };
+            (element, calendar)
+        }
+        Ok(GedcomDate::Before(date)) => {
+            let (element, calendar) = value(&date);
+            (element.with_attribute("type", "before"), calendar)
+        }
+        Ok(GedcomDate::After(date)) => {
+            let (element, calendar) = value(&date);
+            (element.with_attribute("type", "after"), calendar)
+        }
+        Ok(GedcomDate::Between(start, stop)) => range("daterange", &start, &stop),
+        Ok(GedcomDate::Period(Some(start), Some(stop))) => range("datespan", &start, &stop),
+        _ => {
+            let text = phrase.filter(|_| text.is_empty()).unwrap_or(text);
+            return Element::new("datestr").with_attribute("val", text);
+        }
+    };
+    match cformat(calendar) {
+        Some(cformat) => element.with_attribute("cformat", cformat),
+        None => element,
+    }
+}
+
+/// The current date as `YYYY-MM-DD`, for the header of the database.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+    // Civil date from days since 1970-01-01, after Howard Hinnant's algorithm.
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -0,0 +1,584 @@
+use std::{collections::HashMap, io::Read};
+
+use flate2::read::GzDecoder;
+
+use crate::{
+    gedcom_io::{
+        date::{Approximation, GedcomDate},
+        diagnostics::Diagnostic,
+        parser::Structure,
+    },
+    relationship::RelationshipKind,
+};
+
+use super::{
+    calendar, calendar_date, event_tag, gedcom_coordinate, gedcom_quality, xml::Element, ADDRESS_PARTS,
+    DESCRIBED_BY_VALUE, PEDIGREES,
+};
+
+/// Places are nested by `placeref`, this is deeper than any real jurisdiction hierarchy.
+const MAX_PLACE_DEPTH: usize = 16;
+
+/// The top-level objects of a Gramps database indexed by their handle.
+struct Database<'a> {
+    events: HashMap<&'a str, &'a Element>,
+    places: HashMap<&'a str, &'a Element>,
+    citations: HashMap<&'a str, &'a Element>,
+    notes: HashMap<&'a str, &'a Element>,
+    families: HashMap<&'a str, &'a Element>,
+    /// The GEDCOM xref every person, family, source, repository, media object and shared note is written with.
+    xrefs: HashMap<&'a str, String>,
+}
+
+/// Reads a Gramps XML database, gzip-compressed or not, into GEDCOM records that `import_tree` understands.
+pub fn read_gramps(bytes: &[u8]) -> Result<(Vec<Structure>, Vec<Diagnostic>), String> {
+    let mut xml = String::new();
+    // Gramps compresses its files by default but also writes them plain.
+    let read = if bytes.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bytes).read_to_string(&mut xml)
+    } else {
+        bytes.as_ref().read_to_string(&mut xml)
+    };
+    read.map_err(|error| format!("Could not read Gramps file: {}", error))?;
+    let root = Element::parse(&xml).map_err(|error| format!("Could not parse Gramps XML: {}", error))?;
+    if root.name != "database" {
+        return Err(format!("Not a Gramps database, the root element is <{}>", root.name));
+    }
+
+    let mut diagnostics = vec![];
+    let section = |name: &str| {
+        root.child(name)
+            .map(|section| section.children.as_slice())
+            .unwrap_or_default()
+    };
+    // Gramps keeps every note as an object of its own, only those referenced more than once become shared notes.
+    let mut note_references: HashMap<&str, usize> = HashMap::new();
+    count_note_references(&root, &mut note_references);
+    let shared_notes: Vec<&Element> = section("notes")
+        .iter()
+        .filter(|note| {
+            note.attribute("handle")
+                .is_some_and(|handle| note_references.get(handle).is_some_and(|count| *count > 1))
+        })
+        .collect();
+
+    let mut xrefs: HashMap<&str, String> = HashMap::new();
+    for (prefix, objects) in [
+        ("I", section("people").iter().collect::<Vec<_>>()),
+        ("F", section("families").iter().collect()),
+        ("S", section("sources").iter().collect()),
+        ("R", section("repositories").iter().collect()),
+        ("O", section("objects").iter().collect()),
+        ("N", shared_notes.clone()),
+    ] {
+        for (index, object) in objects.into_iter().enumerate() {
+            let Some(handle) = object.attribute("handle") else {
+                continue;
+            };
+            let id = object
+                .attribute("id")
+                .map(String::from)
+                .unwrap_or_else(|| format!("{}{}", prefix, index + 1));
+            xrefs.insert(handle, format!("@{}@", id));
+        }
+    }
+
+    let database = Database {
+        events: by_handle(section("events")),
+        places: by_handle(section("places")),
+        citations: by_handle(section("citations")),
+        notes: by_handle(section("notes")),
+        families: by_handle(section("families")),
+        xrefs,
+    };
+
+    let mut records = vec![];
+    for person in section("people") {
+        records.push(database.individual(person, &mut diagnostics));
+    }
+    for family in section("families") {
+        records.push(database.family(family, &mut diagnostics));
+    }
+    for source in section("sources") {
+        records.push(database.source(source));
+    }
+    for repository in section("repositories") {
+        records.push(database.repository(repository));
+    }
+    for object in section("objects") {
+        records.push(database.media_object(object));
+    }
+    for note in shared_notes {
+        records.push(database.shared_note(note));
+    }
+    Ok((records, diagnostics))
+}
+
+fn count_note_references<'a>(element: &'a Element, counts: &mut HashMap<&'a str, usize>) {
+    for child in element.children.iter() {
+        match child.attribute("hlink").filter(|_| child.name == "noteref") {
+            Some(handle) => *counts.entry(handle).or_insert(0) += 1,
+            None => count_note_references(child, counts),
+        }
+    }
+}
+
+impl<'a> Database<'a> {
+    fn xref(&self, element: &Element) -> Option<String> {
+        element
+            .attribute("handle")
+            .and_then(|handle| self.xrefs.get(handle))
+            .cloned()
+    }
+
+    fn pointer(&self, element: &Element, diagnostics: &mut Vec<Diagnostic>) -> Option<String> {
+        let handle = element.attribute("hlink")?;
+        let xref = self.xrefs.get(handle).cloned();
+        if xref.is_none() {
+            diagnostics.push(Diagnostic::warning(
+                None,
+                format!("<{}> refers to {} which is not in the file", element.name, handle),
+            ));
+        }
+        xref
+    }
+
+    fn individual(&self, person: &Element, diagnostics: &mut Vec<Diagnostic>) -> Structure {
+        let mut record = new_structure("INDI", None);
+        record.xref = self.xref(person);
+
+        for name in person.children_named("name") {
+            record.children.push(self.name(name));
+        }
+        if let Some(gender) = person.child_text("gender") {
+            record.children.push(new_structure("SEX", Some(gender)));
+        }
+        for event_ref in person.children_named("eventref") {
+            match event_ref.attribute("role") {
+                None | Some("Primary") => {
+                    if let Some(event) = self.event(event_ref, diagnostics) {
+                        record.children.push(event);
+                    }
+                }
+                Some(role) => diagnostics.push(Diagnostic::warning(
+                    None,
+                    format!(
+                        "Event in role {} of {} skipped, only primary events are imported",
+                        role,
+                        record.xref.as_deref().unwrap_or_default()
+                    ),
+                )),
+            }
+        }
+        for child_of in person.children_named("childof") {
+            let Some(family_xref) = self.pointer(child_of, diagnostics) else {
+                continue;
+            };
+            let mut link = new_structure("FAMC", Some(&family_xref));
+            if let Some(pedigree) = self.pedigree(child_of.attribute("hlink"), person.attribute("handle")) {
+                link.children.push(new_structure("PEDI", Some(pedigree)));
+            }
+            record.children.push(link);
+        }
+        for parent_in in person.children_named("parentin") {
+            if let Some(family_xref) = self.pointer(parent_in, diagnostics) {
+                record.children.push(new_structure("FAMS", Some(&family_xref)));
+            }
+        }
+        // Gramps names the relation freely, as the `RELA` of 5.5.x does.
+        for person_ref in person.children_named("personref") {
+            if let Some(xref) = self.pointer(person_ref, diagnostics) {
+                let mut association = new_structure("ASSO", Some(&xref));
+                if let Some(relation) = person_ref.attribute("rel") {
+                    association.children.push(new_structure("RELA", Some(relation)));
+                }
+                self.push_notes_and_citations(&mut association, person_ref, diagnostics);
+                record.children.push(association);
+            }
+        }
+        self.push_media_links(&mut record, person, diagnostics);
+        self.push_notes_and_citations(&mut record, person, diagnostics);
+        record
+    }
+
+    fn name(&self, name: &Element) -> Structure {
+        let given = name.child_text("first");
+        let surname = name
+            .children_named("surname")
+            .find(|surname| surname.attribute("prim") != Some("0"))
+            .or_else(|| name.child("surname"));
+        let surname_text = surname
+            .map(|surname| surname.text.as_str())
+            .filter(|text| !text.is_empty());
+        let surname_prefix = surname.and_then(|surname| surname.attribute("prefix"));
+        let suffix = name.child_text("suffix");
+
+        let full_surname = [surname_prefix, surname_text]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let title = name.child_text("title");
+        let full_surname = format!("/{}/", full_surname);
+        let value = [title, given, Some(full_surname.as_str()), suffix]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        let mut structure = new_structure("NAME", Some(&value));
+        for (tag, part) in [
+            ("NPFX", title),
+            ("GIVN", given),
+            ("NICK", name.child_text("nick")),
+            ("SPFX", surname_prefix),
+            ("SURN", surname_text),
+            ("NSFX", suffix),
+        ] {
+            if let Some(part) = part {
+                structure.children.push(new_structure(tag, Some(part)));
+            }
+        }
+        // Birth names are what Gramps takes a name for unless told otherwise, custom types come as their own name.
+        let name_type = match name.attribute("type") {
+            Some("Also Known As") => Some("aka"),
+            Some("Married Name") => Some("married"),
+            None | Some("Birth Name") | Some("Unknown") => None,
+            Some(custom) => Some(custom),
+        };
+        if let Some(name_type) = name_type {
+            structure.children.push(new_structure("TYPE", Some(name_type)));
+        }
+        structure
+    }
+
+    /// The relationship of a child to a family, taken from the father's side unless it is a birth.
+    fn pedigree(&self, family_handle: Option<&str>, child_handle: Option<&str>) -> Option<&'a str> {
+        let family = self.families.get(family_handle?)?;
+        let child_ref = family
+            .children_named("childref")
+            .find(|child_ref| child_ref.attribute("hlink") == child_handle)?;
+        let relation = [child_ref.attribute("frel"), child_ref.attribute("mrel")]
+            .into_iter()
+            .flatten()
+            .find(|relation| *relation != "Birth")?;
+        // An unknown relation says no more than a missing one does.
+        match PEDIGREES.iter().find(|(gramps, _)| *gramps == relation) {
+            Some((_, pedigree)) => Some(pedigree),
+            None if relation == "Unknown" => None,
+            None => Some(relation),
+        }
+    }
+
+    fn family(&self, family: &Element, diagnostics: &mut Vec<Diagnostic>) -> Structure {
+        let mut record = new_structure("FAM", None);
+        record.xref = self.xref(family);
+        for (element, tag) in [("father", "HUSB"), ("mother", "WIFE")] {
+            if let Some(xref) = family
+                .child(element)
+                .and_then(|partner| self.pointer(partner, diagnostics))
+            {
+                record.children.push(new_structure(tag, Some(&xref)));
+            }
+        }
+        for child_ref in family.children_named("childref") {
+            if let Some(xref) = self.pointer(child_ref, diagnostics) {
+                record.children.push(new_structure("CHIL", Some(&xref)));
+            }
+        }
+        for event_ref in family.children_named("eventref") {
+            if let Some(event) = self.event(event_ref, diagnostics) {
+                record.children.push(event);
+            }
+        }
+        let relationship = family.child("rel").and_then(|rel| rel.attribute("type"));
+        add_relationship_kind(&mut record, relationship);
+        self.push_notes_and_citations(&mut record, family, diagnostics);
+        record
+    }
+
+    fn event(&self, event_ref: &Element, diagnostics: &mut Vec<Diagnostic>) -> Option<Structure> {
+        let handle = event_ref.attribute("hlink")?;
+        let Some(event) = self.events.get(handle) else {
+            diagnostics.push(Diagnostic::warning(
+                None,
+                format!("Event {} is not in the file", handle),
+            ));
+            return None;
+        };
+        let gramps_type = event.child_text("type").unwrap_or("Event");
+        let description = event.child_text("description");
+
+        let mut structure = match event_tag(gramps_type) {
+            Some(tag) if DESCRIBED_BY_VALUE.contains(&tag) => new_structure(tag, description),
+            Some(tag) => {
+                let mut structure = new_structure(tag, None);
+                if let Some(description) = description {
+                    structure.children.push(new_structure("TYPE", Some(description)));
+                }
+                structure
+            }
+            None => {
+                let mut structure = new_structure("EVEN", description);
+                structure.children.push(new_structure("TYPE", Some(gramps_type)));
+                structure
+            }
+        };
+        if let Some(date) = gedcom_date(event) {
+            structure.children.push(new_structure("DATE", Some(&date)));
+        }
+        if let Some(place) = event
+            .child("place")
+            .and_then(|place| place.attribute("hlink"))
+            .and_then(|handle| self.places.get(handle))
+        {
+            structure.children.push(self.place(place));
+        }
+        self.push_notes_and_citations(&mut structure, event, diagnostics);
+        Some(structure)
+    }
+
+    fn place(&self, place: &Element) -> Structure {
+        let mut names = vec![];
+        let mut current = Some(place);
+        // Older databases only have a title, newer ones a name per level of the hierarchy.
+        while let Some(level) = current.filter(|_| names.len() < MAX_PLACE_DEPTH) {
+            if let Some(name) = level.child("pname").and_then(|name| name.attribute("value")) {
+                names.push(name);
+            }
+            current = level
+                .child("placeref")
+                .and_then(|parent| parent.attribute("hlink"))
+                .and_then(|handle| self.places.get(handle))
+                .copied();
+        }
+        let title = match place.child_text("ptitle") {
+            Some(title) => title.to_string(),
+            None => names.join(", "),
+        };
+
+        let mut structure = new_structure("PLAC", Some(&title));
+        if let Some(coordinates) = place.child("coord") {
+            let latitude = coordinates
+                .attribute("lat")
+                .and_then(|lat| gedcom_coordinate(lat, 'N', 'S'));
+            let longitude = coordinates
+                .attribute("long")
+                .and_then(|long| gedcom_coordinate(long, 'E', 'W'));
+            if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+                let mut map = new_structure("MAP", None);
+                map.children.push(new_structure("LATI", Some(&latitude)));
+                map.children.push(new_structure("LONG", Some(&longitude)));
+                structure.children.push(map);
+            }
+        }
+        structure
+    }
+
+    fn source(&self, source: &Element) -> Structure {
+        let mut record = new_structure("SOUR", None);
+        record.xref = self.xref(source);
+        for (element, tag) in [
+            ("stitle", "TITL"),
+            ("sauthor", "AUTH"),
+            ("spubinfo", "PUBL"),
+            ("sabbrev", "ABBR"),
+        ] {
+            if let Some(text) = source.child_text(element) {
+                record.children.push(new_structure(tag, Some(text)));
+            }
+        }
+        for repository_ref in source.children_named("reporef") {
+            let Some(xref) = repository_ref
+                .attribute("hlink")
+                .and_then(|handle| self.xrefs.get(handle))
+            else {
+                continue;
+            };
+            let mut link = new_structure("REPO", Some(xref));
+            if let Some(call_number) = repository_ref.attribute("callno").filter(|number| !number.is_empty()) {
+                link.children.push(new_structure("CALN", Some(call_number)));
+            }
+            record.children.push(link);
+        }
+        self.push_media_links(&mut record, source, &mut vec![]);
+        self.push_notes_and_citations(&mut record, source, &mut vec![]);
+        record
+    }
+
+    fn media_object(&self, object: &Element) -> Structure {
+        let mut record = new_structure("OBJE", None);
+        record.xref = self.xref(object);
+        if let Some(file) = object.child("file") {
+            let mut structure = new_structure("FILE", file.attribute("src"));
+            if let Some(media_type) = file.attribute("mime").filter(|media_type| !media_type.is_empty()) {
+                structure.children.push(new_structure("FORM", Some(media_type)));
+            }
+            if let Some(title) = file.attribute("description").filter(|title| !title.is_empty()) {
+                structure.children.push(new_structure("TITL", Some(title)));
+            }
+            record.children.push(structure);
+        }
+        self.push_notes_and_citations(&mut record, object, &mut vec![]);
+        record
+    }
+
+    /// Turns every `objref` into an `OBJE` pointer.
+    fn push_media_links(&self, structure: &mut Structure, element: &Element, diagnostics: &mut Vec<Diagnostic>) {
+        for object_ref in element.children_named("objref") {
+            if let Some(xref) = self.pointer(object_ref, diagnostics) {
+                structure.children.push(new_structure("OBJE", Some(&xref)));
+            }
+        }
+    }
+
+    fn repository(&self, repository: &Element) -> Structure {
+        let mut record = new_structure("REPO", None);
+        record.xref = self.xref(repository);
+        if let Some(name) = repository.child_text("rname") {
+            record.children.push(new_structure("NAME", Some(name)));
+        }
+        if let Some(address) = repository.child("address") {
+            let parts: Vec<(&str, &str)> = ADDRESS_PARTS
+                .iter()
+                .filter_map(|(part, tag)| Some((*tag, address.child_text(part)?)))
+                .collect();
+            if !parts.is_empty() {
+                let lines: Vec<&str> = parts.iter().map(|(_, value)| *value).collect();
+                let mut structure = new_structure("ADDR", Some(&lines.join("\n")));
+                for (tag, value) in parts {
+                    structure.children.push(new_structure(tag, Some(value)));
+                }
+                record.children.push(structure);
+            }
+            if let Some(phone) = address.child_text("phone") {
+                record.children.push(new_structure("PHON", Some(phone)));
+            }
+        }
+        for url in repository.children_named("url") {
+            let Some(href) = url.attribute("href").filter(|href| !href.is_empty()) else {
+                continue;
+            };
+            let tag = match url.attribute("type") {
+                Some("E-mail") => "EMAIL",
+                _ => "WWW",
+            };
+            record.children.push(new_structure(tag, Some(href)));
+        }
+        record
+    }
+
+    fn shared_note(&self, note: &Element) -> Structure {
+        let mut record = new_structure("NOTE", note.child_text("text"));
+        record.xref = self.xref(note);
+        record
+    }
+
+    /// Points at the shared notes and inlines the others as `NOTE`, and turns each citation into a `SOUR` pointer
+    /// with its page.
+    fn push_notes_and_citations(
+        &self,
+        structure: &mut Structure,
+        element: &Element,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for note in element
+            .children_named("noteref")
+            .filter_map(|note_ref| self.notes.get(note_ref.attribute("hlink")?))
+        {
+            if let Some(xref) = self.xref(note) {
+                structure.children.push(new_structure("NOTE", Some(&xref)));
+            } else if let Some(text) = note.child_text("text") {
+                structure.children.push(new_structure("NOTE", Some(text)));
+            }
+        }
+        for citation in element
+            .children_named("citationref")
+            .filter_map(|citation_ref| self.citations.get(citation_ref.attribute("hlink")?))
+        {
+            let Some(source_xref) = citation
+                .child("sourceref")
+                .and_then(|source_ref| self.pointer(source_ref, diagnostics))
+            else {
+                continue;
+            };
+            let mut source = new_structure("SOUR", Some(&source_xref));
+            if let Some(page) = citation.child_text("page") {
+                source.children.push(new_structure("PAGE", Some(page)));
+            }
+            if let Some(quality) = citation.child_text("confidence").and_then(gedcom_quality) {
+                source.children.push(new_structure("QUAY", Some(quality)));
+            }
+            structure.children.push(source);
+        }
+    }
+}
+
+/// GEDCOM only tells the kind of a relationship through the events of the family, so the Gramps relationship
+/// type becomes the `TYPE` of its marriages or the partnership marker.
+fn add_relationship_kind(record: &mut Structure, relationship: Option<&str>) {
+    let marriage_type = match relationship {
+        Some("Married") => None,
+        Some("Civil Union") => Some(RelationshipKind::CIVIL_UNION_TYPE),
+        Some("Unmarried") => {
+            let mut marker = new_structure("EVEN", None);
+            marker
+                .children
+                .push(new_structure("TYPE", Some(RelationshipKind::PARTNERSHIP_TYPE)));
+            record.children.push(marker);
+            return;
+        }
+        _ => return,
+    };
+    if record.child("MARR").is_none() {
+        record.children.push(new_structure("MARR", Some("Y")));
+    }
+    let Some(marriage_type) = marriage_type else {
+        return;
+    };
+    for marriage in record.children.iter_mut().filter(|structure| structure.tag == "MARR") {
+        if marriage.child("TYPE").is_none() {
+            marriage.children.push(new_structure("TYPE", Some(marriage_type)));
+        }
+    }
+}
+
+fn by_handle(objects: &[Element]) -> HashMap<&str, &Element> {
+    objects
+        .iter()
+        .filter_map(|object| object.attribute("handle").map(|handle| (handle, object)))
+        .collect()
+}
+
+/// Gramps keeps a date in one of `dateval`, `daterange`, `datespan` or free text in `datestr`.
+fn gedcom_date(event: &Element) -> Option<String> {
+    let date = |date: &Element, attribute: &str| {
+        calendar_date(date.attribute(attribute)?, calendar(date.attribute("cformat")))
+    };
+    let value = if let Some(element) = event.child("dateval") {
+        let value = date(element, "val")?;
+        match (element.attribute("type"), element.attribute("quality")) {
+            (Some("about"), _) => GedcomDate::Approximate(Approximation::About, value),
+            (Some("before"), _) => GedcomDate::Before(value),
+            (Some("after"), _) => GedcomDate::After(value),
+            (_, Some("estimated")) => GedcomDate::Approximate(Approximation::Estimated, value),
+            (_, Some("calculated")) => GedcomDate::Approximate(Approximation::Calculated, value),
+            _ => GedcomDate::Date(value),
+        }
+    } else if let Some(element) = event.child("daterange") {
+        GedcomDate::Between(date(element, "start")?, date(element, "stop")?)
+    } else if let Some(element) = event.child("datespan") {
+        GedcomDate::Period(Some(date(element, "start")?), Some(date(element, "stop")?))
+    } else {
+        GedcomDate::Phrase(event.child("datestr")?.attribute("val")?.to_string())
+    };
+    Some(value.to_string())
+}
+
+fn new_structure(tag: &str, value: Option<&str>) -> Structure {
+    Structure {
+        tag: tag.to_string(),
+        value: value.map(String::from),
+        ..Default::default()
+    }
+}
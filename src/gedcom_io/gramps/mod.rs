@@ -0,0 +1,151 @@
+pub mod export;
+pub mod import;
+mod xml;
+
+use super::date::{Calendar, CalendarDate};
+
+/// Gramps event types and the GEDCOM tags they correspond to. Other types become `EVEN` with a `TYPE`.
+const EVENT_TYPES: [(&str, &str); 33] = [
+    ("Birth", "BIRT"),
+    ("Death", "DEAT"),
+    ("Burial", "BURI"),
+    ("Cremation", "CREM"),
+    ("Baptism", "BAPM"),
+    ("Christening", "CHR"),
+    ("Adult Christening", "CHRA"),
+    ("Confirmation", "CONF"),
+    ("First Communion", "FCOM"),
+    ("Bar Mitzvah", "BARM"),
+    ("Bas Mitzvah", "BASM"),
+    ("Blessing", "BLES"),
+    ("Adopted", "ADOP"),
+    ("Census", "CENS"),
+    ("Emigration", "EMIG"),
+    ("Immigration", "IMMI"),
+    ("Naturalization", "NATU"),
+    ("Graduation", "GRAD"),
+    ("Retirement", "RETI"),
+    ("Probate", "PROB"),
+    ("Will", "WILL"),
+    ("Residence", "RESI"),
+    ("Occupation", "OCCU"),
+    ("Education", "EDUC"),
+    ("Religion", "RELI"),
+    ("Nobility Title", "TITL"),
+    ("Marriage", "MARR"),
+    ("Marriage Banns", "MARB"),
+    ("Marriage Contract", "MARC"),
+    ("Marriage License", "MARL"),
+    ("Engagement", "ENGA"),
+    ("Divorce", "DIV"),
+    ("Annulment", "ANUL"),
+];
+
+/// Events whose GEDCOM value is their description, e.g. `1 OCCU Farmer`.
+const DESCRIBED_BY_VALUE: [&str; 4] = ["OCCU", "EDUC", "RELI", "TITL"];
+
+/// Gramps names of the calendars besides the Gregorian, given as the `cformat` of a date.
+const CALENDARS: [(&str, Calendar); 3] = [
+    ("Julian", Calendar::Julian),
+    ("Hebrew", Calendar::Hebrew),
+    ("French Republican", Calendar::FrenchRepublican),
+];
+
+/// Gramps child reference types and the `PEDI` values they correspond to, others are carried over as they are named.
+const PEDIGREES: [(&str, &str); 4] = [
+    ("Birth", "birth"),
+    ("Adopted", "adopted"),
+    ("Foster", "foster"),
+    ("Stepchild", "step"),
+];
+
+/// Parts of a Gramps address and the parts of a GEDCOM `ADDR` they correspond to, in the order Gramps writes them.
+const ADDRESS_PARTS: [(&str, &str); 6] = [
+    ("street", "ADR1"),
+    ("locality", "ADR2"),
+    ("city", "CITY"),
+    ("state", "STAE"),
+    ("country", "CTRY"),
+    ("postal", "POST"),
+];
+
+fn event_tag(gramps_type: &str) -> Option<&'static str> {
+    EVENT_TYPES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(gramps_type))
+        .map(|(_, tag)| *tag)
+}
+
+fn event_type(tag: &str) -> Option<&'static str> {
+    EVENT_TYPES
+        .iter()
+        .find(|(_, other)| *other == tag)
+        .map(|(name, _)| *name)
+}
+
+/// Reads a Gramps `1822-10-02`, `1822-10` or `1822`, where a zero stands for an unknown month or day.
+fn calendar_date(value: &str, calendar: Calendar) -> Option<CalendarDate> {
+    let mut parts = value.split('-');
+    let year = parts.next()?.parse().ok().filter(|year| *year > 0)?;
+    let month = parts
+        .next()
+        .and_then(|month| month.parse().ok())
+        .filter(|month| *month > 0);
+    let day = parts.next().and_then(|day| day.parse().ok()).filter(|day| *day > 0);
+    CalendarDate::new(calendar, year, month, day)
+}
+
+/// Writes a date in the Gramps `1822-10-02` form. Only the old-style year of a dual year is kept.
+fn gramps_date_value(date: &CalendarDate) -> String {
+    match (date.month, date.day) {
+        (Some(month), Some(day)) => format!("{:04}-{:02}-{:02}", date.year, month, day),
+        (Some(month), None) => format!("{:04}-{:02}", date.year, month),
+        _ => format!("{:04}", date.year),
+    }
+}
+
+fn calendar(cformat: Option<&str>) -> Calendar {
+    CALENDARS
+        .iter()
+        .find(|(name, _)| Some(*name) == cformat)
+        .map(|(_, calendar)| *calendar)
+        .unwrap_or(Calendar::Gregorian)
+}
+
+fn cformat(calendar: Calendar) -> Option<&'static str> {
+    CALENDARS
+        .iter()
+        .find(|(_, other)| *other == calendar)
+        .map(|(name, _)| *name)
+}
+
+/// Gramps writes coordinates as signed decimals, GEDCOM prefixes them with a hemisphere instead.
+fn gedcom_coordinate(value: &str, positive: char, negative: char) -> Option<String> {
+    let value = value.trim();
+    if value.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some(value.to_uppercase());
+    }
+    let number: f64 = value.parse().ok()?;
+    let hemisphere = if number < 0.0 { negative } else { positive };
+    Some(format!("{}{}", hemisphere, value.trim_start_matches(['-', '+'])))
+}
+
+fn gramps_coordinate(value: &str) -> String {
+    let value = value.trim();
+    match value.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some('N') | Some('E') => value[1..].to_string(),
+        Some('S') | Some('W') => format!("-{}", &value[1..]),
+        _ => value.to_string(),
+    }
+}
+
+/// Gramps rates citations from 0 (very low) to 4 (very high), GEDCOM `QUAY` goes from 0 to 3.
+fn gedcom_quality(confidence: &str) -> Option<&'static str> {
+    match confidence {
+        "0" => Some("0"),
+        "1" => Some("1"),
+        "2" => Some("2"),
+        "3" | "4" => Some("3"),
+        _ => None,
+    }
+}
@@ -0,0 +1,135 @@
+use quick_xml::{escape::escape, events::Event, Reader};
+
+/// A minimal XML element tree, enough to read and write a Gramps database in one piece.
+#[derive(Debug, Clone, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: &str) -> Self {
+        self.attributes.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    pub fn with_child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn push(&mut self, child: Element) {
+        self.children.push(child);
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// The text of the named child, ignoring children that are empty.
+    pub fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name)
+            .map(|child| child.text.as_str())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Parses a document into its root element. Namespace prefixes are dropped from element and attribute names.
+    pub fn parse(xml: &str) -> Result<Element, quick_xml::Error> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+        let mut open: Vec<Element> = vec![];
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => open.push(Self::from_start(&start)?),
+                Event::Empty(start) => {
+                    let element = Self::from_start(&start)?;
+                    match open.last_mut() {
+                        Some(parent) => parent.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::End(_) => {
+                    let Some(element) = open.pop() else { continue };
+                    match open.last_mut() {
+                        Some(parent) => parent.push(element),
+                        None => return Ok(element),
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = open.last_mut() {
+                        element.text.push_str(&text.unescape()?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(element) = open.last_mut() {
+                        element.text.push_str(&String::from_utf8_lossy(&data));
+                    }
+                }
+                Event::Eof => return Err(quick_xml::Error::Syntax(quick_xml::errors::SyntaxError::UnclosedTag)),
+                _ => {}
+            }
+        }
+    }
+
+    fn from_start(start: &quick_xml::events::BytesStart) -> Result<Element, quick_xml::Error> {
+        let mut element = Element::new(&String::from_utf8_lossy(start.local_name().as_ref()));
+        for attribute in start.attributes() {
+            let attribute = attribute?;
+            element.attributes.push((
+                String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+                attribute.unescape_value()?.to_string(),
+            ));
+        }
+        Ok(element)
+    }
+
+    /// Appends the element as indented XML.
+    pub fn write(&self, out: &mut String, depth: usize) {
+        out.push_str(&"  ".repeat(depth));
+        out.push('<');
+        out.push_str(&self.name);
+        for (name, value) in self.attributes.iter() {
+            out.push_str(&format!(" {}=\"{}\"", name, escape(value.as_str())));
+        }
+        if self.children.is_empty() && self.text.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push('>');
+        if self.children.is_empty() {
+            out.push_str(&escape(self.text.as_str()));
+        } else {
+            out.push('\n');
+            for child in self.children.iter() {
+                child.write(out, depth + 1);
+            }
+            out.push_str(&"  ".repeat(depth));
+        }
+        out.push_str(&format!("</{}>\n", self.name));
+    }
+}
@@ -0,0 +1,692 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use graph::node::GraphNodeTrait;
+use iced::{Point, Vector};
+
+use crate::{
+    association::{Association, AssociationRole},
+    family::{ChildLink, Families, Family, Pedigree},
+    genealogical_node::{GenealogicalNode, Sex},
+    life_event::{EventType, LifeEvent},
+    media::{Media, MediaLink, MediaObject},
+    name::PersonalName,
+    note::{Note, NoteText, Notes, SharedNote},
+    place::Places,
+    relationship::{RelationshipEvent, RelationshipEventType, RelationshipKind},
+    repository::{Repository, RepositoryLink},
+    source::{Citation, Source, Sources},
+};
+
+use super::{
+    date::GedcomDate,
+    diagnostics::Diagnostic,
+    encoding::decode,
+    gedzip::read_gedzip,
+    gramps::import::read_gramps,
+    parser::{is_pointer, parse, Structure},
+    preserved::PreservedRecords,
+    FileFormat,
+};
+
+pub(super) const NODE_SPACING: Vector = Vector::new(192.0, 192.0);
+
+/// Nodes and edges built from a parsed GEDCOM file, ready to be inserted into a `Graph`.
+///
+/// Whatever could not be read is described in `diagnostics` instead of aborting the import.
+#[derive(Default)]
+pub struct ImportedTree {
+    pub nodes: Vec<GenealogicalNode>,
+    pub edges: Vec<(u128, u128)>,
+    pub preserved: PreservedRecords,
+    pub families: Families,
+    pub places: Places,
+    pub sources: Sources,
+    pub media: Media,
+    pub notes: Notes,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// The partner and child pointers of a `FAM` record.
+struct FamilyPointers {
+    line: usize,
+    partners: Vec<String>,
+    children: Vec<String>,
+}
+
+impl FamilyPointers {
+    fn from_record(record: &Structure) -> Self {
+        let pointers = |tag| {
+            record
+                .children_with_tag(tag)
+                .filter_map(|child| child.value.clone())
+                .collect::<Vec<String>>()
+        };
+        Self {
+            line: record.line,
+            partners: [pointers("HUSB"), pointers("WIFE")].concat(),
+            children: pointers("CHIL"),
+        }
+    }
+}
+
+/// Reads, decodes and imports a `.ged`, `.gdz` or `.gramps` file.
+pub fn import_file(path: &Path, origin: Point) -> ImportedTree {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            return ImportedTree {
+                diagnostics: vec![Diagnostic::error(
+                    None,
+                    format!("Could not read {}: {}", path.display(), error),
+                )],
+                ..Default::default()
+            }
+        }
+    };
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    if FileFormat::Gramps.matches(path) {
+        return match read_gramps(&bytes) {
+            Ok((records, mut diagnostics)) => {
+                let mut tree = import_tree(&records, origin);
+                tree.media.set_directory(directory);
+                diagnostics.append(&mut tree.diagnostics);
+                tree.diagnostics = diagnostics;
+                tree
+            }
+            Err(error) => ImportedTree {
+                diagnostics: vec![Diagnostic::error(None, error)],
+                ..Default::default()
+            },
+        };
+    }
+    let (bytes, directory) = if FileFormat::Gedzip.matches(path) {
+        let directory = extraction_directory(path);
+        match read_gedzip(&bytes, &directory) {
+            Ok(bytes) => (bytes, directory),
+            Err(error) => {
+                return ImportedTree {
+                    diagnostics: vec![Diagnostic::error(
+                        None,
+                        format!("Could not open GEDZIP archive: {}", error),
+                    )],
+                    ..Default::default()
+                }
+            }
+        }
+    } else {
+        (bytes, directory)
+    };
+
+    let (content, mut diagnostics) = decode(&bytes);
+    let (records, parse_diagnostics) = parse(&content);
+    diagnostics.extend(parse_diagnostics);
+
+    let mut tree = import_tree(&records, origin);
+    tree.media.set_directory(directory);
+    diagnostics.append(&mut tree.diagnostics);
+    tree.diagnostics = diagnostics;
+    tree
+}
+
+/// Where the media files bundled in a GEDZIP archive are unpacked to, a directory of their own for every archive.
+fn extraction_directory(archive: &Path) -> PathBuf {
+    let name = archive.file_stem().unwrap_or_default();
+    dirs::cache_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join("iced-graph")
+        .join("gedzip")
+        .join(name)
+}
+
+/// Builds one populated node per `INDI` record and connects them following the `FAM` records.
+///
+/// Every generation gets its own row below `origin` so that parents are placed above their children.
+pub fn import_tree(records: &[Structure], origin: Point) -> ImportedTree {
+    let mut diagnostics = vec![];
+    let mut places = Places::default();
+    let mut media = Media::default();
+    for record in records
+        .iter()
+        .filter(|record| record.tag == "OBJE" && record.xref.is_some())
+    {
+        if let Some(object) = MediaObject::from_structure(record) {
+            media.insert(object);
+        }
+    }
+    let mut notes = Notes::default();
+    for record in records.iter().filter(|record| is_shared_note(record)) {
+        notes.insert(SharedNote::from_record(record));
+    }
+    let mut sources = Sources::default();
+    for record in records
+        .iter()
+        .filter(|record| record.tag == "REPO" && record.xref.is_some())
+    {
+        sources.insert_repository(Repository::from_record(record));
+    }
+    for record in records
+        .iter()
+        .filter(|record| record.tag == "SOUR" && record.xref.is_some())
+    {
+        let mut source = Source::from_record(record);
+        source.repositories = repository_links(record, &sources, &mut diagnostics);
+        (source.media, _) = media_links(record, &mut media, &mut diagnostics);
+        source.notes = attached_notes(record, &notes, &mut diagnostics);
+        sources.insert(source);
+    }
+    let individuals: Vec<&Structure> = records.iter().filter(|record| record.tag == "INDI").collect();
+    let pointers: Vec<FamilyPointers> = records
+        .iter()
+        .filter(|record| record.tag == "FAM")
+        .map(FamilyPointers::from_record)
+        .collect();
+
+    let generations = generations(&pointers, individuals.len());
+    let mut row_lengths: HashMap<usize, usize> = HashMap::new();
+
+    let mut nodes: Vec<GenealogicalNode> = individuals
+        .iter()
+        .map(|individual| {
+            let generation = individual
+                .xref
+                .as_ref()
+                .and_then(|xref| generations.get(xref))
+                .copied()
+                .unwrap_or(0);
+            let column = row_lengths.entry(generation).or_insert(0);
+            let anchor = origin + Vector::new(*column as f32 * NODE_SPACING.x, generation as f32 * NODE_SPACING.y);
+            *column += 1;
+            individual_to_node(
+                individual,
+                anchor,
+                &mut places,
+                &sources,
+                &mut media,
+                &notes,
+                &mut diagnostics,
+            )
+        })
+        .collect();
+
+    let ids_by_xref: HashMap<String, u128> = nodes
+        .iter()
+        .filter_map(|node| node.xref().map(|xref| (xref, node.id())))
+        .collect();
+    for node in nodes.iter_mut() {
+        read_associations(node, &ids_by_xref, &mut diagnostics);
+    }
+    for family in pointers.iter() {
+        for xref in family.partners.iter().chain(family.children.iter()) {
+            if !ids_by_xref.contains_key(xref) {
+                diagnostics.push(Diagnostic::warning(
+                    Some(family.line),
+                    format!("Family refers to {} which is not in the file", xref),
+                ));
+            }
+        }
+    }
+
+    // A child listed in several families of the same parent would otherwise be linked to that parent twice.
+    let mut seen = HashSet::new();
+    let edges: Vec<(u128, u128)> = pointers
+        .iter()
+        .flat_map(|family| family_edges(family, &ids_by_xref))
+        .filter(|edge| seen.insert(*edge))
+        .collect();
+
+    let individuals_by_xref: HashMap<&str, &Structure> = individuals
+        .iter()
+        .filter_map(|individual| Some((individual.xref.as_deref()?, *individual)))
+        .collect();
+    let mut preserved = PreservedRecords::default();
+    let mut families = Families::default();
+    for record in records.iter() {
+        match record.tag.as_str() {
+            "HEAD" => preserved.header = Some(record.clone()),
+            "INDI" | "TRLR" => {}
+            "SOUR" | "REPO" if record.xref.is_some() => {}
+            "OBJE" if record.xref.as_deref().and_then(|xref| media.find_xref(xref)).is_some() => {}
+            _ if is_shared_note(record) => {}
+            "FAM" => {
+                let pointers = FamilyPointers::from_record(record);
+                let partners: Vec<u128> = pointers
+                    .partners
+                    .iter()
+                    .filter_map(|xref| ids_by_xref.get(xref))
+                    .copied()
+                    .collect();
+                let citations = citations(record, &sources, &mut diagnostics);
+                if !partners.is_empty() && !citations.is_empty() {
+                    sources.relationship_mut(&partners).extend(citations);
+                }
+                let family_notes = attached_notes(record, &notes, &mut diagnostics);
+                if !partners.is_empty() && !family_notes.is_empty() {
+                    notes.relationship_mut(&partners).extend(family_notes);
+                }
+                let mut family = Family::new(partners);
+                family.xref = record.xref.clone();
+                family.kind = RelationshipKind::from_record(record);
+                family.events = relationship_events(record, &mut places);
+                for event in family.events.iter() {
+                    if let Some(Err(error)) = event.date.as_deref().map(str::parse::<GedcomDate>) {
+                        diagnostics.push(Diagnostic::warning(
+                            event
+                                .gedcom_record
+                                .as_ref()
+                                .and_then(|record| record.child("DATE"))
+                                .map(|date| date.line),
+                            format!("{}, the date is kept as written", error),
+                        ));
+                    }
+                }
+                family.gedcom_record = Some(record.clone());
+                for xref in pointers.children.iter() {
+                    let Some(child) = ids_by_xref.get(xref) else { continue };
+                    // The pedigree is told by the child's link to the family.
+                    let pedigree = individuals_by_xref
+                        .get(xref.as_str())
+                        .and_then(|individual| {
+                            individual
+                                .children_with_tag("FAMC")
+                                .find(|link| link.value.is_some() && link.value == record.xref)
+                        })
+                        .and_then(|link| link.child("PEDI"))
+                        .map(Pedigree::from_structure)
+                        .unwrap_or_default();
+                    family.children.push(ChildLink {
+                        child: *child,
+                        pedigree,
+                    });
+                }
+                families.insert(family);
+            }
+            _ => preserved.records.push(record.clone()),
+        }
+    }
+
+    ImportedTree {
+        nodes,
+        edges,
+        preserved,
+        families,
+        places,
+        sources,
+        media,
+        notes,
+        diagnostics,
+    }
+}
+
+/// A `NOTE` record of 5.5.x or an `SNOTE` record of 7.0.
+fn is_shared_note(record: &Structure) -> bool {
+    matches!(record.tag.as_str(), "NOTE" | "SNOTE") && record.xref.is_some()
+}
+
+/// Spouse link between the partners followed by a parent-to-child link for every child and partner.
+fn family_edges(family: &FamilyPointers, ids_by_xref: &HashMap<String, u128>) -> Vec<(u128, u128)> {
+    let parents: Vec<u128> = family
+        .partners
+        .iter()
+        .filter_map(|xref| ids_by_xref.get(xref))
+        .copied()
+        .collect();
+
+    let mut edges = vec![];
+    if let [first, second] = parents[..] {
+        edges.push((first, second));
+    }
+    for child in family.children.iter().filter_map(|xref| ids_by_xref.get(xref)) {
+        for parent in parents.iter() {
+            edges.push((*parent, *child));
+        }
+    }
+    edges
+}
+
+/// Assigns each individual a generation so that partners share a row and children sit below their parents.
+fn generations(families: &[FamilyPointers], individual_count: usize) -> HashMap<String, usize> {
+    let mut generations: HashMap<String, usize> = HashMap::new();
+    // Every pass can only push a generation one step further, so a cyclic file stops after this many passes.
+    for _ in 0..=individual_count {
+        let mut changed = false;
+        for family in families.iter() {
+            let partner_generation = family
+                .partners
+                .iter()
+                .map(|xref| generations.get(xref).copied().unwrap_or(0))
+                .max()
+                .unwrap_or(0);
+            for xref in family.partners.iter() {
+                changed |= raise_generation(&mut generations, xref, partner_generation);
+            }
+            for xref in family.children.iter() {
+                changed |= raise_generation(&mut generations, xref, partner_generation + 1);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    generations
+}
+
+fn raise_generation(generations: &mut HashMap<String, usize>, xref: &str, generation: usize) -> bool {
+    let current = generations.entry(xref.to_string()).or_insert(0);
+    if *current < generation {
+        *current = generation;
+        return true;
+    }
+    false
+}
+
+fn individual_to_node(
+    individual: &Structure,
+    anchor: Point,
+    places: &mut Places,
+    sources: &Sources,
+    media: &mut Media,
+    notes: &Notes,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> GenealogicalNode {
+    let mut node = GenealogicalNode::new(anchor);
+    node.set_gedcom_record(individual.clone());
+    if let Some(xref) = &individual.xref {
+        node.set_xref(xref.clone());
+    }
+    // The first name is the preferred one, as GEDCOM has it.
+    for name in individual.children_with_tag("NAME") {
+        node.add_name(PersonalName::from_structure(name));
+    }
+    if let Some(sex) = individual.child("SEX") {
+        match sex.value.as_deref() {
+            Some("M") => node.set_sex(Sex::Male),
+            Some("F") => node.set_sex(Sex::Female),
+            Some("X") => node.set_sex(Sex::Intersex),
+            Some("U") => node.set_sex(Sex::Unknown),
+            // Not recorded, as some programs write it.
+            Some("N") => {}
+            value => diagnostics.push(Diagnostic::warning(
+                Some(sex.line),
+                format!("Unknown SEX value {}, left unset", value.unwrap_or_default()),
+            )),
+        }
+    }
+    for structure in individual.children.iter() {
+        if let Some(event_type) = EventType::from_structure(structure) {
+            let mut event = structure_to_event(event_type, structure, places);
+            event.citations = citations(structure, sources, diagnostics);
+            event.notes = attached_notes(structure, notes, diagnostics);
+            if let Some(Err(error)) = event.date.as_deref().map(str::parse::<GedcomDate>) {
+                diagnostics.push(Diagnostic::warning(
+                    structure.child("DATE").map(|date| date.line),
+                    format!("{}, the date is kept as written", error),
+                ));
+            }
+            node.add_event(event);
+        }
+    }
+    *node.citations_mut() = citations(individual, sources, diagnostics);
+    *node.notes_mut() = attached_notes(individual, notes, diagnostics);
+    let (links, portrait) = media_links(individual, media, diagnostics);
+    *node.media_mut() = links;
+    node.set_portrait(portrait);
+    node
+}
+
+/// The media links of a structure and the picture to show for it, the object marked with `_PRIM Y` or else the first
+/// image.
+///
+/// An object given inline, which 5.5.1 allows, becomes a media object of its own. A pointer to an object that is
+/// missing stays part of the imported structure.
+fn media_links(
+    structure: &Structure,
+    media: &mut Media,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> (Vec<MediaLink>, Option<u128>) {
+    let mut links = vec![];
+    let mut primary = None;
+    for link in structure.children_with_tag("OBJE") {
+        let object = match link.value.as_deref().filter(|value| is_pointer(value)) {
+            Some(xref) => match media.find_xref(xref) {
+                Some(object) => {
+                    links.push(MediaLink {
+                        object,
+                        gedcom_record: Some(link.clone()),
+                    });
+                    object
+                }
+                None => {
+                    diagnostics.push(Diagnostic::warning(
+                        Some(link.line),
+                        format!("Media link refers to {} which is not in the file", xref),
+                    ));
+                    continue;
+                }
+            },
+            None => {
+                let Some(object) = MediaObject::from_structure(link) else {
+                    continue;
+                };
+                let object = media.insert(object);
+                links.push(MediaLink::new(object));
+                object
+            }
+        };
+        if primary.is_none() && link.child_value("_PRIM") == Some("Y") {
+            primary = Some(object);
+        }
+    }
+    let portrait = primary.or_else(|| {
+        links
+            .iter()
+            .map(|link| link.object)
+            .find(|object| media.get(*object).is_some_and(MediaObject::is_image))
+    });
+    (links, portrait)
+}
+
+/// The citations of a structure that point at a source record of the tree.
+///
+/// A source given only as text has no record to point at and stays part of the imported structure, as does a
+/// pointer to a source that is missing.
+fn citations(structure: &Structure, sources: &Sources, diagnostics: &mut Vec<Diagnostic>) -> Vec<Citation> {
+    let mut citations = vec![];
+    for citation in structure.children_with_tag("SOUR") {
+        let Some(xref) = citation.value.as_deref().filter(|value| is_pointer(value)) else {
+            continue;
+        };
+        match sources.find_xref(xref) {
+            Some(source) => citations.push(Citation::from_structure(citation, source)),
+            None => diagnostics.push(Diagnostic::warning(
+                Some(citation.line),
+                format!("Citation refers to source {} which is not in the file", xref),
+            )),
+        }
+    }
+    citations
+}
+
+/// The repositories a source record points at.
+///
+/// A repository given only by a note has no record to point at and stays part of the imported structure, as does a
+/// pointer to a repository that is missing.
+fn repository_links(record: &Structure, sources: &Sources, diagnostics: &mut Vec<Diagnostic>) -> Vec<RepositoryLink> {
+    let mut links = vec![];
+    for link in record.children_with_tag("REPO") {
+        let Some(xref) = link.value.as_deref().filter(|value| is_pointer(value)) else {
+            continue;
+        };
+        match sources.find_repository_xref(xref) {
+            Some(repository) => links.push(RepositoryLink::from_structure(link, repository)),
+            None => diagnostics.push(Diagnostic::warning(
+                Some(link.line),
+                format!("Source refers to repository {} which is not in the file", xref),
+            )),
+        }
+    }
+    links
+}
+
+/// The associations of a structure, its `ASSO` links or the `_ASSO` links 5.5.x programs write on events.
+///
+/// A link to anything but an individual, which 5.5.1 allows through `TYPE`, stays part of the imported structure, as
+/// does a pointer to an individual that is missing.
+fn associations(
+    structure: &Structure,
+    ids_by_xref: &HashMap<String, u128>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Association> {
+    let mut associations = vec![];
+    for link in structure
+        .children
+        .iter()
+        .filter(|child| matches!(child.tag.as_str(), "ASSO" | "_ASSO"))
+    {
+        let Some(xref) = link.value.as_deref().filter(|value| is_pointer(value)) else {
+            continue;
+        };
+        if link.child_value("TYPE").is_some_and(|kind| kind != "INDI") {
+            continue;
+        }
+        match ids_by_xref.get(xref) {
+            Some(person) => associations.push(Association {
+                person: *person,
+                role: AssociationRole::from_structure(link),
+                gedcom_record: Some(link.clone()),
+            }),
+            None => diagnostics.push(Diagnostic::warning(
+                Some(link.line),
+                format!("Association refers to {} which is not in the file", xref),
+            )),
+        }
+    }
+    associations
+}
+
+/// Reads the associations of a person and of its events, once every person of the tree has its id.
+pub fn read_associations(
+    node: &mut GenealogicalNode,
+    ids_by_xref: &HashMap<String, u128>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if let Some(record) = node.gedcom_record().cloned() {
+        *node.associations_mut() = associations(&record, ids_by_xref, diagnostics);
+    }
+    for index in 0..node.events().len() {
+        let Some(event) = node.event_mut(index) else { continue };
+        if let Some(record) = event.gedcom_record.clone() {
+            event.associations = associations(&record, ids_by_xref, diagnostics);
+        }
+    }
+}
+
+/// The notes of a structure, written out or pointing at a shared note of the tree.
+///
+/// A pointer to a shared note that is missing stays part of the imported structure.
+fn attached_notes(structure: &Structure, notes: &Notes, diagnostics: &mut Vec<Diagnostic>) -> Vec<Note> {
+    let mut attached = vec![];
+    for note in structure
+        .children
+        .iter()
+        .filter(|child| matches!(child.tag.as_str(), "NOTE" | "SNOTE"))
+    {
+        let text = match note.value.as_deref().filter(|value| is_pointer(value)) {
+            Some(xref) => match notes.find_xref(xref) {
+                Some(shared) => NoteText::Shared(shared),
+                None => {
+                    diagnostics.push(Diagnostic::warning(
+                        Some(note.line),
+                        format!("Note refers to {} which is not in the file", xref),
+                    ));
+                    continue;
+                }
+            },
+            None => NoteText::Inline(note.value.clone().unwrap_or_default()),
+        };
+        attached.push(Note {
+            text,
+            gedcom_record: Some(note.clone()),
+        });
+    }
+    attached
+}
+
+fn structure_to_event(event_type: EventType, structure: &Structure, places: &mut Places) -> LifeEvent {
+    // A standard event can only say `Y` in its value, a description goes into its `TYPE`.
+    let description = if event_type.described_by_value() {
+        structure.value.clone()
+    } else {
+        structure.child_value("TYPE").map(String::from)
+    };
+    LifeEvent {
+        event_type,
+        date: structure.child("DATE").and_then(event_date),
+        place: structure.child("PLAC").and_then(|place| place_to_record(place, places)),
+        description,
+        age: structure.child_value("AGE").map(String::from),
+        citations: vec![],
+        notes: vec![],
+        associations: vec![],
+        gedcom_record: Some(structure.clone()),
+    }
+}
+
+/// The engagements, marriages, divorces and annulments of a `FAM` record.
+fn relationship_events(record: &Structure, places: &mut Places) -> Vec<RelationshipEvent> {
+    record
+        .children
+        .iter()
+        .filter_map(|structure| {
+            Some(RelationshipEvent {
+                event_type: RelationshipEventType::from_tag(&structure.tag)?,
+                date: structure.child("DATE").and_then(event_date),
+                place: structure.child("PLAC").and_then(|place| place_to_record(place, places)),
+                gedcom_record: Some(structure.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Every event naming the same place shares one record, which collects the form and coordinates any of them give.
+fn place_to_record(structure: &Structure, places: &mut Places) -> Option<u128> {
+    let name = structure.value.as_deref().filter(|name| !name.trim().is_empty())?;
+    let id = places.intern(name);
+    let place = places.get_mut(id)?;
+    if let Some(form) = structure.child_value("FORM").filter(|_| place.form().is_none()) {
+        place.set_form(form.to_string());
+    }
+    if let Some(map) = structure.child("MAP").filter(|_| place.coordinates().is_none()) {
+        let latitude = map.child_value("LATI").and_then(|value| coordinate(value, 'N', 'S'));
+        let longitude = map.child_value("LONG").and_then(|value| coordinate(value, 'E', 'W'));
+        if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+            place.set_coordinates((latitude, longitude));
+        }
+    }
+    Some(id)
+}
+
+/// Reads a GEDCOM `N41.25` or `W72.5` as signed decimal degrees.
+fn coordinate(value: &str, positive: char, negative: char) -> Option<f64> {
+    let value = value.trim();
+    let hemisphere = value.chars().next()?.to_ascii_uppercase();
+    let degrees: f64 = value[hemisphere.len_utf8()..].parse().ok()?;
+    match hemisphere {
+        hemisphere if hemisphere == positive => Some(degrees),
+        hemisphere if hemisphere == negative => Some(-degrees),
+        _ => None,
+    }
+}
+
+/// 7.0 keeps a date phrase in a `PHRASE` substructure, events hold it in the 5.5.x form.
+fn event_date(date: &Structure) -> Option<String> {
+    let value = date.value.as_deref().unwrap_or_default();
+    match date.child_value("PHRASE") {
+        Some(phrase) if value.is_empty() => Some(format!("({})", phrase)),
+        Some(phrase) if matches!(value.parse(), Ok(GedcomDate::Date(_))) => Some(format!("INT {} ({})", value, phrase)),
+        _ => date.value.clone(),
+    }
+}
@@ -0,0 +1,769 @@
+use std::collections::{HashMap, HashSet};
+
+use graph::{edge::GraphEdgeTrait, node::GraphNodeTrait, Graph};
+use iced::Vector;
+
+use crate::{
+    association::Association,
+    family::Families,
+    genealogical_edge::{family_edges_of, GenealogicalEdge},
+    genealogical_node::{GenealogicalNode, Sex},
+    life_event::{EventType, LifeEvent},
+    media::{Media, MediaLink},
+    note::{Note, NoteText, Notes},
+    place::Places,
+    relationship::RelationshipKind,
+    source::{Citation, Sources},
+};
+
+use super::{
+    export::free_xref,
+    import::{ImportedTree, NODE_SPACING},
+    parser::{is_pointer, Structure},
+    preserved::PreservedRecords,
+};
+
+/// Candidates scoring at least this much agree on more than just the name and start out accepted.
+const ACCEPT_SCORE: u32 = 4;
+/// Years further apart than this rule a candidate out, closer ones allow for rounding and estimates.
+const YEAR_TOLERANCE: i32 = 2;
+
+/// An individual of the incoming file that looks like a node already in the tree.
+#[derive(Debug, Clone)]
+pub struct MergeCandidate {
+    pub incoming: u128,
+    pub existing: u128,
+    pub accepted: bool,
+}
+
+/// An imported file waiting for the user to confirm or reject its candidates before it is merged into the tree.
+pub struct PendingMerge {
+    pub tree: ImportedTree,
+    pub candidates: Vec<MergeCandidate>,
+}
+
+/// What an individual is compared by, taken from the node and its neighbours.
+struct Person {
+    id: u128,
+    /// The given name and surname of every name the person went by.
+    names: Vec<(Option<String>, Option<String>)>,
+    sex: Option<Sex>,
+    birth: Option<i32>,
+    death: Option<i32>,
+    parents: Vec<(Option<String>, Option<String>)>,
+}
+
+impl Person {
+    fn collect(nodes: &[GenealogicalNode], families: &Families) -> Vec<Person> {
+        let by_id: HashMap<u128, &GenealogicalNode> = nodes.iter().map(|node| (node.id(), node)).collect();
+        // The partners of every family a person is a child of, gathered in one pass over the families.
+        let mut parents_of: HashMap<u128, Vec<u128>> = HashMap::new();
+        for family in families.iter() {
+            for link in family.children.iter() {
+                parents_of
+                    .entry(link.child)
+                    .or_default()
+                    .extend(family.partners.iter().copied());
+            }
+        }
+        nodes
+            .iter()
+            .map(|node| {
+                let parents = parents_of
+                    .get(&node.id())
+                    .into_iter()
+                    .flatten()
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .filter_map(|parent| by_id.get(parent))
+                    .map(|parent| (normalize(parent.first_name()), normalize(parent.last_name())))
+                    .collect();
+                Person {
+                    id: node.id(),
+                    names: node
+                        .names()
+                        .iter()
+                        .map(|name| (normalize(name.given.clone()), normalize(name.surname.clone())))
+                        .filter(|(given, surname)| given.is_some() || surname.is_some())
+                        .collect(),
+                    sex: node.sex(),
+                    birth: event_year(node, EventType::Birth),
+                    death: event_year(node, EventType::Death),
+                    parents,
+                }
+            })
+            .collect()
+    }
+
+    /// `None` when the two cannot be the same person, otherwise how much evidence says they are.
+    fn match_score(&self, other: &Person) -> Option<u32> {
+        // A maiden name on one side matches it among the names on the other just as well as the preferred one.
+        let same_name = self.names.iter().any(|(given, surname)| {
+            other
+                .names
+                .iter()
+                .any(|(other_given, other_surname)| same_given_name(given, other_given) && surname == other_surname)
+        });
+        if !same_name {
+            return None;
+        }
+        if let (Some(sex), Some(other_sex)) = (&self.sex, &other.sex) {
+            if sex.is_known() && other_sex.is_known() && sex != other_sex {
+                return None;
+            }
+        }
+
+        let mut score = 2;
+        for (year, other_year) in [(self.birth, other.birth), (self.death, other.death)] {
+            if let (Some(year), Some(other_year)) = (year, other_year) {
+                match (year - other_year).abs() {
+                    0 => score += 2,
+                    difference if difference <= YEAR_TOLERANCE => score += 1,
+                    _ => return None,
+                }
+            }
+        }
+        score += self
+            .parents
+            .iter()
+            .filter(|parent| other.parents.contains(parent))
+            .count() as u32;
+        Some(score)
+    }
+}
+
+impl PendingMerge {
+    /// Pairs every incoming individual with the existing node it matches best, each node being used at most once.
+    pub fn new(graph: &Graph<GenealogicalNode, GenealogicalEdge>, families: &Families, tree: ImportedTree) -> Self {
+        let existing = Person::collect(graph.nodes(), families);
+        let incoming = Person::collect(&tree.nodes, &tree.families);
+
+        // Only people sharing a surname can match, so each incoming person is scored against those alone.
+        let mut by_surname: HashMap<&Option<String>, Vec<&Person>> = HashMap::new();
+        for person in existing.iter() {
+            let surnames: HashSet<&Option<String>> = person.names.iter().map(|(_, surname)| surname).collect();
+            for surname in surnames {
+                by_surname.entry(surname).or_default().push(person);
+            }
+        }
+        let mut scored: Vec<(u32, u128, u128)> = vec![];
+        for person in incoming.iter() {
+            let mut compared = HashSet::new();
+            let others = person
+                .names
+                .iter()
+                .filter_map(|(_, surname)| by_surname.get(surname))
+                .flatten();
+            for other in others.filter(|other| compared.insert(other.id)) {
+                if let Some(score) = person.match_score(other) {
+                    scored.push((score, person.id, other.id));
+                }
+            }
+        }
+        scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+        let mut candidates: Vec<MergeCandidate> = vec![];
+        let (mut matched_incoming, mut matched_existing) = (HashSet::new(), HashSet::new());
+        for (score, incoming, existing) in scored {
+            if matched_incoming.contains(&incoming) || matched_existing.contains(&existing) {
+                continue;
+            }
+            matched_incoming.insert(incoming);
+            matched_existing.insert(existing);
+            candidates.push(MergeCandidate {
+                incoming,
+                existing,
+                accepted: score >= ACCEPT_SCORE,
+            });
+        }
+        // Keep the review list in file order rather than by score.
+        let order: HashMap<u128, usize> = incoming
+            .iter()
+            .enumerate()
+            .map(|(index, person)| (person.id, index))
+            .collect();
+        candidates.sort_by_key(|candidate| order[&candidate.incoming]);
+
+        Self { tree, candidates }
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(candidate) = self.candidates.get_mut(index) {
+            candidate.accepted = !candidate.accepted;
+        }
+    }
+
+    pub fn incoming_node(&self, node_id: u128) -> Option<&GenealogicalNode> {
+        self.tree.nodes.iter().find(|node| node.id() == node_id)
+    }
+
+    /// Individuals that will be added as new nodes with the current choices.
+    pub fn new_individual_count(&self) -> usize {
+        self.tree.nodes.len() - self.candidates.iter().filter(|candidate| candidate.accepted).count()
+    }
+
+    /// Folds accepted matches into their existing nodes and adds everything else next to the current tree.
+    ///
+    /// Incoming xrefs that are already taken are renamed, and every pointer to them rewritten, so that records
+    /// from both files can be exported together.
+    #[allow(clippy::too_many_arguments)]
+    pub fn merge_into(
+        self,
+        graph: &mut Graph<GenealogicalNode, GenealogicalEdge>,
+        preserved: &mut PreservedRecords,
+        families: &mut Families,
+        places: &mut Places,
+        sources: &mut Sources,
+        media: &mut Media,
+        notes: &mut Notes,
+    ) {
+        let PendingMerge { mut tree, candidates } = self;
+        let incoming_media = std::mem::take(&mut tree.media).into_objects(media.directory());
+        let place_ids = places.extend(std::mem::take(&mut tree.places));
+        let (incoming_sources, incoming_repositories, relationships) = std::mem::take(&mut tree.sources).into_parts();
+        let (incoming_notes, note_relationships) = std::mem::take(&mut tree.notes).into_parts();
+        let incoming_families = std::mem::take(&mut tree.families).into_families();
+        let matches: HashMap<u128, u128> = candidates
+            .iter()
+            .filter(|candidate| candidate.accepted)
+            .map(|candidate| (candidate.incoming, candidate.existing))
+            .collect();
+        let map_id = |id: &u128| matches.get(id).copied().unwrap_or(*id);
+
+        let mut used: HashSet<String> = graph
+            .nodes()
+            .iter()
+            .filter_map(|node| node.xref())
+            .chain(preserved.xrefs().cloned())
+            .chain(families.xrefs().cloned())
+            .chain(sources.xrefs().cloned())
+            .chain(media.xrefs().cloned())
+            .chain(notes.xrefs().cloned())
+            .collect();
+        let mut renames: HashMap<String, String> = HashMap::new();
+        let mut next: HashMap<String, usize> = HashMap::new();
+        let mut claim = |xref: &str, used: &mut HashSet<String>, renames: &mut HashMap<String, String>| {
+            if renames.contains_key(xref) {
+                return;
+            }
+            if used.insert(xref.to_string()) {
+                renames.insert(xref.to_string(), xref.to_string());
+                return;
+            }
+            let prefix = xref.trim_matches('@').trim_end_matches(|c: char| c.is_ascii_digit());
+            let prefix = if prefix.is_empty() { "X" } else { prefix };
+            let next = next.entry(prefix.to_string()).or_insert(1);
+            renames.insert(xref.to_string(), free_xref(prefix, next, used));
+        };
+
+        // Matched individuals take over the xref of the node they are merged into.
+        for node in tree.nodes.iter() {
+            let Some(xref) = node.xref() else { continue };
+            if let Some(existing_xref) = matches.get(&node.id()).and_then(|id| graph.get_node(Some(*id))?.xref()) {
+                renames.insert(xref, existing_xref);
+            }
+        }
+        // A family whose partners are all matched is the same family as the one already in the tree.
+        let mut family_ids: HashMap<u128, u128> = HashMap::new();
+        for family in incoming_families.iter() {
+            if family.partners.is_empty() || !family.partners.iter().all(|id| matches.contains_key(id)) {
+                continue;
+            }
+            let partners: Vec<u128> = family.partners.iter().map(map_id).collect();
+            let Some(existing) = families.find_partners(&partners).and_then(|id| families.get(id)) else {
+                continue;
+            };
+            if let (Some(xref), Some(existing_xref)) = (&family.xref, &existing.xref) {
+                renames.insert(xref.clone(), existing_xref.clone());
+            }
+            family_ids.insert(family.id(), existing.id());
+        }
+        // Submitters, vendor records and the like that both files share are kept once.
+        tree.preserved.records.retain(|record| {
+            let duplicate = preserved.records.iter().find(|existing| {
+                existing.tag == record.tag
+                    && existing.value == record.value
+                    && existing.children.len() == record.children.len()
+                    && existing
+                        .children
+                        .iter()
+                        .zip(record.children.iter())
+                        .all(|(a, b)| same_structure(a, b))
+            });
+            match (duplicate.and_then(|existing| existing.xref.as_ref()), &record.xref) {
+                (Some(existing_xref), Some(xref)) => {
+                    renames.insert(xref.clone(), existing_xref.clone());
+                    false
+                }
+                _ => true,
+            }
+        });
+        // The same archive is kept once, the sources it holds point at the existing one.
+        let mut repository_ids: HashMap<u128, u128> = HashMap::new();
+        let mut new_repositories = vec![];
+        for repository in incoming_repositories {
+            match sources
+                .repositories()
+                .find(|existing| existing.same_content(&repository))
+            {
+                Some(existing) => {
+                    if let (Some(xref), Some(existing_xref)) = (&repository.xref, &existing.xref) {
+                        renames.insert(xref.clone(), existing_xref.clone());
+                    }
+                    repository_ids.insert(repository.id(), existing.id());
+                }
+                None => new_repositories.push(repository),
+            }
+        }
+        // Sources saying the same are kept once, citations of the incoming one move to the existing one.
+        let mut source_ids: HashMap<u128, u128> = HashMap::new();
+        let mut new_sources = vec![];
+        for source in incoming_sources {
+            match sources.iter().find(|existing| existing.same_content(&source)) {
+                Some(existing) => {
+                    if let (Some(xref), Some(existing_xref)) = (&source.xref, &existing.xref) {
+                        renames.insert(xref.clone(), existing_xref.clone());
+                    }
+                    source_ids.insert(source.id(), existing.id());
+                }
+                None => new_sources.push(source),
+            }
+        }
+        // The same file is the same object, links to the incoming one move to the existing one.
+        let mut media_ids: HashMap<u128, u128> = HashMap::new();
+        let mut new_media = vec![];
+        for object in incoming_media {
+            match media
+                .iter()
+                .find(|existing| media.same_file(&existing.file, &object.file))
+            {
+                Some(existing) => {
+                    if let (Some(xref), Some(existing_xref)) = (&object.xref, &existing.xref) {
+                        renames.insert(xref.clone(), existing_xref.clone());
+                    }
+                    media_ids.insert(object.id(), existing.id());
+                }
+                None => new_media.push(object),
+            }
+        }
+        // A shared note saying the same is the same note.
+        let mut note_ids: HashMap<u128, u128> = HashMap::new();
+        let mut new_notes = vec![];
+        for note in incoming_notes {
+            match notes.iter().find(|existing| existing.text == note.text) {
+                Some(existing) => {
+                    if let (Some(xref), Some(existing_xref)) = (&note.xref, &existing.xref) {
+                        renames.insert(xref.clone(), existing_xref.clone());
+                    }
+                    note_ids.insert(note.id(), existing.id());
+                }
+                None => new_notes.push(note),
+            }
+        }
+        let incoming_xrefs: Vec<String> = tree
+            .nodes
+            .iter()
+            .filter_map(|node| node.xref())
+            .chain(tree.preserved.xrefs().cloned())
+            .chain(
+                incoming_families
+                    .iter()
+                    .filter(|family| !family_ids.contains_key(&family.id()))
+                    .filter_map(|family| family.xref.clone()),
+            )
+            .chain(new_sources.iter().filter_map(|source| source.xref.clone()))
+            .chain(new_repositories.iter().filter_map(|repository| repository.xref.clone()))
+            .chain(new_media.iter().filter_map(|object| object.xref.clone()))
+            .chain(new_notes.iter().filter_map(|note| note.xref.clone()))
+            .collect();
+        for xref in incoming_xrefs.iter() {
+            claim(xref, &mut used, &mut renames);
+        }
+        for mut note in new_notes {
+            if let Some(xref) = note.xref.as_ref().and_then(|xref| renames.get(xref)) {
+                note.xref = Some(xref.clone());
+            }
+            if let Some(record) = note.gedcom_record.as_mut() {
+                rename_pointers(record, &renames);
+            }
+            notes.insert(note);
+        }
+        for mut object in new_media {
+            if let Some(xref) = object.xref.as_ref().and_then(|xref| renames.get(xref)) {
+                object.xref = Some(xref.clone());
+            }
+            if let Some(record) = object.gedcom_record.as_mut() {
+                rename_pointers(record, &renames);
+            }
+            media.insert(object);
+        }
+        let map_media = |links: &[MediaLink]| -> Vec<MediaLink> {
+            links
+                .iter()
+                .cloned()
+                .map(|mut link| {
+                    link.object = media_ids.get(&link.object).copied().unwrap_or(link.object);
+                    if let Some(record) = link.gedcom_record.as_mut() {
+                        rename_pointers(record, &renames);
+                    }
+                    link
+                })
+                .collect()
+        };
+        let map_notes = |attached: &[Note]| -> Vec<Note> {
+            attached
+                .iter()
+                .cloned()
+                .map(|mut note| {
+                    if let NoteText::Shared(id) = &mut note.text {
+                        *id = note_ids.get(id).copied().unwrap_or(*id);
+                    }
+                    if let Some(record) = note.gedcom_record.as_mut() {
+                        rename_pointers(record, &renames);
+                    }
+                    note
+                })
+                .collect()
+        };
+        let map_associations = |associations: &[Association]| -> Vec<Association> {
+            associations
+                .iter()
+                .cloned()
+                .map(|mut association| {
+                    association.person = map_id(&association.person);
+                    if let Some(record) = association.gedcom_record.as_mut() {
+                        rename_pointers(record, &renames);
+                    }
+                    association
+                })
+                .collect()
+        };
+        for mut repository in new_repositories {
+            if let Some(xref) = repository.xref.as_ref().and_then(|xref| renames.get(xref)) {
+                repository.xref = Some(xref.clone());
+            }
+            if let Some(record) = repository.gedcom_record.as_mut() {
+                rename_pointers(record, &renames);
+            }
+            sources.insert_repository(repository);
+        }
+        for mut source in new_sources {
+            for link in source.repositories.iter_mut() {
+                link.repository = repository_ids.get(&link.repository).copied().unwrap_or(link.repository);
+                if let Some(record) = link.gedcom_record.as_mut() {
+                    rename_pointers(record, &renames);
+                }
+            }
+            source.media = map_media(&source.media);
+            source.notes = map_notes(&source.notes);
+            if let Some(xref) = source.xref.as_ref().and_then(|xref| renames.get(xref)) {
+                source.xref = Some(xref.clone());
+            }
+            if let Some(record) = source.gedcom_record.as_mut() {
+                rename_pointers(record, &renames);
+            }
+            sources.insert(source);
+        }
+        let map_citations = |citations: &[Citation]| -> Vec<Citation> {
+            citations
+                .iter()
+                .cloned()
+                .map(|mut citation| {
+                    citation.source = source_ids.get(&citation.source).copied().unwrap_or(citation.source);
+                    if let Some(record) = citation.gedcom_record.as_mut() {
+                        rename_pointers(record, &renames);
+                    }
+                    citation
+                })
+                .collect()
+        };
+
+        let existing_right = graph
+            .nodes()
+            .iter()
+            .map(|node| node.anchor().x + node.size().width)
+            .reduce(f32::max);
+        let incoming_left = tree
+            .nodes
+            .iter()
+            .map(|node| node.anchor().x)
+            .reduce(f32::min)
+            .unwrap_or(0.0);
+        // Line the generations up with the first confirmed match so that parents stay above their children.
+        let vertical = candidates
+            .iter()
+            .find(|candidate| candidate.accepted)
+            .and_then(|candidate| {
+                let existing = graph.get_node(Some(candidate.existing))?;
+                let incoming = tree.nodes.iter().find(|node| node.id() == candidate.incoming)?;
+                Some(existing.anchor().y - incoming.anchor().y)
+            })
+            .unwrap_or(0.0);
+        let offset = match existing_right {
+            Some(right) => Vector::new(right + NODE_SPACING.x - incoming_left, vertical),
+            None => Vector::new(0.0, vertical),
+        };
+
+        for mut node in tree.nodes.drain(..) {
+            if let Some(xref) = node.xref().and_then(|xref| renames.get(&xref).cloned()) {
+                node.set_xref(xref);
+            }
+            if let Some(mut record) = node.gedcom_record().cloned() {
+                rename_pointers(&mut record, &renames);
+                node.set_gedcom_record(record);
+            }
+            *node.citations_mut() = map_citations(node.citations());
+            *node.media_mut() = map_media(node.media());
+            *node.notes_mut() = map_notes(node.notes());
+            *node.associations_mut() = map_associations(node.associations());
+            let portrait = node
+                .portrait()
+                .map(|object| media_ids.get(&object).copied().unwrap_or(object));
+            node.set_portrait(portrait);
+            for index in 0..node.events().len() {
+                let Some(event) = node.event_mut(index) else { continue };
+                event.place = event.place.and_then(|place| place_ids.get(&place).copied());
+                event.citations = map_citations(&event.citations);
+                event.notes = map_notes(&event.notes);
+                event.associations = map_associations(&event.associations);
+                if let Some(record) = event.gedcom_record.as_mut() {
+                    rename_pointers(record, &renames);
+                }
+            }
+            match matches.get(&node.id()) {
+                Some(existing_id) => merge_node(graph.get_node_mut_unsafe(Some(*existing_id)), node),
+                None => {
+                    node.set_anchor(node.anchor() + offset);
+                    graph.insert_node(node);
+                }
+            }
+        }
+
+        for (start, end) in tree.edges.iter() {
+            let (start, end) = (map_id(start), map_id(end));
+            let exists = family_edges_of(graph, start).any(|edge| edge.joins(start, end));
+            if start != end && !exists {
+                graph.add_edge_between_nodes(Some(start), end);
+            }
+        }
+
+        for relationship in relationships {
+            let partners: Vec<u128> = relationship.partners.iter().map(map_id).collect();
+            add_citations(
+                sources.relationship_mut(&partners),
+                &map_citations(&relationship.citations),
+            );
+        }
+        for relationship in note_relationships {
+            let partners: Vec<u128> = relationship.partners.iter().map(map_id).collect();
+            add_notes(notes.relationship_mut(&partners), &map_notes(&relationship.notes));
+        }
+
+        for mut family in incoming_families {
+            if let Some(record) = family.gedcom_record.as_mut() {
+                rename_pointers(record, &renames);
+            }
+            family.partners = family.partners.iter().map(map_id).collect();
+            for link in family.children.iter_mut() {
+                link.child = map_id(&link.child);
+            }
+            for event in family.events.iter_mut() {
+                event.place = event.place.and_then(|place| place_ids.get(&place).copied());
+                if let Some(record) = event.gedcom_record.as_mut() {
+                    rename_pointers(record, &renames);
+                }
+            }
+            let Some(existing) = family_ids.get(&family.id()).and_then(|id| families.get_mut(*id)) else {
+                if let Some(xref) = family.xref.as_ref().and_then(|xref| renames.get(xref)) {
+                    family.xref = Some(xref.clone());
+                }
+                families.insert(family);
+                continue;
+            };
+            // A child both files know keeps the pedigree the tree already gave it.
+            for link in family.children {
+                if existing.pedigree(link.child).is_none() {
+                    existing.children.push(link);
+                }
+            }
+            if existing.kind == RelationshipKind::Unknown {
+                existing.kind = family.kind;
+            }
+            for event in family.events {
+                if !existing
+                    .events
+                    .iter()
+                    .any(|other| other.event_type == event.event_type && other.date == event.date)
+                {
+                    existing.events.push(event);
+                }
+            }
+            match (existing.gedcom_record.as_mut(), family.gedcom_record) {
+                (Some(existing_record), Some(record)) => merge_children(existing_record, record),
+                (None, record) => existing.gedcom_record = record,
+                (Some(_), None) => {}
+            }
+        }
+        for record in tree.preserved.records.iter_mut() {
+            rename_pointers(record, &renames);
+        }
+        preserved.extend(tree.preserved);
+        graph.redraw();
+    }
+}
+
+/// One line summary used to tell candidates apart in the review list.
+pub fn describe(node: &GenealogicalNode) -> String {
+    let mut description = node
+        .preferred_name()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| "Unnamed".to_string());
+    for (label, event_type) in [("b.", EventType::Birth), ("d.", EventType::Death)] {
+        if let Some(date) = event(node, event_type).and_then(|event| event.date.clone()) {
+            description.push_str(&format!(", {} {}", label, date));
+        }
+    }
+    description
+}
+
+/// Keeps what the existing node already has and fills in what only the incoming one knows.
+fn merge_node(existing: &mut GenealogicalNode, incoming: GenealogicalNode) {
+    for name in incoming.names() {
+        if !existing.names().iter().any(|existing| existing.same_parts(name)) {
+            existing.add_name(name.clone());
+        }
+    }
+    if !existing.sex().is_some_and(|sex| sex.is_known()) {
+        if let Some(sex) = incoming.sex().filter(Sex::is_known) {
+            existing.set_sex(sex);
+        }
+    }
+    if existing.xref().is_none() {
+        if let Some(xref) = incoming.xref() {
+            existing.set_xref(xref);
+        }
+    }
+    for event in incoming.events() {
+        let known = existing.events().iter().position(|other| {
+            other.event_type == event.event_type && other.date == event.date && other.place == event.place
+        });
+        match known.and_then(|index| existing.event_mut(index)) {
+            Some(known) => {
+                add_citations(&mut known.citations, &event.citations);
+                add_notes(&mut known.notes, &event.notes);
+                add_associations(&mut known.associations, &event.associations);
+            }
+            None => existing.add_event(event.clone()),
+        }
+    }
+    add_citations(existing.citations_mut(), incoming.citations());
+    add_notes(existing.notes_mut(), incoming.notes());
+    add_associations(existing.associations_mut(), incoming.associations());
+    for link in incoming.media() {
+        if !existing.media().iter().any(|other| other.object == link.object) {
+            existing.media_mut().push(link.clone());
+        }
+    }
+    if existing.portrait().is_none() {
+        existing.set_portrait(incoming.portrait());
+    }
+    if let Some(record) = incoming.gedcom_record().cloned() {
+        match existing.gedcom_record().cloned() {
+            Some(mut existing_record) => {
+                merge_children(&mut existing_record, record);
+                existing.set_gedcom_record(existing_record);
+            }
+            None => existing.set_gedcom_record(record),
+        }
+    }
+}
+
+/// Appends the citations that do not cite what one of the existing citations already does.
+fn add_citations(existing: &mut Vec<Citation>, incoming: &[Citation]) {
+    for citation in incoming.iter() {
+        if !existing.iter().any(|other| other.cites_same(citation)) {
+            existing.push(citation.clone());
+        }
+    }
+}
+
+/// Appends the notes that do not say what one of the existing notes already does.
+fn add_notes(existing: &mut Vec<Note>, incoming: &[Note]) {
+    for note in incoming.iter() {
+        if !existing.iter().any(|other| other.text == note.text) {
+            existing.push(note.clone());
+        }
+    }
+}
+
+/// Appends the associations with a person in a role not already linked that way.
+fn add_associations(existing: &mut Vec<Association>, incoming: &[Association]) {
+    for association in incoming.iter() {
+        if !existing
+            .iter()
+            .any(|other| other.person == association.person && other.role == association.role)
+        {
+            existing.push(association.clone());
+        }
+    }
+}
+
+/// Appends the substructures of `incoming` that `existing` does not already have.
+fn merge_children(existing: &mut Structure, incoming: Structure) {
+    for child in incoming.children {
+        if !existing.children.iter().any(|other| same_structure(other, &child)) {
+            existing.children.push(child);
+        }
+    }
+}
+
+/// Compares two structures by content, ignoring the lines they were read from.
+fn same_structure(a: &Structure, b: &Structure) -> bool {
+    a.tag == b.tag
+        && a.xref == b.xref
+        && a.value == b.value
+        && a.children.len() == b.children.len()
+        && a.children
+            .iter()
+            .zip(b.children.iter())
+            .all(|(a, b)| same_structure(a, b))
+}
+
+fn rename_pointers(structure: &mut Structure, renames: &HashMap<String, String>) {
+    if let Some(xref) = structure.xref.as_mut() {
+        if let Some(renamed) = renames.get(xref) {
+            *xref = renamed.clone();
+        }
+    }
+    if let Some(value) = structure.value.as_mut() {
+        if is_pointer(value) {
+            if let Some(renamed) = renames.get(value) {
+                *value = renamed.clone();
+            }
+        }
+    }
+    for child in structure.children.iter_mut() {
+        rename_pointers(child, renames);
+    }
+}
+
+fn normalize(name: Option<String>) -> Option<String> {
+    name.map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+}
+
+/// Equal given names, or one being only the first of the other's names, e.g. "John" and "John Henry".
+fn same_given_name(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a == b || a.split_whitespace().next() == b.split_whitespace().next(),
+        (a, b) => a == b,
+    }
+}
+
+fn event(node: &GenealogicalNode, event_type: EventType) -> Option<&LifeEvent> {
+    node.events().iter().find(|event| event.event_type == event_type)
+}
+
+fn event_year(node: &GenealogicalNode, event_type: EventType) -> Option<i32> {
+    event(node, event_type)?.year()
+}
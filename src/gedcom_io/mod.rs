@@ -0,0 +1,72 @@
+pub mod date;
+pub mod diagnostics;
+pub mod encoding;
+pub mod export;
+pub mod gedzip;
+pub mod gramps;
+pub mod import;
+pub mod merge;
+pub mod parser;
+pub mod preserved;
+
+use std::path::Path;
+
+use parser::Structure;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Gedcom(GedcomVersion),
+    /// A zip archive bundling a GEDCOM 7.0 dataset with the media files it references.
+    Gedzip,
+    /// A Gramps XML database, gzip-compressed.
+    Gramps,
+}
+
+impl FileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Gedcom(_) => "ged",
+            FileFormat::Gedzip => "gdz",
+            FileFormat::Gramps => "gramps",
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(self.extension()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GedcomVersion {
+    V551,
+    V555,
+    V70,
+}
+
+impl GedcomVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GedcomVersion::V551 => "5.5.1",
+            GedcomVersion::V555 => "5.5.5",
+            GedcomVersion::V70 => "7.0",
+        }
+    }
+
+    /// Reads `HEAD.GEDC.VERS`, files without one are treated as 5.5.1.
+    pub fn detect(records: &[Structure]) -> Self {
+        let version = records
+            .iter()
+            .find(|record| record.tag == "HEAD")
+            .and_then(|head| head.child("GEDC"))
+            .and_then(|gedc| gedc.child_value("VERS"))
+            .unwrap_or_default();
+        if version.starts_with('7') {
+            GedcomVersion::V70
+        } else if version.starts_with("5.5.5") {
+            GedcomVersion::V555
+        } else {
+            GedcomVersion::V551
+        }
+    }
+}
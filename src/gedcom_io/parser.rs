@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+use super::{diagnostics::Diagnostic, GedcomVersion};
+
+/// A single GEDCOM structure: one line together with all of its substructures.
+///
+/// `CONT` and `CONC` lines are folded into the value of the structure they continue.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Structure {
+    /// Where the structure was read from, only meaningful for the diagnostics of the import that produced it.
+    #[serde(skip)]
+    pub line: usize,
+    pub xref: Option<String>,
+    pub tag: String,
+    pub value: Option<String>,
+    pub children: Vec<Structure>,
+}
+
+impl Structure {
+    pub fn child(&self, tag: &str) -> Option<&Structure> {
+        self.children.iter().find(|child| child.tag == tag)
+    }
+
+    pub fn children_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Structure> {
+        self.children.iter().filter(move |child| child.tag == tag)
+    }
+
+    pub fn child_value(&self, tag: &str) -> Option<&str> {
+        self.child(tag).and_then(|child| child.value.as_deref())
+    }
+}
+
+/// Parses the content of a GEDCOM file of any version into its top-level records.
+///
+/// Lines that cannot be placed in the tree are skipped and reported, everything else is kept.
+pub fn parse(content: &str) -> (Vec<Structure>, Vec<Diagnostic>) {
+    let mut records: Vec<Structure> = vec![];
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    // The structures from the current record down to the previous line, indexed by level.
+    let mut open: Vec<Structure> = vec![];
+
+    // Old Macintosh files end their lines with a lone carriage return.
+    let content = content
+        .trim_start_matches('\u{FEFF}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    for (index, raw_line) in content.lines().enumerate() {
+        let line = index + 1;
+        if raw_line.trim().is_empty() {
+            diagnostics.push(Diagnostic::warning(Some(line), "Empty line skipped"));
+            continue;
+        }
+        let Some((level, structure)) = parse_line(raw_line, line) else {
+            diagnostics.push(Diagnostic::error(
+                Some(line),
+                format!("Malformed line skipped: {}", raw_line.trim()),
+            ));
+            continue;
+        };
+        if level > open.len() {
+            let message = match open.len() {
+                0 => format!("Level {} line is outside of any record, line skipped", level),
+                depth => format!("Level {} cannot follow level {}, line skipped", level, depth - 1),
+            };
+            diagnostics.push(Diagnostic::error(Some(line), message));
+            continue;
+        }
+
+        if matches!(structure.tag.as_str(), "CONT" | "CONC") {
+            let Some(parent) = level.checked_sub(1).and_then(|parent_level| open.get_mut(parent_level)) else {
+                diagnostics.push(Diagnostic::error(
+                    Some(line),
+                    format!("{} has nothing to continue, line skipped", structure.tag),
+                ));
+                continue;
+            };
+            let value = parent.value.get_or_insert_with(String::new);
+            if structure.tag == "CONT" {
+                value.push('\n');
+            }
+            value.push_str(structure.value.as_deref().unwrap_or_default());
+            continue;
+        }
+
+        close_to_level(&mut open, &mut records, level);
+        open.push(structure);
+    }
+    close_to_level(&mut open, &mut records, 0);
+
+    if records.first().is_none_or(|record| record.tag != "HEAD") {
+        diagnostics.push(Diagnostic::warning(Some(1), "File does not start with a HEAD record"));
+    }
+    if records.last().is_none_or(|record| record.tag != "TRLR") {
+        diagnostics.push(Diagnostic::warning(
+            None,
+            "File does not end with a TRLR record, it may be truncated",
+        ));
+    }
+
+    let version = GedcomVersion::detect(&records);
+    for record in records.iter_mut() {
+        unescape(record, version);
+    }
+    (records, diagnostics)
+}
+
+pub fn is_pointer(value: &str) -> bool {
+    value.len() > 2 && value.starts_with('@') && value.ends_with('@') && !value.starts_with("@@")
+}
+
+/// 5.5.x doubles every `@` in text, 7.0 only a leading one. Pointers are left untouched.
+fn unescape(structure: &mut Structure, version: GedcomVersion) {
+    if let Some(value) = structure.value.as_mut() {
+        if !is_pointer(value) {
+            *value = match version {
+                GedcomVersion::V70 => value
+                    .strip_prefix('@')
+                    .filter(|text| text.starts_with('@'))
+                    .unwrap_or(value)
+                    .to_string(),
+                GedcomVersion::V551 | GedcomVersion::V555 => value.replace("@@", "@"),
+            };
+        }
+    }
+    for child in structure.children.iter_mut() {
+        unescape(child, version);
+    }
+}
+
+/// Pops the open structures deeper than `level` and attaches each one to its parent.
+fn close_to_level(open: &mut Vec<Structure>, records: &mut Vec<Structure>, level: usize) {
+    while open.len() > level {
+        let structure = open.pop().unwrap();
+        match open.last_mut() {
+            Some(parent) => parent.children.push(structure),
+            None => records.push(structure),
+        }
+    }
+}
+
+/// Splits `level [xref] tag [value]` into its parts, returning `None` for lines that do not follow that shape.
+fn parse_line(raw_line: &str, line: usize) -> Option<(usize, Structure)> {
+    let raw_line = raw_line.trim_start();
+    let (level, rest) = raw_line.split_once(' ')?;
+    let level: usize = level.parse().ok()?;
+
+    let (xref, rest) = match rest.strip_prefix('@') {
+        Some(_) => {
+            let (xref, rest) = rest.split_once(' ')?;
+            (Some(xref.to_string()), rest)
+        }
+        None => (None, rest),
+    };
+
+    let (tag, value) = match rest.split_once(' ') {
+        Some((tag, value)) => (tag, Some(value)),
+        None => (rest, None),
+    };
+    if tag.is_empty() {
+        return None;
+    }
+
+    let value = value.filter(|value| !value.is_empty()).map(String::from);
+
+    Some((
+        level,
+        Structure {
+            line,
+            xref,
+            tag: tag.trim_end().to_string(),
+            value,
+            children: vec![],
+        },
+    ))
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+use super::parser::Structure;
+
+/// The parts of imported files that nodes and edges do not represent, written back unchanged on export.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreservedRecords {
+    /// The `HEAD` of the first imported file.
+    pub header: Option<Structure>,
+    /// Top-level records other than `HEAD`, `INDI`, `FAM` and `TRLR`, e.g. submitters and vendor records.
+    pub records: Vec<Structure>,
+}
+
+impl PreservedRecords {
+    pub fn extend(&mut self, other: PreservedRecords) {
+        if self.header.is_none() {
+            self.header = other.header;
+        }
+        self.records.extend(other.records);
+    }
+
+    /// Every xref in use by a preserved record, which generated xrefs must not collide with.
+    pub fn xrefs(&self) -> impl Iterator<Item = &String> {
+        self.records.iter().filter_map(|record| record.xref.as_ref())
+    }
+}
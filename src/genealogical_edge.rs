@@ -0,0 +1,199 @@
+use graph::{edge::GraphEdgeTrait, Graph};
+use iced::{
+    alignment,
+    widget::canvas::{stroke::LineDash, Frame, Path, Stroke, Text},
+    Color, Point, Rectangle,
+};
+
+use crate::genealogical_node::GenealogicalNode;
+
+/// How far from an edge the cursor still hits it.
+const HIT_DISTANCE: f32 = 4.0;
+const ASSOCIATION_COLOR: Color = Color::from_rgb(0.95, 0.69, 0.2);
+
+/// What an edge of the chart stands for, told by the families and associations of the tree.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum EdgeKind {
+    /// Drawn, but not yet part of a family.
+    #[default]
+    Unknown,
+    /// Between partners, labelled with how they are related, e.g. `m. 1850`.
+    Partners(Option<String>),
+    /// Between a parent, either end of the edge, and a child, dashed when the child belongs to none of that parent's
+    /// families by birth.
+    Parent { parent: u128, by_birth: bool },
+    /// From a person to someone associated with them or with one of their events, labelled with the role.
+    Association(String),
+}
+
+/// What the node an edge is drawn to is to the node it is drawn from, chosen before drawing it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tie {
+    Partner,
+    Parent,
+    Child,
+}
+
+impl Tie {
+    /// The kind of an edge drawn from `start` to `end`.
+    pub fn edge_kind(self, start: u128, end: u128) -> EdgeKind {
+        match self {
+            Tie::Partner => EdgeKind::Partners(None),
+            Tie::Parent => EdgeKind::Parent {
+                parent: end,
+                by_birth: true,
+            },
+            Tie::Child => EdgeKind::Parent {
+                parent: start,
+                by_birth: true,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GenealogicalEdge {
+    start: u128,
+    end: u128,
+    pub kind: EdgeKind,
+}
+
+impl GenealogicalEdge {
+    pub fn association(start: u128, end: u128, label: String) -> Self {
+        Self {
+            start,
+            end,
+            kind: EdgeKind::Association(label),
+        }
+    }
+
+    pub fn is_association(&self) -> bool {
+        matches!(self.kind, EdgeKind::Association(_))
+    }
+
+    /// The line from the bottom of the upper node to the top of the lower one, turning halfway between them.
+    fn family_route(start: Rectangle, end: Rectangle) -> [Point; 4] {
+        let (upper, lower) = if start.y > end.y { (end, start) } else { (start, end) };
+        let first = Point::new(upper.center_x(), upper.y + upper.height);
+        let last = Point::new(lower.center_x(), lower.y);
+        let second = Point::new(first.x, first.y + (last.y - first.y) / 2.0);
+        let third = Point::new(last.x, second.y);
+        [first, second, third, last]
+    }
+
+    /// The straight line between the middles of the sides the nodes face each other with.
+    fn association_route(start: Rectangle, end: Rectangle) -> [Point; 2] {
+        let side = |node: Rectangle, towards: Point| {
+            let center = node.center();
+            let (dx, dy) = (towards.x - center.x, towards.y - center.y);
+            if dx.abs() * node.height > dy.abs() * node.width {
+                Point::new(center.x + dx.signum() * node.width / 2.0, center.y)
+            } else {
+                Point::new(center.x, center.y + dy.signum() * node.height / 2.0)
+            }
+        };
+        [side(start, end.center()), side(end, start.center())]
+    }
+}
+
+impl GraphEdgeTrait for GenealogicalEdge {
+    fn new(start: u128, end: u128) -> Self {
+        Self {
+            start,
+            end,
+            kind: EdgeKind::Unknown,
+        }
+    }
+    fn start(&self) -> u128 {
+        self.start
+    }
+    fn end(&self) -> u128 {
+        self.end
+    }
+
+    fn draw(&self, frame: &mut Frame, start: Rectangle, end: Rectangle, hovered: bool) {
+        let width = if hovered { 4.0 } else { 2.0 };
+        let (route, stroke, label, label_position, color): (Vec<Point>, Stroke, Option<&str>, Point, Color) =
+            match &self.kind {
+                EdgeKind::Association(label) => {
+                    let route = Self::association_route(start, end);
+                    let stroke = Stroke {
+                        line_dash: LineDash {
+                            segments: &[8.0, 4.0],
+                            offset: 0,
+                        },
+                        ..Stroke::default().with_width(1.5).with_color(ASSOCIATION_COLOR)
+                    };
+                    let middle = Point::new((route[0].x + route[1].x) / 2.0, (route[0].y + route[1].y) / 2.0);
+                    (route.to_vec(), stroke, Some(label), middle, ASSOCIATION_COLOR)
+                }
+                kind => {
+                    let route = Self::family_route(start, end);
+                    let stroke = Stroke::default().with_width(width).with_color(Color::WHITE);
+                    let stroke = match kind {
+                        EdgeKind::Parent { by_birth: false, .. } => Stroke {
+                            line_dash: LineDash {
+                                segments: &[8.0, 6.0],
+                                offset: 0,
+                            },
+                            ..stroke
+                        },
+                        _ => stroke,
+                    };
+                    let label = match kind {
+                        EdgeKind::Partners(label) => label.as_deref(),
+                        _ => None,
+                    };
+                    let middle = Point::new((route[1].x + route[2].x) / 2.0, route[1].y);
+                    (route.to_vec(), stroke, label, middle, Color::WHITE)
+                }
+            };
+
+        for segment in route.windows(2) {
+            frame.stroke(&Path::line(segment[0], segment[1]), stroke);
+        }
+        if let Some(label) = label {
+            frame.fill_text(Text {
+                content: label.to_string(),
+                position: Point::new(label_position.x, label_position.y - 4.0),
+                color,
+                horizontal_alignment: alignment::Horizontal::Center,
+                vertical_alignment: alignment::Vertical::Bottom,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Associations are edited on the person they belong to, they cannot be picked on the canvas.
+    fn is_in_bounds(&self, point: Point, start: Rectangle, end: Rectangle) -> bool {
+        if self.is_association() {
+            return false;
+        }
+        Self::family_route(start, end)
+            .windows(2)
+            .any(|segment| distance_to_segment(point, segment[0], segment[1]) <= HIT_DISTANCE)
+    }
+}
+
+/// The edges that stand for family ties, leaving out the associations drawn next to them.
+pub fn family_edges(graph: &Graph<GenealogicalNode, GenealogicalEdge>) -> impl Iterator<Item = &GenealogicalEdge> {
+    graph.edges().iter().filter(|edge| !edge.is_association())
+}
+
+/// The family edges a person is an end of.
+pub fn family_edges_of(
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    node_id: u128,
+) -> impl Iterator<Item = &GenealogicalEdge> {
+    graph.edges_of(node_id).filter(|edge| !edge.is_association())
+}
+
+fn distance_to_segment(point: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return point.distance(a);
+    }
+    let t = (((point.x - a.x) * dx + (point.y - a.y) * dy) / length).clamp(0.0, 1.0);
+    point.distance(Point::new(a.x + t * dx, a.y + t * dy))
+}
@@ -1,39 +1,122 @@
+use std::fmt;
+
 use graph::node::GraphNodeTrait;
 use iced::{
-    widget::canvas::{Frame, Text},
-    Color, Point, Size, Vector,
+    widget::{
+        canvas::{Frame, Text},
+        image::Handle,
+    },
+    Color, Point, Rectangle, Size, Vector,
 };
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{
+    association::Association,
+    gedcom_io::parser::Structure,
+    life_event::{EventType, LifeEvent},
+    media::MediaLink,
+    name::PersonalName,
+    note::Note,
+    source::Citation,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Sex {
     Male,
     Female,
+    /// Neither only male nor only female, GEDCOM 7.0 `X`.
+    Intersex,
+    /// Recorded as not known, GEDCOM `U`.
+    Unknown,
+}
+
+impl Sex {
+    /// The values offered in the side panel.
+    pub const ALL: [Sex; 4] = [Sex::Male, Sex::Female, Sex::Intersex, Sex::Unknown];
+
+    /// Whether it says anything about the person, an unknown sex matches any other.
+    pub fn is_known(&self) -> bool {
+        *self != Sex::Unknown
+    }
+
+    /// The tint of the card.
+    fn color(&self) -> Color {
+        match self {
+            Sex::Male => Color::from_rgb(0.85, 0.91, 1.0),
+            Sex::Female => Color::from_rgb(1.0, 0.87, 0.91),
+            Sex::Intersex => Color::from_rgb(0.91, 0.87, 1.0),
+            Sex::Unknown => Color::from_rgb(0.92, 0.92, 0.92),
+        }
+    }
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct GenealogicalNode {
     id: u128,
+    xref: Option<String>,
+    /// The `INDI` record this node was imported from, so that what the node does not model survives an export.
+    gedcom_record: Option<Structure>,
     anchor: Point,
     size: Size,
     sex: Option<Sex>,
-    first_name: Option<String>,
-    last_name: Option<String>,
+    /// Every name the person went by, the preferred one is shown on the card.
+    names: Vec<PersonalName>,
+    events: Vec<LifeEvent>,
+    /// Sources for the person as a whole, events carry citations of their own.
+    citations: Vec<Citation>,
+    /// Notes on the person as a whole, events carry notes of their own.
+    notes: Vec<Note>,
+    /// People outside the family linked to the person as a whole, events carry associations of their own.
+    associations: Vec<Association>,
+    media: Vec<MediaLink>,
+    /// The media object shown on the card, one of the linked images.
+    portrait: Option<u128>,
+    /// The portrait scaled down to card size, `None` until it is decoded.
+    thumbnail: Option<Handle>,
 }
 
 impl GenealogicalNode {
     const NODE_PADDING_V: f32 = 0.2;
     const NODE_PADDING_H: f32 = 0.2;
     const NODE_FONT_SIZE: f32 = 16.0;
+    const THUMBNAIL_SIZE: f32 = 36.0;
+    const THUMBNAIL_MARGIN: f32 = 8.0;
+
+    /// Where the text starts, to the right of the thumbnail when there is one.
+    fn text_padding_h(&self) -> f32 {
+        match self.thumbnail {
+            Some(_) => 2.0 * Self::THUMBNAIL_MARGIN + Self::THUMBNAIL_SIZE,
+            None => self.size().width * Self::NODE_PADDING_H,
+        }
+    }
+
+    fn draw_thumbnail<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
+        if let Some(thumbnail) = &self.thumbnail {
+            let padding_v = self.size().height * Self::NODE_PADDING_V;
+            let bounds = Rectangle::new(
+                self.anchor() + Vector::new(Self::THUMBNAIL_MARGIN, padding_v),
+                Size::new(Self::THUMBNAIL_SIZE, Self::THUMBNAIL_SIZE),
+            );
+            frame.draw_image(bounds, thumbnail);
+        }
+        frame
+    }
 
     fn draw_first_name<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
-        if let Some(first_name) = &self.first_name {
-            let padding_h = self.size().width * Self::NODE_PADDING_H;
+        if let Some(first_name) = self.first_name() {
+            let padding_h = self.text_padding_h();
             let padding_v = self.size().height * Self::NODE_PADDING_V;
 
             let anchor = self.anchor() + Vector::new(padding_h, padding_v);
             frame.fill_text(Text {
-                content: first_name.clone(),
+                content: first_name,
                 size: Self::NODE_FONT_SIZE.into(),
                 position: anchor,
                 color: Color::BLACK,
@@ -44,13 +127,13 @@ impl GenealogicalNode {
     }
 
     fn draw_last_name<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
-        if let Some(last_name) = &self.last_name {
-            let padding_h = self.size().width * Self::NODE_PADDING_H;
+        if let Some(last_name) = self.preferred_name().and_then(PersonalName::full_surname) {
+            let padding_h = self.text_padding_h();
             let padding_v = self.size().height * Self::NODE_PADDING_V + Self::NODE_FONT_SIZE + 5.0;
 
             let anchor = self.anchor() + Vector::new(padding_h, padding_v);
             frame.fill_text(Text {
-                content: last_name.clone(),
+                content: last_name,
                 size: Self::NODE_FONT_SIZE.into(),
                 position: anchor,
                 color: Color::BLACK,
@@ -60,23 +143,164 @@ impl GenealogicalNode {
         frame
     }
 
+    fn draw_life_years<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
+        if let Some(life_years) = self.life_years() {
+            let padding_h = self.text_padding_h();
+            let padding_v = self.size().height * Self::NODE_PADDING_V + 2.0 * (Self::NODE_FONT_SIZE + 5.0);
+
+            let anchor = self.anchor() + Vector::new(padding_h, padding_v);
+            frame.fill_text(Text {
+                content: life_years,
+                size: Self::NODE_FONT_SIZE.into(),
+                position: anchor,
+                color: Color::BLACK,
+                ..Default::default()
+            });
+        }
+        frame
+    }
+
+    /// Birth and death years for the card, falling back to baptism and burial when those are all that is known.
+    pub fn life_years(&self) -> Option<String> {
+        let year_of = |event_types: &[EventType]| {
+            event_types.iter().find_map(|event_type| {
+                self.events
+                    .iter()
+                    .filter(|event| &event.event_type == event_type)
+                    .find_map(LifeEvent::year)
+            })
+        };
+        let birth = year_of(&[EventType::Birth, EventType::Christening, EventType::Baptism]);
+        let death = year_of(&[EventType::Death, EventType::Burial, EventType::Cremation]);
+        match (birth, death) {
+            (None, None) => None,
+            (Some(birth), None) => Some(format!("{} –", birth)),
+            (None, Some(death)) => Some(format!("– {}", death)),
+            (Some(birth), Some(death)) => Some(format!("{} – {}", birth, death)),
+        }
+    }
+
+    pub fn xref(&self) -> Option<String> {
+        self.xref.clone()
+    }
+    pub fn set_xref(&mut self, xref: String) {
+        self.xref = Some(xref);
+    }
+    /// Restores the id of a node read back from a project file, so that edges keep pointing at it.
+    pub fn set_id(&mut self, id: u128) {
+        self.id = id;
+    }
+    pub fn gedcom_record(&self) -> Option<&Structure> {
+        self.gedcom_record.as_ref()
+    }
+    pub fn set_gedcom_record(&mut self, record: Structure) {
+        self.gedcom_record = Some(record);
+    }
     pub fn sex(&self) -> Option<Sex> {
         self.sex.clone()
     }
     pub fn set_sex(&mut self, sex: Sex) {
         self.sex = Some(sex)
     }
-    pub fn first_name(&self) -> Option<String> {
-        self.first_name.clone()
+    pub fn names(&self) -> &[PersonalName] {
+        &self.names
+    }
+    pub fn name_mut(&mut self, index: usize) -> Option<&mut PersonalName> {
+        self.names.get_mut(index)
+    }
+    /// Adds a name, the preferred one when it is the first.
+    pub fn add_name(&mut self, mut name: PersonalName) {
+        name.preferred = self.names.is_empty();
+        self.names.push(name);
     }
-    pub fn set_first_name(&mut self, first_name: String) {
-        self.first_name = Some(first_name);
+    /// Removes a name, the next one becomes preferred when it was.
+    pub fn remove_name(&mut self, index: usize) {
+        if index < self.names.len() && self.names.remove(index).preferred {
+            if let Some(name) = self.names.first_mut() {
+                name.preferred = true;
+            }
+        }
+    }
+    /// The name shown on the card, the first one when none is marked.
+    pub fn preferred_name(&self) -> Option<&PersonalName> {
+        self.names.iter().find(|name| name.preferred).or(self.names.first())
     }
+    pub fn set_preferred_name(&mut self, index: usize) {
+        if index < self.names.len() {
+            for (other, name) in self.names.iter_mut().enumerate() {
+                name.preferred = other == index;
+            }
+        }
+    }
+    /// The given names of the preferred name.
+    pub fn first_name(&self) -> Option<String> {
+        self.preferred_name()?.given.clone()
+    }
+    /// The surname of the preferred name, without its prefix.
     pub fn last_name(&self) -> Option<String> {
-        self.last_name.clone()
+        self.preferred_name()?.surname.clone()
+    }
+    pub fn events(&self) -> &[LifeEvent] {
+        &self.events
+    }
+    pub fn event_mut(&mut self, index: usize) -> Option<&mut LifeEvent> {
+        self.events.get_mut(index)
+    }
+    pub fn add_event(&mut self, event: LifeEvent) {
+        self.events.push(event);
     }
-    pub fn set_last_name(&mut self, last_name: String) {
-        self.last_name = Some(last_name);
+    /// Puts the events in chronological order, those without a date that can be placed go last.
+    pub fn sort_events(&mut self) {
+        self.events
+            .sort_by_key(|event| event.parsed_date().and_then(|date| date.sort_key()).unwrap_or(i64::MAX));
+    }
+    pub fn remove_event(&mut self, index: usize) {
+        if index < self.events.len() {
+            self.events.remove(index);
+        }
+    }
+    pub fn citations(&self) -> &[Citation] {
+        &self.citations
+    }
+    pub fn citations_mut(&mut self) -> &mut Vec<Citation> {
+        &mut self.citations
+    }
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+    pub fn notes_mut(&mut self) -> &mut Vec<Note> {
+        &mut self.notes
+    }
+    pub fn associations(&self) -> &[Association] {
+        &self.associations
+    }
+    pub fn associations_mut(&mut self) -> &mut Vec<Association> {
+        &mut self.associations
+    }
+    pub fn media(&self) -> &[MediaLink] {
+        &self.media
+    }
+    pub fn media_mut(&mut self) -> &mut Vec<MediaLink> {
+        &mut self.media
+    }
+    /// Unlinks a media object, the portrait too when it was the one shown.
+    pub fn remove_media(&mut self, index: usize) {
+        if index < self.media.len() {
+            let link = self.media.remove(index);
+            if self.portrait == Some(link.object) && !self.media.iter().any(|other| other.object == link.object) {
+                self.set_portrait(None);
+            }
+        }
+    }
+    pub fn portrait(&self) -> Option<u128> {
+        self.portrait
+    }
+    pub fn set_portrait(&mut self, portrait: Option<u128>) {
+        self.portrait = portrait;
+        self.thumbnail = None;
+    }
+    pub fn set_thumbnail(&mut self, thumbnail: Option<Handle>) {
+        self.thumbnail = thumbnail;
     }
 }
 
@@ -85,10 +309,18 @@ impl GraphNodeTrait for GenealogicalNode {
         Self {
             anchor,
             id: Uuid::new_v4().as_u128(),
+            xref: None,
+            gedcom_record: None,
             size: Size::new(128.0, 96.0),
             sex: None,
-            first_name: None,
-            last_name: None,
+            names: vec![],
+            events: vec![],
+            citations: vec![],
+            notes: vec![],
+            associations: vec![],
+            media: vec![],
+            portrait: None,
+            thumbnail: None,
         }
     }
     fn id(&self) -> u128 {
@@ -107,8 +339,14 @@ impl GraphNodeTrait for GenealogicalNode {
         self.size
     }
 
+    fn color(&self) -> Color {
+        self.sex.as_ref().map(Sex::color).unwrap_or(Color::WHITE)
+    }
+
     fn draw_content<'a>(&self, frame: &'a mut Frame) -> &'a Frame {
+        self.draw_thumbnail(frame);
         self.draw_first_name(frame);
-        self.draw_last_name(frame)
+        self.draw_last_name(frame);
+        self.draw_life_years(frame)
     }
 }
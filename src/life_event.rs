@@ -0,0 +1,171 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    association::Association,
+    gedcom_io::{date::GedcomDate, parser::Structure},
+    note::Note,
+    place::Places,
+    source::Citation,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventType {
+    #[default]
+    Birth,
+    Christening,
+    Baptism,
+    Death,
+    Burial,
+    Cremation,
+    Adoption,
+    Census,
+    Emigration,
+    Immigration,
+    Naturalization,
+    Graduation,
+    Retirement,
+    Residence,
+    Occupation,
+    Education,
+    Probate,
+    Will,
+    /// Any other event, named by the user or by the `TYPE` of a GEDCOM `EVEN`.
+    Other(String),
+}
+
+impl EventType {
+    /// The types offered in the side panel, `Other` stands for every custom type.
+    pub const ALL: [EventType; 19] = [
+        EventType::Birth,
+        EventType::Christening,
+        EventType::Baptism,
+        EventType::Death,
+        EventType::Burial,
+        EventType::Cremation,
+        EventType::Adoption,
+        EventType::Census,
+        EventType::Emigration,
+        EventType::Immigration,
+        EventType::Naturalization,
+        EventType::Graduation,
+        EventType::Retirement,
+        EventType::Residence,
+        EventType::Occupation,
+        EventType::Education,
+        EventType::Probate,
+        EventType::Will,
+        EventType::Other(String::new()),
+    ];
+
+    const GEDCOM_TAGS: [(&'static str, EventType); 18] = [
+        ("BIRT", EventType::Birth),
+        ("CHR", EventType::Christening),
+        ("BAPM", EventType::Baptism),
+        ("DEAT", EventType::Death),
+        ("BURI", EventType::Burial),
+        ("CREM", EventType::Cremation),
+        ("ADOP", EventType::Adoption),
+        ("CENS", EventType::Census),
+        ("EMIG", EventType::Emigration),
+        ("IMMI", EventType::Immigration),
+        ("NATU", EventType::Naturalization),
+        ("GRAD", EventType::Graduation),
+        ("RETI", EventType::Retirement),
+        ("RESI", EventType::Residence),
+        ("OCCU", EventType::Occupation),
+        ("EDUC", EventType::Education),
+        ("PROB", EventType::Probate),
+        ("WILL", EventType::Will),
+    ];
+
+    /// The type of an individual event structure, `None` for tags that are not events.
+    pub fn from_structure(structure: &Structure) -> Option<Self> {
+        if structure.tag == "EVEN" {
+            return Some(EventType::Other(
+                structure.child_value("TYPE").unwrap_or_default().to_string(),
+            ));
+        }
+        Self::GEDCOM_TAGS
+            .iter()
+            .find(|(tag, _)| *tag == structure.tag)
+            .map(|(_, event_type)| event_type.clone())
+    }
+
+    pub fn gedcom_tag(&self) -> &'static str {
+        Self::GEDCOM_TAGS
+            .iter()
+            .find(|(_, event_type)| event_type == self)
+            .map(|(tag, _)| *tag)
+            .unwrap_or("EVEN")
+    }
+
+    /// Occupations and the like carry their description as the value of the GEDCOM line, `1 OCCU Farmer`.
+    pub fn described_by_value(&self) -> bool {
+        matches!(self, EventType::Occupation | EventType::Education | EventType::Other(_))
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventType::Other(name) if !name.is_empty() => write!(f, "{}", name),
+            EventType::Other(_) => write!(f, "Other"),
+            event_type => write!(f, "{:?}", event_type),
+        }
+    }
+}
+
+/// Something that happened in a person's life.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LifeEvent {
+    pub event_type: EventType,
+    pub date: Option<String>,
+    /// The id of the place in the tree's `Places`.
+    pub place: Option<u128>,
+    pub description: Option<String>,
+    /// The age of the person at the time, as GEDCOM writes it, e.g. `72y 3m`.
+    pub age: Option<String>,
+    #[serde(default)]
+    pub citations: Vec<Citation>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// People who took part, e.g. the witnesses of a marriage or the godparents at a baptism.
+    #[serde(default)]
+    pub associations: Vec<Association>,
+    /// The structure this event was imported from, so that addresses, media links and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl LifeEvent {
+    pub fn apply(&mut self, edit: EventEdit, places: &mut Places) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            EventEdit::Type(event_type) => self.event_type = event_type,
+            EventEdit::Date(date) => self.date = non_empty(date),
+            EventEdit::Place(place) => self.place = non_empty(place).map(|place| places.intern_exact(&place)),
+            EventEdit::Description(description) => self.description = non_empty(description),
+            EventEdit::Age(age) => self.age = non_empty(age),
+        }
+    }
+
+    /// The date as a GEDCOM date value, `None` when there is none or it is not one that can be read.
+    pub fn parsed_date(&self) -> Option<GedcomDate> {
+        self.date.as_deref()?.parse().ok()
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        self.parsed_date()?.year()
+    }
+}
+
+/// A change made to one field of an event in the side panel.
+#[derive(Debug, Clone)]
+pub enum EventEdit {
+    Type(EventType),
+    Date(String),
+    Place(String),
+    Description(String),
+    Age(String),
+}
@@ -1,48 +1,205 @@
+mod association;
+mod autosave;
+mod diagnostics_panel;
+mod family;
+mod gedcom_io;
+mod genealogical_edge;
 mod genealogical_node;
+mod life_event;
+mod media;
+mod merge_panel;
+mod name;
+mod note;
+mod place;
+mod places_panel;
+mod project;
+mod recovery_panel;
+mod relationship;
+mod repository;
 mod side_panel;
+mod source;
+mod thumbnails;
 
-use std::{env::current_dir, fs::File, io::Read, time::Duration};
+use std::{
+    env::current_dir,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use association::{draw_associations, remove_associations_with, Association, AssociationRole, AssociationTarget};
+use autosave::{recovered_session, recovery_path, Autosave};
 
-use gedcom::parse;
+use diagnostics_panel::diagnostics_panel;
+use family::{Families, Pedigree};
+use gedcom_io::{
+    diagnostics::Diagnostic, export::export_tree, gedzip::write_gedzip, gramps::export::export_gramps,
+    import::import_file, merge::PendingMerge, preserved::PreservedRecords, FileFormat, GedcomVersion,
+};
+use genealogical_edge::{GenealogicalEdge, Tie};
 use genealogical_node::{GenealogicalNode, Sex};
-use graph::{Graph, GraphMessage};
+use graph::{edge::GraphEdgeTrait, Graph, GraphMessage, Removal};
 use iced::{
     time,
-    widget::{container, row},
+    widget::{column, container, image::Handle, row, text_editor},
     Element, Error,
     Length::Fill,
-    Subscription, Task,
+    Point, Subscription, Task,
 };
+use life_event::{EventEdit, LifeEvent};
+use media::{Media, MediaLink, MediaObject, MediaTarget};
+use merge_panel::merge_panel;
+use name::{NameEdit, PersonalName};
+use note::{Note, NoteEditor, NoteTarget, NoteText, Notes, SharedNote};
+use place::{PlaceStandardization, Places};
+use places_panel::places_panel;
+use project::{Project, PROJECT_EXTENSION};
+use recovery_panel::recovery_panel;
+use relationship::{RelationshipEdit, RelationshipEvent, RelationshipKind};
+use repository::{Repository, RepositoryEdit, RepositoryLink};
 use rfd::{AsyncFileDialog, FileHandle};
-use side_panel::side_panel;
+use side_panel::{side_panel, NotesView};
+use source::{Citation, CitationEdit, CitationTarget, Source, SourceEdit, Sources};
+use thumbnails::Thumbnails;
 
 #[derive(Debug, Clone)]
 enum Message {
     Graph(GraphMessage),
+    /// Starts drawing an edge from a person to whoever is clicked next, who becomes the given tie of the person.
+    DrawTie((u128, Tie)),
     Tick,
-    UpdateNodeFirstName((u128, String)),
-    UpdateNodeLastName((u128, String)),
+    AddNodeName(u128),
+    UpdateNodeName((u128, usize, NameEdit)),
+    RemoveNodeName((u128, usize)),
+    SetPreferredName((u128, usize)),
     SetNodeSex((u128, Sex)),
+    SetPedigree((u128, u128, Pedigree)),
+    SetRelationshipKind((u128, RelationshipKind)),
+    AddRelationshipEvent(u128),
+    UpdateRelationshipEvent((u128, usize, RelationshipEdit)),
+    RemoveRelationshipEvent((u128, usize)),
+    AddNodeEvent(u128),
+    UpdateNodeEvent((u128, usize, EventEdit)),
+    RemoveNodeEvent((u128, usize)),
+    SortNodeEvents(u128),
+    AddSource,
+    SelectSource(u128),
+    CloseSource,
+    UpdateSource((u128, SourceEdit)),
+    AddRepository,
+    SelectRepository(u128),
+    CloseRepository,
+    UpdateRepository((u128, RepositoryEdit)),
+    LinkRepository((u128, u128)),
+    UpdateCallNumber((u128, usize, String)),
+    RemoveRepositoryLink((u128, usize)),
+    AddCitation(CitationTarget),
+    UpdateCitation((CitationTarget, usize, CitationEdit)),
+    RemoveCitation((CitationTarget, usize)),
+    AddAssociation((AssociationTarget, u128)),
+    UpdateAssociationRole((AssociationTarget, usize, AssociationRole)),
+    RemoveAssociation((AssociationTarget, usize)),
+    AttachMedia(MediaTarget),
+    AttachMediaResult((MediaTarget, Option<FileHandle>)),
+    LinkMedia((MediaTarget, u128)),
+    RemoveMedia((MediaTarget, usize)),
+    UpdateMediaTitle((u128, String)),
+    SetPortrait((u128, Option<u128>)),
+    ThumbnailLoaded((PathBuf, Option<Handle>)),
+    AddNote(NoteTarget),
+    LinkNote((NoteTarget, u128)),
+    EditNote((NoteTarget, usize)),
+    NoteEditorAction(text_editor::Action),
+    CloseNoteEditor,
+    ShareNote((NoteTarget, usize)),
+    RemoveNote((NoteTarget, usize)),
+    SearchNotes(String),
+    OpenNote((NoteTarget, usize)),
     MenuBar(menubar::Event),
+    OpenProjectResult(Option<FileHandle>),
+    SaveProjectResult(Option<FileHandle>),
     OpenFileResult(Option<FileHandle>),
+    MergeFileResult(Option<FileHandle>),
+    ToggleMergeCandidate(usize),
+    ConfirmMerge,
+    CancelMerge,
+    ExportFileResult((Option<FileHandle>, FileFormat)),
+    TogglePlaceVariants(usize),
+    SetPreferredPlace((usize, u128)),
+    ConfirmPlaceStandardization,
+    CancelPlaceStandardization,
+    DismissDiagnostics,
+    RestoreSession,
+    DiscardSession,
 }
 
 struct App {
-    graph: Graph<GenealogicalNode>,
+    graph: Graph<GenealogicalNode, GenealogicalEdge>,
+    preserved: PreservedRecords,
+    families: Families,
+    places: Places,
+    sources: Sources,
+    media: Media,
+    notes: Notes,
+    thumbnails: Thumbnails,
+    /// The source being edited in the side panel.
+    selected_source: Option<u128>,
+    /// The repository being edited in the side panel.
+    selected_repository: Option<u128>,
+    note_editor: Option<NoteEditor>,
+    note_search: String,
+    diagnostics: Vec<Diagnostic>,
+    /// An imported file whose matches against the current tree are waiting to be reviewed.
+    pending_merge: Option<PendingMerge>,
+    /// Spelling variants of places waiting to be reviewed before they are standardized.
+    place_standardization: Option<PlaceStandardization>,
+    /// Where the open project was opened from or last saved to, `None` until it is saved as a project.
+    project_path: Option<PathBuf>,
+    autosave: Autosave,
+    /// What the edge being drawn will stand for, chosen in the side panel before drawing it.
+    drawing_tie: Option<Tie>,
+    /// When the unsaved session left by a previous run was autosaved, until the user restores or discards it.
+    recovered_session: Option<SystemTime>,
 }
 
 impl App {
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Tick => self.graph.tick(),
-            Message::UpdateNodeFirstName((node_id, name)) => {
+            Message::Tick => {
+                self.graph.tick();
+                // Writing now would overwrite the session the user has not decided about yet.
+                if self.recovered_session.is_none() {
+                    self.autosave.tick(
+                        &self.graph,
+                        &self.preserved,
+                        &self.families,
+                        &self.places,
+                        &self.sources,
+                        &self.media,
+                        &self.notes,
+                    );
+                }
+            }
+            Message::AddNodeName(node_id) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                node.add_name(PersonalName::default());
+                self.graph.redraw();
+            }
+            Message::UpdateNodeName((node_id, index, edit)) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                if let Some(name) = node.name_mut(index) {
+                    name.apply(edit);
+                }
+                self.graph.redraw();
+            }
+            Message::RemoveNodeName((node_id, index)) => {
                 let node = self.graph.get_node_mut_unsafe(Some(node_id));
-                node.set_first_name(name);
+                node.remove_name(index);
                 self.graph.redraw();
             }
-            Message::UpdateNodeLastName((node_id, name)) => {
+            Message::SetPreferredName((node_id, index)) => {
                 let node = self.graph.get_node_mut_unsafe(Some(node_id));
-                node.set_last_name(name);
+                node.set_preferred_name(index);
                 self.graph.redraw();
             }
             Message::SetNodeSex((node_id, sex)) => {
@@ -50,38 +207,782 @@ impl App {
                 node.set_sex(sex);
                 self.graph.redraw();
             }
+            Message::SetPedigree((family_id, child_id, pedigree)) => {
+                if let Some(link) = self
+                    .families
+                    .get_mut(family_id)
+                    .and_then(|family| family.children.iter_mut().find(|link| link.child == child_id))
+                {
+                    link.pedigree = pedigree;
+                }
+                self.families.style_family_edges(family_id, &mut self.graph);
+            }
+            Message::SetRelationshipKind((family_id, kind)) => {
+                if let Some(family) = self.families.get_mut(family_id) {
+                    family.kind = kind;
+                }
+                self.families.style_family_edges(family_id, &mut self.graph);
+            }
+            Message::AddRelationshipEvent(family_id) => {
+                if let Some(family) = self.families.get_mut(family_id) {
+                    family.events.push(RelationshipEvent::default());
+                }
+                self.families.style_family_edges(family_id, &mut self.graph);
+            }
+            Message::UpdateRelationshipEvent((family_id, index, edit)) => {
+                if let Some(event) = self
+                    .families
+                    .get_mut(family_id)
+                    .and_then(|family| family.events.get_mut(index))
+                {
+                    event.apply(edit, &mut self.places);
+                }
+                self.places.remove_unused(&self.graph, &self.families);
+                self.families.style_family_edges(family_id, &mut self.graph);
+            }
+            Message::RemoveRelationshipEvent((family_id, index)) => {
+                if let Some(family) = self
+                    .families
+                    .get_mut(family_id)
+                    .filter(|family| index < family.events.len())
+                {
+                    family.events.remove(index);
+                }
+                self.places.remove_unused(&self.graph, &self.families);
+                self.families.style_family_edges(family_id, &mut self.graph);
+            }
+            Message::AddNodeEvent(node_id) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                node.add_event(LifeEvent::default());
+                self.graph.redraw();
+            }
+            Message::UpdateNodeEvent((node_id, index, edit)) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                if let Some(event) = node.event_mut(index) {
+                    event.apply(edit, &mut self.places);
+                }
+                self.places.remove_unused(&self.graph, &self.families);
+                // The type of the event is part of the label of its associations.
+                draw_associations(&mut self.graph);
+            }
+            Message::RemoveNodeEvent((node_id, index)) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                node.remove_event(index);
+                // The events after it moved up, an open note of one of them would now belong to another.
+                self.close_event_note_editor(node_id);
+                self.places.remove_unused(&self.graph, &self.families);
+                draw_associations(&mut self.graph);
+            }
+            Message::SortNodeEvents(node_id) => {
+                let node = self.graph.get_node_mut_unsafe(Some(node_id));
+                node.sort_events();
+                self.close_event_note_editor(node_id);
+            }
+            Message::AddSource => self.selected_source = Some(self.sources.insert(Source::default())),
+            Message::SelectSource(source_id) => self.selected_source = Some(source_id),
+            Message::CloseSource => self.selected_source = None,
+            Message::UpdateSource((source_id, edit)) => {
+                if let Some(source) = self.sources.get_mut(source_id) {
+                    source.apply(edit);
+                }
+            }
+            Message::AddRepository => {
+                self.selected_repository = Some(self.sources.insert_repository(Repository::default()))
+            }
+            Message::SelectRepository(repository_id) => self.selected_repository = Some(repository_id),
+            Message::CloseRepository => self.selected_repository = None,
+            Message::UpdateRepository((repository_id, edit)) => {
+                if let Some(repository) = self.sources.repository_mut(repository_id) {
+                    repository.apply(edit);
+                }
+            }
+            Message::LinkRepository((source_id, repository_id)) => {
+                if let Some(source) = self.sources.get_mut(source_id) {
+                    source.repositories.push(RepositoryLink::new(repository_id));
+                }
+            }
+            Message::UpdateCallNumber((source_id, index, call_number)) => {
+                if let Some(link) = self
+                    .sources
+                    .get_mut(source_id)
+                    .and_then(|source| source.repositories.get_mut(index))
+                {
+                    link.call_number = Some(call_number).filter(|call_number| !call_number.trim().is_empty());
+                }
+            }
+            Message::RemoveRepositoryLink((source_id, index)) => {
+                if let Some(source) = self
+                    .sources
+                    .get_mut(source_id)
+                    .filter(|source| index < source.repositories.len())
+                {
+                    source.repositories.remove(index);
+                }
+            }
+            Message::AddCitation(target) => {
+                let source = self
+                    .selected_source
+                    .or_else(|| self.sources.iter().next().map(Source::id));
+                if let (Some(source), Some(citations)) = (source, self.citations_mut(&target)) {
+                    citations.push(Citation::new(source));
+                }
+            }
+            Message::UpdateCitation((target, index, edit)) => {
+                if let Some(citation) = self
+                    .citations_mut(&target)
+                    .and_then(|citations| citations.get_mut(index))
+                {
+                    citation.apply(edit);
+                }
+            }
+            Message::RemoveCitation((target, index)) => {
+                if let Some(citations) = self.citations_mut(&target).filter(|citations| index < citations.len()) {
+                    citations.remove(index);
+                }
+            }
+            Message::AddAssociation((target, person)) => {
+                if let Some(associations) = self.associations_mut(target) {
+                    associations.push(Association::new(person));
+                }
+                draw_associations(&mut self.graph);
+            }
+            Message::UpdateAssociationRole((target, index, role)) => {
+                if let Some(association) = self
+                    .associations_mut(target)
+                    .and_then(|associations| associations.get_mut(index))
+                {
+                    association.role = role;
+                }
+                draw_associations(&mut self.graph);
+            }
+            Message::RemoveAssociation((target, index)) => {
+                if let Some(associations) = self
+                    .associations_mut(target)
+                    .filter(|associations| index < associations.len())
+                {
+                    associations.remove(index);
+                }
+                draw_associations(&mut self.graph);
+            }
+            Message::AttachMedia(target) => {
+                let mut dialog = AsyncFileDialog::new();
+                if let Some(directory) = self.media.directory().filter(|directory| directory.is_dir()) {
+                    dialog = dialog.set_directory(directory);
+                }
+                return Task::perform(dialog.pick_file(), move |handle| {
+                    Message::AttachMediaResult((target.clone(), handle))
+                });
+            }
+            Message::AttachMediaResult((target, handle)) => {
+                if let Some(handle) = handle {
+                    let file = self.media.file_name(handle.path());
+                    let existing = self
+                        .media
+                        .iter()
+                        .find(|object| object.file == file)
+                        .map(MediaObject::id);
+                    let object = existing.unwrap_or_else(|| self.media.insert(MediaObject::new(file)));
+                    return self.link_media(target, object);
+                }
+            }
+            Message::LinkMedia((target, object)) => return self.link_media(target, object),
+            Message::RemoveMedia((target, index)) => {
+                match target {
+                    MediaTarget::Person(node_id) => {
+                        if let Some(node) = self.graph.get_node_mut(Some(node_id)) {
+                            node.remove_media(index);
+                        }
+                    }
+                    MediaTarget::Source(source_id) => {
+                        if let Some(source) = self
+                            .sources
+                            .get_mut(source_id)
+                            .filter(|source| index < source.media.len())
+                        {
+                            source.media.remove(index);
+                        }
+                    }
+                }
+                return self.thumbnails.refresh(&mut self.graph, &self.media);
+            }
+            Message::UpdateMediaTitle((object, title)) => {
+                if let Some(object) = self.media.get_mut(object) {
+                    object.title = Some(title).filter(|title| !title.trim().is_empty());
+                }
+            }
+            Message::SetPortrait((node_id, portrait)) => {
+                if let Some(node) = self.graph.get_node_mut(Some(node_id)) {
+                    node.set_portrait(portrait);
+                }
+                return self.thumbnails.refresh(&mut self.graph, &self.media);
+            }
+            Message::ThumbnailLoaded((path, thumbnail)) => {
+                self.thumbnails.insert(path, thumbnail);
+                return self.thumbnails.refresh(&mut self.graph, &self.media);
+            }
+            Message::AddNote(target) => {
+                if let Some(notes) = self.notes_mut(&target) {
+                    notes.push(Note::new(NoteText::Inline(String::new())));
+                    let index = notes.len() - 1;
+                    self.note_editor = Some(NoteEditor::new(target, index, ""));
+                }
+            }
+            Message::LinkNote((target, shared)) => {
+                if let Some(notes) = self.notes_mut(&target) {
+                    notes.push(Note::new(NoteText::Shared(shared)));
+                }
+            }
+            Message::EditNote((target, index)) => self.edit_note(target, index),
+            Message::NoteEditorAction(action) => {
+                let Some(editor) = self.note_editor.as_mut() else {
+                    return Task::none();
+                };
+                let is_edit = action.is_edit();
+                editor.content.perform(action);
+                if !is_edit {
+                    return Task::none();
+                }
+                let (target, index, text) = (editor.target.clone(), editor.index, editor.text());
+                let Some(note) = self.notes_mut(&target).and_then(|notes| notes.get_mut(index)) else {
+                    self.note_editor = None;
+                    return Task::none();
+                };
+                let shared = match &mut note.text {
+                    NoteText::Inline(inline) => {
+                        *inline = text;
+                        return Task::none();
+                    }
+                    NoteText::Shared(shared) => *shared,
+                };
+                if let Some(shared) = self.notes.get_mut(shared) {
+                    shared.text = text;
+                }
+            }
+            Message::CloseNoteEditor => self.note_editor = None,
+            Message::ShareNote((target, index)) => {
+                let Some(note) = self.notes_mut(&target).and_then(|notes| notes.get_mut(index)) else {
+                    return Task::none();
+                };
+                if let NoteText::Inline(text) = note.text.clone() {
+                    // The imported structure keeps what the note said next to its text, e.g. its language.
+                    let mut shared = SharedNote::new(text);
+                    shared.gedcom_record = note.gedcom_record.take();
+                    note.text = NoteText::Shared(shared.id());
+                    self.notes.insert(shared);
+                }
+            }
+            Message::RemoveNote((target, index)) => {
+                if let Some(notes) = self.notes_mut(&target).filter(|notes| index < notes.len()) {
+                    notes.remove(index);
+                }
+                // The notes after it moved up, so the editor would now show another one.
+                if self.note_editor.as_ref().is_some_and(|editor| editor.target == target) {
+                    self.note_editor = None;
+                }
+            }
+            Message::SearchNotes(query) => self.note_search = query,
+            Message::OpenNote((target, index)) => {
+                match &target {
+                    NoteTarget::Person(node_id) | NoteTarget::Event((node_id, _)) => {
+                        self.graph.set_selected_node(*node_id)
+                    }
+                    NoteTarget::Relationship(partners) => {
+                        if let Some(node_id) = partners.first() {
+                            self.graph.set_selected_node(*node_id);
+                        }
+                    }
+                    NoteTarget::Source(source_id) => self.selected_source = Some(*source_id),
+                }
+                self.edit_note(target, index);
+                self.graph.redraw();
+            }
             Message::MenuBar(event) => match event {
+                menubar::Event::OpenProject => {
+                    return Task::perform(
+                        AsyncFileDialog::new()
+                            .set_directory(current_dir().unwrap())
+                            .add_filter("", &[PROJECT_EXTENSION])
+                            .pick_file(),
+                        Message::OpenProjectResult,
+                    )
+                }
+                menubar::Event::SaveProject => match self.project_path.clone() {
+                    Some(path) => self.save_project(path),
+                    None => return Self::pick_project_file(),
+                },
+                menubar::Event::SaveProjectAs => return Self::pick_project_file(),
                 menubar::Event::OpenFile => {
                     return Task::perform(
                         AsyncFileDialog::new()
                             .set_directory(current_dir().unwrap())
-                            .add_filter("", &["GED"])
+                            .add_filter("", &["GED", "gdz", "gramps"])
                             .pick_file(),
                         |handle| Message::OpenFileResult(handle),
                     )
                 }
+                menubar::Event::MergeFile => {
+                    return Task::perform(
+                        AsyncFileDialog::new()
+                            .set_directory(current_dir().unwrap())
+                            .add_filter("", &["GED", "gdz", "gramps"])
+                            .pick_file(),
+                        Message::MergeFileResult,
+                    )
+                }
+                menubar::Event::ExportGedcom551 => {
+                    return Self::pick_export_file(FileFormat::Gedcom(GedcomVersion::V551))
+                }
+                menubar::Event::ExportGedcom555 => {
+                    return Self::pick_export_file(FileFormat::Gedcom(GedcomVersion::V555))
+                }
+                menubar::Event::ExportGedcom70 => {
+                    return Self::pick_export_file(FileFormat::Gedcom(GedcomVersion::V70))
+                }
+                menubar::Event::ExportGedzip => return Self::pick_export_file(FileFormat::Gedzip),
+                menubar::Event::ExportGramps => return Self::pick_export_file(FileFormat::Gramps),
+                menubar::Event::StandardizePlaces => {
+                    self.place_standardization =
+                        Some(PlaceStandardization::new(&self.places, &self.graph, &self.families))
+                }
             },
+            Message::TogglePlaceVariants(index) => {
+                if let Some(standardization) = self.place_standardization.as_mut() {
+                    standardization.toggle(index);
+                }
+            }
+            Message::SetPreferredPlace((index, place)) => {
+                if let Some(standardization) = self.place_standardization.as_mut() {
+                    standardization.set_preferred(index, place);
+                }
+            }
+            Message::ConfirmPlaceStandardization => {
+                if let Some(standardization) = self.place_standardization.take() {
+                    standardization.apply(&mut self.graph, &mut self.families, &mut self.places);
+                }
+            }
+            Message::CancelPlaceStandardization => self.place_standardization = None,
+            Message::OpenProjectResult(handle) => {
+                if let Some(handle) = handle {
+                    match Project::read(handle.path()) {
+                        Ok(project) => {
+                            (
+                                self.graph,
+                                self.preserved,
+                                self.families,
+                                self.places,
+                                self.sources,
+                                self.media,
+                                self.notes,
+                            ) = project.into_document();
+                            self.selected_source = None;
+                            self.selected_repository = None;
+                            self.note_editor = None;
+                            self.pending_merge = None;
+                            self.place_standardization = None;
+                            self.diagnostics.clear();
+                            self.project_path = Some(handle.path().to_path_buf());
+                            self.autosave.mark_saved(
+                                &self.graph,
+                                &self.preserved,
+                                &self.families,
+                                &self.places,
+                                &self.sources,
+                                &self.media,
+                                &self.notes,
+                            );
+                            return self.thumbnails.refresh(&mut self.graph, &self.media);
+                        }
+                        Err(error) => self.diagnostics = vec![Diagnostic::error(None, error)],
+                    }
+                }
+            }
+            Message::SaveProjectResult(handle) => {
+                if let Some(handle) = handle {
+                    self.save_project(handle.path().to_path_buf());
+                }
+            }
             Message::OpenFileResult(handle) => {
                 if let Some(handle) = handle {
-                    let mut file = File::open(handle.path()).unwrap();
-                    let mut content = String::new();
-                    file.read_to_string(&mut content).unwrap();
-                    let res = parse(content.chars());
-                    return Task::batch(
-                        res.individuals
-                            .iter()
-                            .map(|ind| Task::done(Message::Graph(GraphMessage::InsertNode(None)))),
+                    let tree = import_file(handle.path(), Point::ORIGIN);
+                    self.graph = Graph::default();
+                    self.preserved = tree.preserved;
+                    self.families = tree.families;
+                    self.places = tree.places;
+                    self.sources = tree.sources;
+                    self.media = tree.media;
+                    self.notes = tree.notes;
+                    self.selected_source = None;
+                    self.selected_repository = None;
+                    self.note_editor = None;
+                    self.pending_merge = None;
+                    self.place_standardization = None;
+                    self.project_path = None;
+                    for node in tree.nodes {
+                        self.graph.insert_node(node);
+                    }
+                    for (start, end) in tree.edges {
+                        self.graph.add_edge_between_nodes(Some(start), end);
+                    }
+                    self.families.style_edges(&mut self.graph);
+                    draw_associations(&mut self.graph);
+                    self.diagnostics = tree.diagnostics;
+                    return self.thumbnails.refresh(&mut self.graph, &self.media);
+                }
+            }
+            Message::MergeFileResult(handle) => {
+                if let Some(handle) = handle {
+                    let mut tree = import_file(handle.path(), Point::ORIGIN);
+                    self.diagnostics = std::mem::take(&mut tree.diagnostics);
+                    let pending = PendingMerge::new(&self.graph, &self.families, tree);
+                    if pending.candidates.is_empty() {
+                        pending.merge_into(
+                            &mut self.graph,
+                            &mut self.preserved,
+                            &mut self.families,
+                            &mut self.places,
+                            &mut self.sources,
+                            &mut self.media,
+                            &mut self.notes,
+                        );
+                        self.families.style_edges(&mut self.graph);
+                        draw_associations(&mut self.graph);
+                        return self.thumbnails.refresh(&mut self.graph, &self.media);
+                    } else {
+                        self.pending_merge = Some(pending);
+                    }
+                }
+            }
+            Message::ToggleMergeCandidate(index) => {
+                if let Some(pending) = self.pending_merge.as_mut() {
+                    pending.toggle(index);
+                }
+            }
+            Message::ConfirmMerge => {
+                if let Some(pending) = self.pending_merge.take() {
+                    pending.merge_into(
+                        &mut self.graph,
+                        &mut self.preserved,
+                        &mut self.families,
+                        &mut self.places,
+                        &mut self.sources,
+                        &mut self.media,
+                        &mut self.notes,
                     );
+                    self.families.style_edges(&mut self.graph);
+                    draw_associations(&mut self.graph);
+                    return self.thumbnails.refresh(&mut self.graph, &self.media);
+                }
+            }
+            Message::CancelMerge => self.pending_merge = None,
+            Message::DismissDiagnostics => self.diagnostics.clear(),
+            Message::RestoreSession => {
+                self.recovered_session = None;
+                match recovery_path()
+                    .ok_or_else(|| "No recovery location".to_string())
+                    .and_then(|path| Project::read(&path))
+                {
+                    Ok(project) => {
+                        (
+                            self.graph,
+                            self.preserved,
+                            self.families,
+                            self.places,
+                            self.sources,
+                            self.media,
+                            self.notes,
+                        ) = project.into_document();
+                        self.selected_source = None;
+                        self.selected_repository = None;
+                        self.note_editor = None;
+                        self.pending_merge = None;
+                        self.place_standardization = None;
+                        self.project_path = None;
+                        return self.thumbnails.refresh(&mut self.graph, &self.media);
+                    }
+                    Err(error) => self.diagnostics = vec![Diagnostic::error(None, error)],
+                }
+            }
+            Message::DiscardSession => {
+                self.recovered_session = None;
+                self.autosave.discard();
+            }
+            Message::ExportFileResult((handle, format)) => {
+                if let Some(handle) = handle {
+                    let content = match format {
+                        FileFormat::Gedcom(version) => Ok(export_tree(
+                            &self.graph,
+                            &self.preserved,
+                            &self.families,
+                            &self.places,
+                            &self.sources,
+                            &self.media,
+                            &self.notes,
+                            version,
+                        )
+                        .into_bytes()),
+                        FileFormat::Gedzip => write_gedzip(
+                            &export_tree(
+                                &self.graph,
+                                &self.preserved,
+                                &self.families,
+                                &self.places,
+                                &self.sources,
+                                &self.media,
+                                &self.notes,
+                                GedcomVersion::V70,
+                            ),
+                            &self.media,
+                        )
+                        .map_err(|error| format!("Could not write GEDZIP archive: {}", error)),
+                        FileFormat::Gramps => export_gramps(
+                            &self.graph,
+                            &self.preserved,
+                            &self.families,
+                            &self.places,
+                            &self.sources,
+                            &self.media,
+                            &self.notes,
+                        )
+                        .map_err(|error| format!("Could not write Gramps file: {}", error)),
+                    };
+                    let path = handle.path();
+                    let written = content.and_then(|content| {
+                        fs::write(path, content)
+                            .map_err(|error| format!("Could not write {}: {}", path.display(), error))
+                    });
+                    if let Err(error) = written {
+                        self.diagnostics = vec![Diagnostic::error(None, error)];
+                    }
+                }
+            }
+            Message::DrawTie((node_id, tie)) => {
+                self.drawing_tie = Some(tie);
+                self.graph.update(GraphMessage::DrawEdge(node_id));
+            }
+            Message::Graph(graph_message) => {
+                // A new edge is a new partner, parent or child as chosen before drawing it, offspring are children.
+                let drawn_tie = match graph_message {
+                    // The graph refuses an edge from a node to itself, there is no new edge to give the tie to.
+                    GraphMessage::InsertEdge(start, end) => self.drawing_tie.take().filter(|_| start != end),
+                    GraphMessage::InsertNode(Some(_)) => Some(Tie::Child),
+                    GraphMessage::CancelDrawEdge => {
+                        self.drawing_tie = None;
+                        None
+                    }
+                    _ => None,
+                };
+                let removal = match graph_message {
+                    GraphMessage::RemoveNode(id) => Some(Removal::Node(id)),
+                    GraphMessage::RemoveEdge(first, second) => Some(Removal::Edge(first, second)),
+                    _ => None,
+                };
+                self.graph.update(graph_message);
+                if let Some(edge) = drawn_tie.and_then(|tie| {
+                    let edge = self.graph.edges_mut().last()?;
+                    edge.kind = tie.edge_kind(edge.start(), edge.end());
+                    Some(edge.clone())
+                }) {
+                    if let Some(family_id) = self.families.add_edge(edge.start(), edge.end(), &edge.kind) {
+                        self.families.style_family_edges(family_id, &mut self.graph);
+                    }
+                }
+                match removal {
+                    Some(Removal::Node(node_id)) => self.forget_person(node_id),
+                    Some(Removal::Edge(first, second)) => {
+                        self.families.unlink(first, second, &self.graph);
+                        self.families.style_edges(&mut self.graph);
+                        // Removing the edges between the two took an association between them along.
+                        draw_associations(&mut self.graph);
+                    }
+                    None => {}
                 }
             }
-            Message::Graph(graph_message) => self.graph.update(graph_message),
         }
         Task::none()
     }
 
+    fn save_project(&mut self, path: PathBuf) {
+        match Project::new(
+            &self.graph,
+            &self.preserved,
+            &self.families,
+            &self.places,
+            &self.sources,
+            &self.media,
+            &self.notes,
+        )
+        .write(&path)
+        {
+            Ok(()) => {
+                self.project_path = Some(path);
+                self.autosave.mark_saved(
+                    &self.graph,
+                    &self.preserved,
+                    &self.families,
+                    &self.places,
+                    &self.sources,
+                    &self.media,
+                    &self.notes,
+                );
+            }
+            Err(error) => self.diagnostics = vec![Diagnostic::error(None, error)],
+        }
+    }
+
+    /// Drops everything that refers to a person removed from the graph.
+    fn forget_person(&mut self, node_id: u128) {
+        self.families.remove_person(node_id);
+        self.sources.remove_relationships_of(node_id);
+        self.notes.remove_relationships_of(node_id);
+        remove_associations_with(&mut self.graph, node_id);
+        if let Some(editor) = &self.note_editor {
+            let removed = match &editor.target {
+                NoteTarget::Person(id) | NoteTarget::Event((id, _)) => *id == node_id,
+                NoteTarget::Relationship(partners) => partners.contains(&node_id),
+                NoteTarget::Source(_) => false,
+            };
+            if removed {
+                self.note_editor = None;
+            }
+        }
+        self.places.remove_unused(&self.graph, &self.families);
+        self.families.style_edges(&mut self.graph);
+        draw_associations(&mut self.graph);
+    }
+
+    /// The citations of a person, event or relationship, `None` when the person or event is gone.
+    fn citations_mut(&mut self, target: &CitationTarget) -> Option<&mut Vec<Citation>> {
+        match target {
+            CitationTarget::Person(node_id) => Some(self.graph.get_node_mut(Some(*node_id))?.citations_mut()),
+            CitationTarget::Event((node_id, index)) => {
+                Some(&mut self.graph.get_node_mut(Some(*node_id))?.event_mut(*index)?.citations)
+            }
+            CitationTarget::Relationship(partners) => Some(self.sources.relationship_mut(partners)),
+        }
+    }
+
+    /// The associations of a person or event, `None` when the person or event is gone.
+    fn associations_mut(&mut self, target: AssociationTarget) -> Option<&mut Vec<Association>> {
+        match target {
+            AssociationTarget::Person(node_id) => Some(self.graph.get_node_mut(Some(node_id))?.associations_mut()),
+            AssociationTarget::Event((node_id, index)) => {
+                Some(&mut self.graph.get_node_mut(Some(node_id))?.event_mut(index)?.associations)
+            }
+        }
+    }
+
+    /// The notes of a person, event, relationship or source, `None` when it is gone.
+    fn notes_mut(&mut self, target: &NoteTarget) -> Option<&mut Vec<Note>> {
+        match target {
+            NoteTarget::Person(node_id) => Some(self.graph.get_node_mut(Some(*node_id))?.notes_mut()),
+            NoteTarget::Event((node_id, index)) => {
+                Some(&mut self.graph.get_node_mut(Some(*node_id))?.event_mut(*index)?.notes)
+            }
+            NoteTarget::Relationship(partners) => Some(self.notes.relationship_mut(partners)),
+            NoteTarget::Source(source_id) => Some(&mut self.sources.get_mut(*source_id)?.notes),
+        }
+    }
+
+    /// Closes the editor when it shows a note of an event of this person, e.g. once their events moved.
+    fn close_event_note_editor(&mut self, node_id: u128) {
+        if let Some(NoteEditor {
+            target: NoteTarget::Event((id, _)),
+            ..
+        }) = &self.note_editor
+        {
+            if *id == node_id {
+                self.note_editor = None;
+            }
+        }
+    }
+
+    /// Opens a note in the editor of the side panel.
+    fn edit_note(&mut self, target: NoteTarget, index: usize) {
+        let Some(note) = self.notes_mut(&target).and_then(|notes| notes.get(index)).cloned() else {
+            return;
+        };
+        let text = self.notes.text(&note).to_string();
+        self.note_editor = Some(NoteEditor::new(target, index, &text));
+    }
+
+    /// Links a media object to a person or source. The first image linked to a person becomes the portrait.
+    fn link_media(&mut self, target: MediaTarget, object: u128) -> Task<Message> {
+        match target {
+            MediaTarget::Person(node_id) => {
+                let is_image = self.media.get(object).is_some_and(MediaObject::is_image);
+                let Some(node) = self.graph.get_node_mut(Some(node_id)) else {
+                    return Task::none();
+                };
+                node.media_mut().push(MediaLink::new(object));
+                if node.portrait().is_none() && is_image {
+                    node.set_portrait(Some(object));
+                }
+                self.thumbnails.refresh(&mut self.graph, &self.media)
+            }
+            MediaTarget::Source(source_id) => {
+                if let Some(source) = self.sources.get_mut(source_id) {
+                    source.media.push(MediaLink::new(object));
+                }
+                Task::none()
+            }
+        }
+    }
+
+    fn pick_project_file() -> Task<Message> {
+        Task::perform(
+            AsyncFileDialog::new()
+                .set_directory(current_dir().unwrap())
+                .add_filter("", &[PROJECT_EXTENSION])
+                .set_file_name(format!("project.{}", PROJECT_EXTENSION))
+                .save_file(),
+            Message::SaveProjectResult,
+        )
+    }
+
+    fn pick_export_file(format: FileFormat) -> Task<Message> {
+        Task::perform(
+            AsyncFileDialog::new()
+                .set_directory(current_dir().unwrap())
+                .add_filter("", &[format.extension()])
+                .set_file_name(format!("export.{}", format.extension()))
+                .save_file(),
+            move |handle| Message::ExportFileResult((handle, format)),
+        )
+    }
+
     fn view(&self) -> Element<Message> {
-        let selected_node = self.graph.selected_node();
-        let content = row![self.graph.view().map(Message::Graph), side_panel(selected_node)];
+        let selected_source = self.selected_source.and_then(|id| self.sources.get(id));
+        let selected_repository = self.selected_repository.and_then(|id| self.sources.repository(id));
+        let mut content = column![];
+        if let Some(autosaved_at) = self.recovered_session {
+            content = content.push(recovery_panel(autosaved_at));
+        }
+        content = content.push(row![
+            self.graph.view().map(Message::Graph),
+            side_panel(
+                &self.graph,
+                &self.families,
+                &self.places,
+                &self.sources,
+                &self.media,
+                NotesView {
+                    notes: &self.notes,
+                    editor: self.note_editor.as_ref(),
+                    search: &self.note_search,
+                },
+                selected_source,
+                selected_repository,
+            )
+        ]);
+        if let Some(pending) = &self.pending_merge {
+            content = content.push(merge_panel(pending, &self.graph));
+        }
+        if let Some(standardization) = &self.place_standardization {
+            content = content.push(places_panel(standardization, &self.places, &self.graph, &self.families));
+        }
+        if !self.diagnostics.is_empty() {
+            content = content.push(diagnostics_panel(&self.diagnostics));
+        }
 
         container(content).width(Fill).height(Fill).into()
     }
@@ -90,14 +991,40 @@ impl App {
         Subscription::batch(vec![
             time::every(Duration::from_millis(100)).map(|_| Message::Tick),
             Subscription::run(menubar::setup_menu_bar).map(Message::MenuBar),
+            graph::keyboard_subscription().map(Message::Graph),
         ])
     }
 }
 
 impl Default for App {
     fn default() -> Self {
+        let graph = Graph::default();
+        let preserved = PreservedRecords::default();
+        let families = Families::default();
+        let places = Places::default();
+        let sources = Sources::default();
+        let media = Media::default();
+        let notes = Notes::default();
         Self {
-            graph: Graph::default(),
+            autosave: Autosave::new(&graph, &preserved, &families, &places, &sources, &media, &notes),
+            graph,
+            preserved,
+            families,
+            places,
+            sources,
+            media,
+            notes,
+            thumbnails: Thumbnails::default(),
+            selected_source: None,
+            selected_repository: None,
+            note_editor: None,
+            note_search: String::new(),
+            diagnostics: vec![],
+            pending_merge: None,
+            place_standardization: None,
+            project_path: None,
+            drawing_tie: None,
+            recovered_session: recovered_session(),
         }
     }
 }
@@ -0,0 +1,245 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::gedcom_io::parser::Structure;
+
+/// File extensions and the media types 7.0 writes in place of them.
+const MEDIA_TYPES: [(&str, &str); 11] = [
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("webp", "image/webp"),
+    ("pdf", "application/pdf"),
+    ("wav", "audio/wav"),
+    ("mp3", "audio/mpeg"),
+];
+
+/// A photo, scan or other document of the tree, GEDCOM's `OBJE`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaObject {
+    id: u128,
+    pub xref: Option<String>,
+    /// Where the file is as written in `FILE`, a path that is usually relative to the GEDCOM file, or a URL.
+    pub file: String,
+    /// The kind of file as it was given, `jpg` in 5.5.x or `image/jpeg` in 7.0.
+    pub format: Option<String>,
+    pub title: Option<String>,
+    /// The `OBJE` structure this object was imported from, so that notes, further files and the like survive an
+    /// export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl MediaObject {
+    pub fn new(file: String) -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            xref: None,
+            file,
+            format: None,
+            title: None,
+            gedcom_record: None,
+        }
+    }
+
+    /// Reads an `OBJE` record or inline object, `None` when it names no file.
+    ///
+    /// 5.5 put the format and title next to the file, 5.5.1 and later below it, both are understood.
+    pub fn from_structure(structure: &Structure) -> Option<Self> {
+        let file = structure.child("FILE")?;
+        let value = |tag| {
+            file.child_value(tag)
+                .or_else(|| structure.child_value(tag))
+                .map(String::from)
+        };
+        Some(Self {
+            xref: structure.xref.clone(),
+            format: value("FORM"),
+            title: value("TITL"),
+            gedcom_record: Some(structure.clone()),
+            ..Self::new(file.value.clone().unwrap_or_default())
+        })
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    /// The title, or the name of the file when it has none.
+    pub fn name(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            let file = self.file.trim_end_matches(['/', '\\']);
+            file.rsplit(['/', '\\']).next().unwrap_or(file).to_string()
+        })
+    }
+
+    /// The media type of the file, from its format or else its extension.
+    pub fn media_type(&self) -> Option<String> {
+        let format = match &self.format {
+            Some(format) if format.contains('/') => return Some(format.to_lowercase()),
+            Some(format) => format.clone(),
+            None => self.extension()?,
+        };
+        MEDIA_TYPES
+            .iter()
+            .find(|(extension, _)| extension.eq_ignore_ascii_case(format.trim()))
+            .map(|(_, media_type)| media_type.to_string())
+    }
+
+    /// The format as 5.5.x writes it, a file extension like `jpg`.
+    pub fn extension_format(&self) -> Option<String> {
+        match &self.format {
+            Some(format) if format.contains('/') => MEDIA_TYPES
+                .iter()
+                .find(|(_, media_type)| media_type.eq_ignore_ascii_case(format.trim()))
+                .map(|(extension, _)| extension.to_string()),
+            Some(format) => Some(format.clone()),
+            None => self.extension(),
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.media_type()
+            .is_some_and(|media_type| media_type.starts_with("image/"))
+    }
+
+    fn extension(&self) -> Option<String> {
+        let (_, extension) = self.file.rsplit_once('.')?;
+        (!extension.contains(['/', '\\'])).then(|| extension.to_lowercase())
+    }
+}
+
+/// A link from a person or source to one of the media objects of the tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaLink {
+    pub object: u128,
+    /// The `OBJE` pointer this link was imported from, so that a crop or title given with it survives an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl MediaLink {
+    pub fn new(object: u128) -> Self {
+        Self {
+            object,
+            gedcom_record: None,
+        }
+    }
+}
+
+/// What media are attached to.
+#[derive(Debug, Clone)]
+pub enum MediaTarget {
+    Person(u128),
+    Source(u128),
+}
+
+/// Every media object of the tree.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Media {
+    objects: Vec<MediaObject>,
+    /// The directory relative files are found in, that of the file the tree was imported from.
+    directory: Option<PathBuf>,
+}
+
+impl Media {
+    pub fn get(&self, id: u128) -> Option<&MediaObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u128) -> Option<&mut MediaObject> {
+        self.objects.iter_mut().find(|object| object.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MediaObject> {
+        self.objects.iter()
+    }
+
+    pub fn insert(&mut self, object: MediaObject) -> u128 {
+        let id = object.id;
+        self.objects.push(object);
+        id
+    }
+
+    /// The object imported with this xref.
+    pub fn find_xref(&self, xref: &str) -> Option<u128> {
+        self.objects
+            .iter()
+            .find(|object| object.xref.as_deref() == Some(xref))
+            .map(|object| object.id)
+    }
+
+    pub fn xrefs(&self) -> impl Iterator<Item = &String> {
+        self.objects.iter().filter_map(|object| object.xref.as_ref())
+    }
+
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    pub fn set_directory(&mut self, directory: PathBuf) {
+        self.directory = Some(directory);
+    }
+
+    /// Where the file of an object is on disk, `None` for files given as a URL.
+    pub fn path(&self, id: u128) -> Option<PathBuf> {
+        self.resolve(&self.get(id)?.file)
+    }
+
+    /// Whether both name the same file, once relative paths are resolved.
+    pub fn same_file(&self, file: &str, other: &str) -> bool {
+        file == other || self.resolve(file).is_some_and(|path| Some(path) == self.resolve(other))
+    }
+
+    fn resolve(&self, file: &str) -> Option<PathBuf> {
+        let file = match file.strip_prefix("file://") {
+            Some(path) => path,
+            None if file.contains("://") => return None,
+            None => file,
+        };
+        let path = PathBuf::from(file);
+        match &self.directory {
+            Some(directory) if path.is_relative() => Some(directory.join(path)),
+            _ => Some(path),
+        }
+    }
+
+    /// How a picked file is written in `FILE`, relative when it lies in the directory of the tree.
+    pub fn file_name(&self, path: &Path) -> String {
+        let relative = self
+            .directory
+            .as_deref()
+            .and_then(|directory| path.strip_prefix(directory).ok());
+        match relative {
+            Some(relative) => relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => path.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Splits off the objects to merge them into a tree whose relative files are found in `directory`. When that
+    /// is another directory, relative files are resolved so that they are still found.
+    pub fn into_objects(self, directory: Option<&Path>) -> Vec<MediaObject> {
+        if self.directory.as_deref() == directory {
+            return self.objects;
+        }
+        let paths: Vec<Option<PathBuf>> = self.objects.iter().map(|object| self.path(object.id)).collect();
+        self.objects
+            .into_iter()
+            .zip(paths)
+            .map(|(mut object, path)| {
+                if let Some(path) = path {
+                    object.file = path.to_string_lossy().to_string();
+                }
+                object
+            })
+            .collect()
+    }
+}
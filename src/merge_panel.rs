@@ -0,0 +1,64 @@
+use graph::Graph;
+use iced::{
+    widget::{button, checkbox, column, container, row, scrollable, text, Column, Container},
+    Background, Border, Color,
+    Length::Fill,
+};
+
+use crate::{
+    gedcom_io::merge::{describe, PendingMerge},
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode,
+    Message,
+};
+
+pub fn merge_panel<'a>(
+    pending: &'a PendingMerge,
+    graph: &'a Graph<GenealogicalNode, GenealogicalEdge>,
+) -> Container<'a, Message> {
+    let header = row![
+        text(format!(
+            "{} possible matches found, {} individuals will be added",
+            pending.candidates.len(),
+            pending.new_individual_count()
+        ))
+        .color(Color::BLACK)
+        .width(Fill),
+        button("Merge").on_press(Message::ConfirmMerge),
+        button("Cancel").on_press(Message::CancelMerge),
+    ]
+    .spacing(10);
+
+    let entries: Column<'a, Message> = pending
+        .candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let incoming = pending.incoming_node(candidate.incoming)?;
+            let existing = graph.get_node(Some(candidate.existing))?;
+            Some(
+                row![
+                    checkbox("Same person", candidate.accepted)
+                        .on_toggle(move |_| Message::ToggleMergeCandidate(index)),
+                    text(describe(incoming)).color(Color::BLACK).width(Fill),
+                    text(describe(existing)).color(Color::BLACK).width(Fill),
+                ]
+                .spacing(10)
+                .into(),
+            )
+        })
+        .collect::<Column<'a, Message>>()
+        .spacing(5);
+
+    container(column![header, scrollable(entries).width(Fill).height(160)].spacing(10))
+        .width(Fill)
+        .padding(10)
+        .style(|_| container::Style {
+            background: Some(Background::Color(Color::WHITE)),
+            border: Border {
+                radius: 5.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+}
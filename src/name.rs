@@ -0,0 +1,226 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gedcom_io::{parser::Structure, GedcomVersion};
+
+/// What kind of name a person went by, the `TYPE` of a GEDCOM `NAME`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameType {
+    Birth,
+    Maiden,
+    Married,
+    /// Also known as, an alias.
+    Aka,
+    Immigrant,
+    Professional,
+    Religious,
+    /// Any other kind of name, named by the user or by the `TYPE` of a GEDCOM `NAME`.
+    Other(String),
+}
+
+impl NameType {
+    /// The types offered in the side panel, `Other` stands for every custom type.
+    pub const ALL: [NameType; 8] = [
+        NameType::Birth,
+        NameType::Maiden,
+        NameType::Married,
+        NameType::Aka,
+        NameType::Immigrant,
+        NameType::Professional,
+        NameType::Religious,
+        NameType::Other(String::new()),
+    ];
+
+    /// The values 5.5.x writes in lower case and 7.0 in upper case, the rest are user defined.
+    const GEDCOM_VALUES: [(&'static str, NameType); 6] = [
+        ("birth", NameType::Birth),
+        ("maiden", NameType::Maiden),
+        ("married", NameType::Married),
+        ("aka", NameType::Aka),
+        ("immigrant", NameType::Immigrant),
+        ("professional", NameType::Professional),
+    ];
+
+    /// Reads the `TYPE` of a name, where 7.0 names a type it does not know as `OTHER` with a `PHRASE`.
+    pub fn from_structure(structure: &Structure) -> Self {
+        let value = structure.value.as_deref().unwrap_or_default().trim();
+        let name = match structure.child_value("PHRASE") {
+            Some(phrase) if value.eq_ignore_ascii_case("OTHER") => phrase,
+            None if value.eq_ignore_ascii_case("OTHER") => "",
+            _ => value,
+        };
+        Self::GEDCOM_VALUES
+            .iter()
+            .find(|(value, _)| value.eq_ignore_ascii_case(name))
+            .map(|(_, name_type)| name_type.clone())
+            .or_else(|| match name {
+                _ if name.eq_ignore_ascii_case("religious") => Some(NameType::Religious),
+                // Gramps names custom types by what the side panel shows, e.g. `Religious name`.
+                _ => Self::ALL
+                    .into_iter()
+                    .find(|name_type| name_type.to_string().eq_ignore_ascii_case(name)),
+            })
+            .unwrap_or_else(|| NameType::Other(name.to_string()))
+    }
+
+    /// The `TYPE` value and, for types 7.0 does not know, the `PHRASE` naming it.
+    pub fn gedcom_value(&self, version: GedcomVersion) -> (String, Option<String>) {
+        let known = Self::GEDCOM_VALUES
+            .iter()
+            .find(|(_, name_type)| name_type == self)
+            .map(|(value, _)| *value);
+        // 5.5.x has no professional names, it is written like any user defined type.
+        let user_defined = match self {
+            NameType::Religious => "religious",
+            NameType::Other(name) => name,
+            _ => known.unwrap_or_default(),
+        };
+        match (known, version) {
+            (Some(value), GedcomVersion::V70) => (value.to_uppercase(), None),
+            (None, GedcomVersion::V70) if user_defined.is_empty() => ("OTHER".to_string(), None),
+            (None, GedcomVersion::V70) => ("OTHER".to_string(), Some(user_defined.to_string())),
+            (_, _) => (user_defined.to_string(), None),
+        }
+    }
+}
+
+impl fmt::Display for NameType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameType::Birth => write!(f, "Birth name"),
+            NameType::Maiden => write!(f, "Maiden name"),
+            NameType::Married => write!(f, "Married name"),
+            NameType::Aka => write!(f, "Also known as"),
+            NameType::Immigrant => write!(f, "Immigrant name"),
+            NameType::Professional => write!(f, "Professional name"),
+            NameType::Religious => write!(f, "Religious name"),
+            NameType::Other(name) if !name.is_empty() => write!(f, "{}", name),
+            NameType::Other(_) => write!(f, "Other"),
+        }
+    }
+}
+
+/// One of the names of a person, split into the parts GEDCOM knows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersonalName {
+    pub name_type: Option<NameType>,
+    /// A title before the name, e.g. `Dr.`.
+    pub prefix: Option<String>,
+    pub given: Option<String>,
+    pub nickname: Option<String>,
+    /// The part of the surname that is not sorted by, e.g. `van` in `van Gogh`.
+    pub surname_prefix: Option<String>,
+    pub surname: Option<String>,
+    /// What follows the name, e.g. `Jr.`.
+    pub suffix: Option<String>,
+    /// The name shown on the card, exactly one name of a person is preferred.
+    pub preferred: bool,
+    /// The structure this name was imported from, so that its romanized forms, sources and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl PersonalName {
+    /// Prefers the part substructures and falls back to the `Given /Surname/` form of the `NAME` value.
+    pub fn from_structure(name: &Structure) -> Self {
+        let value = name.value.as_deref().unwrap_or_default();
+        let mut parts = value.splitn(3, '/');
+        let given_part = parts.next().map(str::trim).filter(|part| !part.is_empty());
+        let surname_part = parts.next().map(str::trim).filter(|part| !part.is_empty());
+        let part = |tag: &str| name.child_value(tag).map(String::from);
+
+        Self {
+            name_type: name.child("TYPE").map(NameType::from_structure),
+            prefix: part("NPFX"),
+            given: part("GIVN").or(given_part.map(String::from)),
+            nickname: part("NICK"),
+            surname_prefix: part("SPFX"),
+            surname: part("SURN").or(surname_part.map(String::from)),
+            suffix: part("NSFX"),
+            preferred: false,
+            gedcom_record: Some(name.clone()),
+        }
+    }
+
+    /// Whether both say the same in every part, what they were imported from aside.
+    pub fn same_parts(&self, other: &PersonalName) -> bool {
+        self.name_type == other.name_type && self.parts() == other.parts()
+    }
+
+    /// The parts with the tags of their substructures, in the order GEDCOM lists them.
+    pub fn parts(&self) -> [(&'static str, &Option<String>); 6] {
+        [
+            ("NPFX", &self.prefix),
+            ("GIVN", &self.given),
+            ("NICK", &self.nickname),
+            ("SPFX", &self.surname_prefix),
+            ("SURN", &self.surname),
+            ("NSFX", &self.suffix),
+        ]
+    }
+
+    /// The surname together with its prefix, `van Gogh`.
+    pub fn full_surname(&self) -> Option<String> {
+        join([&self.surname_prefix, &self.surname])
+    }
+
+    /// The `NAME` value, `Dr. Vincent /van Gogh/ Jr.`.
+    pub fn gedcom_value(&self) -> String {
+        let surname = format!("/{}/", self.full_surname().unwrap_or_default());
+        [
+            self.prefix.as_deref(),
+            self.given.as_deref(),
+            Some(&surname),
+            self.suffix.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+    }
+
+    pub fn apply(&mut self, edit: NameEdit) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            NameEdit::Type(name_type) => self.name_type = name_type,
+            NameEdit::Prefix(prefix) => self.prefix = non_empty(prefix),
+            NameEdit::Given(given) => self.given = non_empty(given),
+            NameEdit::Nickname(nickname) => self.nickname = non_empty(nickname),
+            NameEdit::SurnamePrefix(surname_prefix) => self.surname_prefix = non_empty(surname_prefix),
+            NameEdit::Surname(surname) => self.surname = non_empty(surname),
+            NameEdit::Suffix(suffix) => self.suffix = non_empty(suffix),
+        }
+    }
+}
+
+/// The whole name as it is read, `Dr. Vincent "Vince" van Gogh Jr.`.
+impl fmt::Display for PersonalName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nickname = self.nickname.as_ref().map(|nickname| format!("\"{}\"", nickname));
+        let name = join([&self.prefix, &self.given, &nickname, &self.full_surname(), &self.suffix]);
+        write!(f, "{}", name.as_deref().unwrap_or("Unnamed"))
+    }
+}
+
+/// A change made to one field of a name in the side panel.
+#[derive(Debug, Clone)]
+pub enum NameEdit {
+    Type(Option<NameType>),
+    Prefix(String),
+    Given(String),
+    Nickname(String),
+    SurnamePrefix(String),
+    Surname(String),
+    Suffix(String),
+}
+
+fn join<const N: usize>(parts: [&Option<String>; N]) -> Option<String> {
+    let parts: Vec<&str> = parts
+        .into_iter()
+        .flatten()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
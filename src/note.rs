@@ -0,0 +1,281 @@
+use graph::{node::GraphNodeTrait, Graph};
+use iced::widget::text_editor;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    family::same_members,
+    gedcom_io::{merge::describe, parser::Structure},
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode,
+    source::Sources,
+};
+
+/// How many characters of a note are shown where there is no room for all of it.
+const EXCERPT_LENGTH: usize = 40;
+
+/// A note several records can point at, a `NOTE` record in 5.5.x and an `SNOTE` record in 7.0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedNote {
+    id: u128,
+    pub xref: Option<String>,
+    pub text: String,
+    /// The record this note was imported from, so that its sources, translations and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl SharedNote {
+    pub fn new(text: String) -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            xref: None,
+            text,
+            gedcom_record: None,
+        }
+    }
+
+    pub fn from_record(record: &Structure) -> Self {
+        Self {
+            xref: record.xref.clone(),
+            gedcom_record: Some(record.clone()),
+            ..Self::new(record.value.clone().unwrap_or_default())
+        }
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+}
+
+/// What a note attached to a record says.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NoteText {
+    /// Written where the note is attached.
+    Inline(String),
+    /// One of the shared notes of the tree, by its id.
+    Shared(u128),
+}
+
+/// A note attached to a person, an event, a relationship or a source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub text: NoteText,
+    /// The structure this note was imported from, so that its language, translations and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Note {
+    pub fn new(text: NoteText) -> Self {
+        Self {
+            text,
+            gedcom_record: None,
+        }
+    }
+}
+
+/// What a note is attached to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NoteTarget {
+    Person(u128),
+    /// An event of a person, by its index.
+    Event((u128, usize)),
+    /// The relationship between the partners of a family.
+    Relationship(Vec<u128>),
+    Source(u128),
+}
+
+/// The notes of a relationship, kept by the partners the same way its citations are.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipNotes {
+    pub partners: Vec<u128>,
+    pub notes: Vec<Note>,
+}
+
+/// Every shared note of the tree together with the notes of its relationships.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Notes {
+    shared: Vec<SharedNote>,
+    relationships: Vec<RelationshipNotes>,
+}
+
+impl Notes {
+    pub fn get(&self, id: u128) -> Option<&SharedNote> {
+        self.shared.iter().find(|note| note.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u128) -> Option<&mut SharedNote> {
+        self.shared.iter_mut().find(|note| note.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SharedNote> {
+        self.shared.iter()
+    }
+
+    pub fn insert(&mut self, note: SharedNote) -> u128 {
+        let id = note.id;
+        self.shared.push(note);
+        id
+    }
+
+    /// The shared note imported with this xref.
+    pub fn find_xref(&self, xref: &str) -> Option<u128> {
+        self.shared
+            .iter()
+            .find(|note| note.xref.as_deref() == Some(xref))
+            .map(|note| note.id)
+    }
+
+    pub fn xrefs(&self) -> impl Iterator<Item = &String> {
+        self.shared.iter().filter_map(|note| note.xref.as_ref())
+    }
+
+    /// What a note says, looked up among the shared notes when it is one of them.
+    pub fn text<'a>(&'a self, note: &'a Note) -> &'a str {
+        match &note.text {
+            NoteText::Inline(text) => text,
+            NoteText::Shared(id) => self.get(*id).map(|note| note.text.as_str()).unwrap_or_default(),
+        }
+    }
+
+    /// Drops the notes of the relationships a removed person was a partner in.
+    pub fn remove_relationships_of(&mut self, person: u128) {
+        self.relationships
+            .retain(|relationship| !relationship.partners.contains(&person));
+    }
+
+    pub fn relationship(&self, partners: &[u128]) -> &[Note] {
+        self.relationships
+            .iter()
+            .find(|relationship| same_members(&relationship.partners, partners))
+            .map(|relationship| relationship.notes.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn relationship_mut(&mut self, partners: &[u128]) -> &mut Vec<Note> {
+        let index = match self
+            .relationships
+            .iter()
+            .position(|relationship| same_members(&relationship.partners, partners))
+        {
+            Some(index) => index,
+            None => {
+                self.relationships.push(RelationshipNotes {
+                    partners: partners.to_vec(),
+                    notes: vec![],
+                });
+                self.relationships.len() - 1
+            }
+        };
+        &mut self.relationships[index].notes
+    }
+
+    /// Splits off the shared notes and relationship notes, e.g. to merge them into another tree.
+    pub fn into_parts(self) -> (Vec<SharedNote>, Vec<RelationshipNotes>) {
+        (self.shared, self.relationships)
+    }
+}
+
+/// The note open in the multi-line editor of the side panel, every edit is written back to it right away.
+pub struct NoteEditor {
+    pub target: NoteTarget,
+    pub index: usize,
+    pub content: text_editor::Content,
+}
+
+impl NoteEditor {
+    pub fn new(target: NoteTarget, index: usize, text: &str) -> Self {
+        Self {
+            target,
+            index,
+            content: text_editor::Content::with_text(text),
+        }
+    }
+
+    pub fn is_editing(&self, target: &NoteTarget, index: usize) -> bool {
+        &self.target == target && self.index == index
+    }
+
+    /// The text of the editor, which always ends its last line with a newline the note itself does not have.
+    pub fn text(&self) -> String {
+        let text = self.content.text();
+        text.strip_suffix('\n').unwrap_or(&text).to_string()
+    }
+}
+
+/// A note whose text contains the searched words, and what it is attached to.
+#[derive(Debug, Clone)]
+pub struct NoteMatch {
+    pub target: NoteTarget,
+    pub index: usize,
+    /// The person, event, relationship or source the note belongs to, as shown in the results.
+    pub owner: String,
+    pub excerpt: String,
+}
+
+/// Every note of the tree containing the query, ignoring case.
+pub fn search(
+    query: &str,
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    notes: &Notes,
+    sources: &Sources,
+) -> Vec<NoteMatch> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+    let mut matches = vec![];
+    let mut find = |target: NoteTarget, owner: String, attached: &[Note]| {
+        for (index, note) in attached.iter().enumerate() {
+            let text = notes.text(note);
+            if text.to_lowercase().contains(&query) {
+                matches.push(NoteMatch {
+                    target: target.clone(),
+                    index,
+                    owner: owner.clone(),
+                    excerpt: excerpt(text),
+                });
+            }
+        }
+    };
+    for node in graph.nodes() {
+        find(NoteTarget::Person(node.id()), describe(node), node.notes());
+        for (index, event) in node.events().iter().enumerate() {
+            find(
+                NoteTarget::Event((node.id(), index)),
+                format!("{} of {}", event.event_type, describe(node)),
+                &event.notes,
+            );
+        }
+    }
+    for relationship in notes.relationships.iter() {
+        let names: Vec<String> = relationship
+            .partners
+            .iter()
+            .filter_map(|id| graph.get_node(Some(*id)))
+            .map(describe)
+            .collect();
+        find(
+            NoteTarget::Relationship(relationship.partners.clone()),
+            names.join(" and "),
+            &relationship.notes,
+        );
+    }
+    for source in sources.iter() {
+        find(NoteTarget::Source(source.id()), source.name(), &source.notes);
+    }
+    matches
+}
+
+/// The first line of a note, shortened to fit a button or picker.
+pub fn excerpt(text: &str) -> String {
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+        .trim();
+    match line.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None if line.is_empty() => "Empty note".to_string(),
+        None => line.to_string(),
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use graph::{node::GraphNodeTrait, Graph};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{family::Families, genealogical_edge::GenealogicalEdge, genealogical_node::GenealogicalNode};
+
+/// Spellings of countries that mean the same one, compared after `normalize`.
+const ALIASES: [&[&str]; 2] = [
+    &["usa", "us", "united states", "united states of america"],
+    &["uk", "united kingdom", "great britain"],
+];
+
+/// A place events happened at, shared by every event that names it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Place {
+    id: u128,
+    /// The jurisdictions from the smallest to the largest separated by commas, e.g. town, county, state, country.
+    name: String,
+    /// Names the jurisdiction levels, as the `FORM` of a GEDCOM place does: `City, County, State, Country`.
+    form: Option<String>,
+    /// Latitude and longitude in decimal degrees, north and east positive.
+    coordinates: Option<(f64, f64)>,
+}
+
+impl Place {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            name: name.to_string(),
+            form: None,
+            coordinates: None,
+        }
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    pub fn jurisdictions(&self) -> Vec<&str> {
+        jurisdictions(&self.name)
+    }
+    /// The place one level up the hierarchy, e.g. the county of a town.
+    pub fn parent_name(&self) -> Option<String> {
+        let jurisdictions = self.jurisdictions();
+        (jurisdictions.len() > 1).then(|| jurisdictions[1..].join(", "))
+    }
+    pub fn form(&self) -> Option<String> {
+        self.form.clone()
+    }
+    pub fn set_form(&mut self, form: String) {
+        self.form = Some(form);
+    }
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.coordinates
+    }
+    pub fn set_coordinates(&mut self, coordinates: (f64, f64)) {
+        self.coordinates = Some(coordinates);
+    }
+
+    /// Takes over what the other record of the same place knows and this one does not.
+    fn absorb(&mut self, other: &Place) {
+        if self.form.is_none() {
+            self.form = other.form.clone();
+        }
+        if self.coordinates.is_none() {
+            self.coordinates = other.coordinates;
+        }
+    }
+}
+
+/// Every place of the tree, one record per distinct name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Places {
+    places: Vec<Place>,
+}
+
+impl Places {
+    pub fn get(&self, id: u128) -> Option<&Place> {
+        self.places.iter().find(|place| place.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u128) -> Option<&mut Place> {
+        self.places.iter_mut().find(|place| place.id == id)
+    }
+
+    /// The place with this name, ignoring case and the spacing around the commas.
+    pub fn find(&self, name: &str) -> Option<u128> {
+        let name = key(name);
+        self.places
+            .iter()
+            .find(|place| key(&place.name) == name)
+            .map(|place| place.id)
+    }
+
+    /// The id of the place with this name, adding a record for it when there is none yet.
+    pub fn intern(&mut self, name: &str) -> u128 {
+        self.find(name).unwrap_or_else(|| self.insert(Place::new(name)))
+    }
+
+    /// Like `intern`, but only shares a place spelled exactly the same, so that a name being typed is kept as it is.
+    pub fn intern_exact(&mut self, name: &str) -> u128 {
+        let existing = self
+            .places
+            .iter()
+            .find(|place| place.name == name)
+            .map(|place| place.id);
+        existing.unwrap_or_else(|| self.insert(Place::new(name)))
+    }
+
+    pub fn insert(&mut self, place: Place) -> u128 {
+        let id = place.id;
+        self.places.push(place);
+        id
+    }
+
+    /// Adds the places of another tree, returning which place each of its ids now refers to.
+    pub fn extend(&mut self, other: Places) -> HashMap<u128, u128> {
+        let mut ids = HashMap::new();
+        for place in other.places {
+            let id = self.intern(&place.name());
+            if let Some(existing) = self.get_mut(id) {
+                existing.absorb(&place);
+            }
+            ids.insert(place.id, id);
+        }
+        ids
+    }
+
+    /// Drops places no event refers to anymore, unless they carry coordinates worth keeping.
+    pub fn remove_unused(&mut self, graph: &Graph<GenealogicalNode, GenealogicalEdge>, families: &Families) {
+        let usage = place_usage(graph, families);
+        self.places
+            .retain(|place| usage.contains_key(&place.id) || place.coordinates.is_some());
+    }
+
+    /// Folds the variants into the preferred place, which keeps its own spelling.
+    pub fn merge(&mut self, preferred: u128, variants: &[u128]) {
+        let removed: Vec<Place> = self
+            .places
+            .iter()
+            .filter(|place| place.id != preferred && variants.contains(&place.id))
+            .cloned()
+            .collect();
+        self.places
+            .retain(|place| place.id == preferred || !variants.contains(&place.id));
+        if let Some(place) = self.get_mut(preferred) {
+            for variant in removed.iter() {
+                place.absorb(variant);
+            }
+        }
+    }
+
+    /// Groups of places whose names differ only in spelling, e.g. abbreviations, punctuation or a typo.
+    pub fn variants(&self) -> Vec<Vec<u128>> {
+        // Union-find over every pair of places.
+        let mut groups: Vec<usize> = (0..self.places.len()).collect();
+        fn root(groups: &mut [usize], index: usize) -> usize {
+            let mut index = index;
+            while groups[index] != index {
+                groups[index] = groups[groups[index]];
+                index = groups[index];
+            }
+            index
+        }
+        for (index, place) in self.places.iter().enumerate() {
+            for (other_index, other) in self.places.iter().enumerate().skip(index + 1) {
+                if same_place(&place.jurisdictions(), &other.jurisdictions(), true) {
+                    let (a, b) = (root(&mut groups, index), root(&mut groups, other_index));
+                    groups[a] = b;
+                }
+            }
+        }
+
+        let mut variants: Vec<Vec<u128>> = vec![];
+        let mut roots: HashMap<usize, usize> = HashMap::new();
+        for (index, place) in self.places.iter().enumerate() {
+            let group = root(&mut groups, index);
+            match roots.get(&group) {
+                Some(position) => variants[*position].push(place.id),
+                None => {
+                    roots.insert(group, variants.len());
+                    variants.push(vec![place.id]);
+                }
+            }
+        }
+        variants.retain(|group| group.len() > 1);
+        variants
+    }
+}
+
+/// Spelling variants of one place, waiting for the user to pick the spelling to keep.
+pub struct VariantGroup {
+    pub places: Vec<u128>,
+    pub preferred: u128,
+    /// Groups that differ by more than punctuation, case or abbreviations may well be different places, and
+    /// are left for the user to accept.
+    pub accepted: bool,
+}
+
+/// The variants found across the whole tree by the place standardization tool.
+pub struct PlaceStandardization {
+    pub groups: Vec<VariantGroup>,
+}
+
+impl PlaceStandardization {
+    /// Prefers the spelling most events use, then one that has coordinates.
+    pub fn new(places: &Places, graph: &Graph<GenealogicalNode, GenealogicalEdge>, families: &Families) -> Self {
+        let usage = place_usage(graph, families);
+        let groups = places
+            .variants()
+            .into_iter()
+            .map(|group| {
+                let rank = |id: &u128| {
+                    let has_coordinates = places.get(*id).and_then(Place::coordinates).is_some();
+                    (usage.get(id).copied().unwrap_or_default(), has_coordinates)
+                };
+                let preferred = group
+                    .iter()
+                    .copied()
+                    .reduce(|best, id| if rank(&id) > rank(&best) { id } else { best })
+                    .unwrap_or_default();
+                let name = |id: u128| places.get(id).map(Place::name).unwrap_or_default();
+                let preferred_name = name(preferred);
+                let accepted = group.iter().all(|id| {
+                    let name = name(*id);
+                    same_place(&jurisdictions(&name), &jurisdictions(&preferred_name), false)
+                });
+                VariantGroup {
+                    places: group,
+                    preferred,
+                    accepted,
+                }
+            })
+            .collect();
+        Self { groups }
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(group) = self.groups.get_mut(index) {
+            group.accepted = !group.accepted;
+        }
+    }
+
+    pub fn set_preferred(&mut self, index: usize, preferred: u128) {
+        if let Some(group) = self
+            .groups
+            .get_mut(index)
+            .filter(|group| group.places.contains(&preferred))
+        {
+            group.preferred = preferred;
+        }
+    }
+
+    /// Points every event at the preferred spelling of its accepted group and drops the other spellings.
+    pub fn apply(
+        self,
+        graph: &mut Graph<GenealogicalNode, GenealogicalEdge>,
+        families: &mut Families,
+        places: &mut Places,
+    ) {
+        let groups: Vec<VariantGroup> = self.groups.into_iter().filter(|group| group.accepted).collect();
+        let node_ids: Vec<u128> = graph.nodes().iter().map(|node| node.id()).collect();
+        for node_id in node_ids {
+            let node = graph.get_node_mut_unsafe(Some(node_id));
+            for index in 0..node.events().len() {
+                let Some(event) = node.event_mut(index) else {
+                    continue;
+                };
+                if let Some(group) = groups
+                    .iter()
+                    .find(|group| event.place.is_some_and(|place| group.places.contains(&place)))
+                {
+                    event.place = Some(group.preferred);
+                }
+            }
+        }
+        for event in families.iter_mut().flat_map(|family| family.events.iter_mut()) {
+            if let Some(group) = groups
+                .iter()
+                .find(|group| event.place.is_some_and(|place| group.places.contains(&place)))
+            {
+                event.place = Some(group.preferred);
+            }
+        }
+        for group in groups.iter() {
+            places.merge(group.preferred, &group.places);
+        }
+        graph.redraw();
+    }
+}
+
+/// How many events of persons and of relationships refer to each place.
+pub fn place_usage(graph: &Graph<GenealogicalNode, GenealogicalEdge>, families: &Families) -> HashMap<u128, usize> {
+    let mut usage = HashMap::new();
+    let family_places = families
+        .iter()
+        .flat_map(|family| family.events.iter())
+        .filter_map(|event| event.place);
+    for place in graph
+        .nodes()
+        .iter()
+        .flat_map(|node| node.events())
+        .filter_map(|event| event.place)
+        .chain(family_places)
+    {
+        *usage.entry(place).or_insert(0) += 1;
+    }
+    usage
+}
+
+fn jurisdictions(name: &str) -> Vec<&str> {
+    name.split(',').map(str::trim).collect()
+}
+
+fn key(name: &str) -> String {
+    jurisdictions(name).join(",").to_lowercase()
+}
+
+fn same_place(a: &[&str], b: &[&str], typos: bool) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_jurisdiction(a, b, typos))
+}
+
+fn same_jurisdiction(a: &str, b: &str, typos: bool) -> bool {
+    let (a, b) = (normalize(a), normalize(b));
+    if a == b {
+        return true;
+    }
+    if ALIASES
+        .iter()
+        .any(|aliases| aliases.contains(&a.as_str()) && aliases.contains(&b.as_str()))
+    {
+        return true;
+    }
+    // Longer names are allowed a typo or two, short ones are too easily another place.
+    let allowed = match a.chars().count().min(b.chars().count()) {
+        0..=4 => 0,
+        5..=9 => 1,
+        _ => 2,
+    };
+    typos && allowed > 0 && edit_distance(&a, &b) <= allowed
+}
+
+/// Lower case, without punctuation, with a single space between words and `Saint` spelled out.
+fn normalize(jurisdiction: &str) -> String {
+    jurisdiction
+        .to_lowercase()
+        .replace(['.', '\'', '-'], " ")
+        .split_whitespace()
+        .map(|word| match word {
+            "st" | "ste" => "saint",
+            "co" => "county",
+            "twp" => "township",
+            word => word,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance, counting characters rather than bytes.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
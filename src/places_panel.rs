@@ -0,0 +1,103 @@
+use std::fmt;
+
+use graph::Graph;
+use iced::{
+    widget::{button, checkbox, column, container, pick_list, row, scrollable, text, Column, Container},
+    Background, Border, Color,
+    Length::Fill,
+};
+
+use crate::{
+    family::Families,
+    genealogical_edge::GenealogicalEdge,
+    genealogical_node::GenealogicalNode,
+    place::{place_usage, PlaceStandardization, Places},
+    Message,
+};
+
+/// One spelling of a place offered as the one to keep.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spelling {
+    place: u128,
+    name: String,
+    events: usize,
+}
+
+impl fmt::Display for Spelling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({} events)", self.name, self.events)
+    }
+}
+
+pub fn places_panel<'a>(
+    standardization: &'a PlaceStandardization,
+    places: &'a Places,
+    graph: &'a Graph<GenealogicalNode, GenealogicalEdge>,
+    families: &'a Families,
+) -> Container<'a, Message> {
+    let accepted = standardization.groups.iter().filter(|group| group.accepted).count();
+    let header = row![
+        text(format!(
+            "{} places are spelled in more than one way, {} will be standardized",
+            standardization.groups.len(),
+            accepted
+        ))
+        .color(Color::BLACK)
+        .width(Fill),
+        button("Standardize").on_press(Message::ConfirmPlaceStandardization),
+        button("Cancel").on_press(Message::CancelPlaceStandardization),
+    ]
+    .spacing(10);
+
+    let usage = place_usage(graph, families);
+    let entries: Column<'a, Message> = standardization
+        .groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| {
+            let spellings: Vec<Spelling> = group
+                .places
+                .iter()
+                .filter_map(|id| places.get(*id))
+                .map(|place| Spelling {
+                    place: place.id(),
+                    name: place.name(),
+                    events: usage.get(&place.id()).copied().unwrap_or_default(),
+                })
+                .collect();
+            let preferred = spellings
+                .iter()
+                .find(|spelling| spelling.place == group.preferred)
+                .cloned();
+            let variants = spellings
+                .iter()
+                .filter(|spelling| spelling.place != group.preferred)
+                .map(|spelling| spelling.to_string())
+                .collect::<Vec<_>>()
+                .join("; ");
+            row![
+                checkbox("Standardize", group.accepted).on_toggle(move |_| Message::TogglePlaceVariants(index)),
+                pick_list(spellings, preferred, move |spelling: Spelling| {
+                    Message::SetPreferredPlace((index, spelling.place))
+                })
+                .width(Fill),
+                text(format!("replaces {}", variants)).color(Color::BLACK).width(Fill),
+            ]
+            .spacing(10)
+            .into()
+        })
+        .collect::<Column<'a, Message>>()
+        .spacing(5);
+
+    container(column![header, scrollable(entries).width(Fill).height(160)].spacing(10))
+        .width(Fill)
+        .padding(10)
+        .style(|_| container::Style {
+            background: Some(Background::Color(Color::WHITE)),
+            border: Border {
+                radius: 5.0.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+}
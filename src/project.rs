@@ -0,0 +1,204 @@
+use std::{fs, path::Path};
+
+use graph::{edge::GraphEdgeTrait, node::GraphNodeTrait, Graph};
+use iced::{Point, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    association::{draw_associations, Association},
+    family::Families,
+    gedcom_io::{parser::Structure, preserved::PreservedRecords},
+    genealogical_edge::{family_edges, GenealogicalEdge},
+    genealogical_node::{GenealogicalNode, Sex},
+    life_event::LifeEvent,
+    media::{Media, MediaLink},
+    name::PersonalName,
+    note::{Note, Notes},
+    place::Places,
+    source::{Citation, Sources},
+};
+
+pub const PROJECT_EXTENSION: &str = "igproj";
+/// Bumped whenever the layout of the file changes in a way older versions cannot read.
+const PROJECT_VERSION: u32 = 1;
+
+/// The native project file: the genealogical data together with how the chart was arranged and viewed.
+#[derive(Serialize, Deserialize)]
+pub struct Project {
+    version: u32,
+    nodes: Vec<ProjectNode>,
+    edges: Vec<(u128, u128)>,
+    preserved: PreservedRecords,
+    families: Families,
+    places: Places,
+    sources: Sources,
+    media: Media,
+    notes: Notes,
+    translation: (f32, f32),
+    scaling: f32,
+    selected_node: Option<u128>,
+}
+
+#[derive(Deserialize)]
+struct ProjectVersion {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProjectNode {
+    id: u128,
+    anchor: (f32, f32),
+    xref: Option<String>,
+    gedcom_record: Option<Structure>,
+    sex: Option<Sex>,
+    names: Vec<PersonalName>,
+    events: Vec<LifeEvent>,
+    citations: Vec<Citation>,
+    media: Vec<MediaLink>,
+    portrait: Option<u128>,
+    notes: Vec<Note>,
+    associations: Vec<Association>,
+}
+
+impl Project {
+    pub fn new(
+        graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+        preserved: &PreservedRecords,
+        families: &Families,
+        places: &Places,
+        sources: &Sources,
+        media: &Media,
+        notes: &Notes,
+    ) -> Self {
+        let nodes = graph
+            .nodes()
+            .iter()
+            .map(|node| ProjectNode {
+                id: node.id(),
+                anchor: (node.anchor().x, node.anchor().y),
+                xref: node.xref(),
+                gedcom_record: node.gedcom_record().cloned(),
+                sex: node.sex(),
+                names: node.names().to_vec(),
+                events: node.events().to_vec(),
+                citations: node.citations().to_vec(),
+                media: node.media().to_vec(),
+                portrait: node.portrait(),
+                notes: node.notes().to_vec(),
+                associations: node.associations().to_vec(),
+            })
+            .collect();
+        Self {
+            version: PROJECT_VERSION,
+            nodes,
+            edges: family_edges(graph).map(|edge| (edge.start(), edge.end())).collect(),
+            preserved: preserved.clone(),
+            families: families.clone(),
+            places: places.clone(),
+            sources: sources.clone(),
+            media: media.clone(),
+            notes: notes.clone(),
+            translation: (graph.translation().x, graph.translation().y),
+            scaling: graph.scaling(),
+            selected_node: graph.selected_node().map(|node| node.id()),
+        }
+    }
+
+    /// Rebuilds the graph exactly as it was saved, including the viewport and selection.
+    pub fn into_document(
+        self,
+    ) -> (
+        Graph<GenealogicalNode, GenealogicalEdge>,
+        PreservedRecords,
+        Families,
+        Places,
+        Sources,
+        Media,
+        Notes,
+    ) {
+        let mut graph = Graph::default();
+        for saved in self.nodes {
+            let mut node = GenealogicalNode::new(Point::new(saved.anchor.0, saved.anchor.1));
+            node.set_id(saved.id);
+            if let Some(xref) = saved.xref {
+                node.set_xref(xref);
+            }
+            if let Some(record) = saved.gedcom_record {
+                node.set_gedcom_record(record);
+            }
+            if let Some(sex) = saved.sex {
+                node.set_sex(sex);
+            }
+            for name in saved.names {
+                let preferred = name.preferred;
+                node.add_name(name);
+                if preferred {
+                    node.set_preferred_name(node.names().len() - 1);
+                }
+            }
+            for event in saved.events {
+                node.add_event(event);
+            }
+            *node.citations_mut() = saved.citations;
+            *node.media_mut() = saved.media;
+            node.set_portrait(saved.portrait);
+            *node.notes_mut() = saved.notes;
+            *node.associations_mut() = saved.associations;
+            graph.insert_node(node);
+        }
+        for (start, end) in self.edges {
+            graph.add_edge_between_nodes(Some(start), end);
+        }
+        graph.set_translation(Vector::new(self.translation.0, self.translation.1));
+        graph.set_scaling(self.scaling);
+        if let Some(node_id) = self.selected_node.filter(|id| graph.get_node(Some(*id)).is_some()) {
+            graph.set_selected_node(node_id);
+        }
+        self.families.style_edges(&mut graph);
+        draw_associations(&mut graph);
+        (
+            graph,
+            self.preserved,
+            self.families,
+            self.places,
+            self.sources,
+            self.media,
+            self.notes,
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let invalid = |error: serde_json::Error| format!("Not a valid project file: {}", error);
+        // Check the version on its own first, a newer layout may not deserialize into this one at all.
+        let version: ProjectVersion = serde_json::from_str(json).map_err(invalid)?;
+        if version.version > PROJECT_VERSION {
+            return Err(format!(
+                "The project was saved by a newer version of the app (format {}, this version reads up to {})",
+                version.version, PROJECT_VERSION
+            ));
+        }
+        serde_json::from_str(json).map_err(invalid)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+        Self::from_json(&json)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        Self::write_json(path, &self.to_json())
+    }
+
+    /// Writes a project already serialized by [`Project::to_json`]. Writes next to the target first so that a failed
+    /// save never leaves a half-written project behind.
+    pub fn write_json(path: &Path, json: &str) -> Result<(), String> {
+        let error = |error: std::io::Error| format!("Could not write {}: {}", path.display(), error);
+        let temporary = path.with_extension(format!("{}.tmp", PROJECT_EXTENSION));
+        fs::write(&temporary, json).map_err(error)?;
+        fs::rename(&temporary, path).map_err(error)
+    }
+}
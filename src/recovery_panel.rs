@@ -0,0 +1,43 @@
+use std::time::SystemTime;
+
+use iced::{
+    widget::{button, container, row, text, Container},
+    Background, Border, Color,
+    Length::Fill,
+};
+
+use crate::Message;
+
+pub fn recovery_panel<'a>(autosaved_at: SystemTime) -> Container<'a, Message> {
+    let minutes = autosaved_at
+        .elapsed()
+        .map(|elapsed| elapsed.as_secs() / 60)
+        .unwrap_or_default();
+    let age = match minutes {
+        0 => "less than a minute ago".to_string(),
+        1 => "a minute ago".to_string(),
+        minutes if minutes < 120 => format!("{} minutes ago", minutes),
+        minutes => format!("{} hours ago", minutes / 60),
+    };
+
+    let content = row![
+        text(format!(
+            "The previous session was not saved. Restore the copy autosaved {}?",
+            age
+        ))
+        .color(Color::BLACK)
+        .width(Fill),
+        button("Restore").on_press(Message::RestoreSession),
+        button("Discard").on_press(Message::DiscardSession),
+    ]
+    .spacing(10);
+
+    container(content).width(Fill).padding(10).style(|_| container::Style {
+        background: Some(Background::Color(Color::WHITE)),
+        border: Border {
+            radius: 5.0.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
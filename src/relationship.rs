@@ -0,0 +1,184 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gedcom_io::{date::GedcomDate, parser::Structure},
+    place::Places,
+};
+
+/// What kind of union the partners of a family live in. Nothing about it depends on the sex of the partners.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelationshipKind {
+    #[default]
+    Unknown,
+    Marriage,
+    CivilUnion,
+    /// Partners living together without a marriage or civil union.
+    Partnership,
+}
+
+impl RelationshipKind {
+    pub const ALL: [RelationshipKind; 4] = [
+        RelationshipKind::Unknown,
+        RelationshipKind::Marriage,
+        RelationshipKind::CivilUnion,
+        RelationshipKind::Partnership,
+    ];
+
+    /// GEDCOM has no kind of its own for a family, a civil union is the `TYPE` of its `MARR`.
+    pub const CIVIL_UNION_TYPE: &'static str = "Civil union";
+    /// A partnership is written as an `EVEN` of this `TYPE`, as marrying is the only union GEDCOM knows.
+    pub const PARTNERSHIP_TYPE: &'static str = "Partnership";
+
+    /// Reads the kind from the events of a `FAM` record.
+    pub fn from_record(record: &Structure) -> Self {
+        if record.children.iter().any(Self::is_partnership_event) {
+            return RelationshipKind::Partnership;
+        }
+        let mut marriages = record.children_with_tag("MARR").peekable();
+        if marriages.peek().is_none() {
+            return RelationshipKind::Unknown;
+        }
+        let civil = marriages.any(|marriage| {
+            marriage
+                .child_value("TYPE")
+                .is_some_and(|kind| kind.to_lowercase().contains("civil"))
+        });
+        match civil {
+            true => RelationshipKind::CivilUnion,
+            false => RelationshipKind::Marriage,
+        }
+    }
+
+    /// The `EVEN` a partnership is written as, which is the kind rather than an event of its own.
+    pub fn is_partnership_event(structure: &Structure) -> bool {
+        structure.tag == "EVEN"
+            && structure
+                .child_value("TYPE")
+                .is_some_and(|kind| kind.eq_ignore_ascii_case(Self::PARTNERSHIP_TYPE))
+    }
+}
+
+impl fmt::Display for RelationshipKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationshipKind::Unknown => write!(f, "Unknown"),
+            RelationshipKind::Marriage => write!(f, "Marriage"),
+            RelationshipKind::CivilUnion => write!(f, "Civil union"),
+            RelationshipKind::Partnership => write!(f, "Partnership"),
+        }
+    }
+}
+
+/// A change in the status of a relationship.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RelationshipEventType {
+    Engagement,
+    /// The wedding or registration that started the marriage or civil union.
+    #[default]
+    Union,
+    Divorce,
+    Annulment,
+}
+
+impl RelationshipEventType {
+    pub const ALL: [RelationshipEventType; 4] = [
+        RelationshipEventType::Engagement,
+        RelationshipEventType::Union,
+        RelationshipEventType::Divorce,
+        RelationshipEventType::Annulment,
+    ];
+
+    const GEDCOM_TAGS: [(&'static str, RelationshipEventType); 4] = [
+        ("ENGA", RelationshipEventType::Engagement),
+        ("MARR", RelationshipEventType::Union),
+        ("DIV", RelationshipEventType::Divorce),
+        ("ANUL", RelationshipEventType::Annulment),
+    ];
+
+    /// The type of a family event structure, `None` for tags that are not one of these events.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        Self::GEDCOM_TAGS
+            .iter()
+            .find(|(other, _)| *other == tag)
+            .map(|(_, event_type)| event_type.clone())
+    }
+
+    pub fn gedcom_tag(&self) -> &'static str {
+        Self::GEDCOM_TAGS
+            .iter()
+            .find(|(_, event_type)| event_type == self)
+            .map(|(tag, _)| *tag)
+            .unwrap_or_default()
+    }
+
+    pub fn is_gedcom_tag(tag: &str) -> bool {
+        Self::from_tag(tag).is_some()
+    }
+
+    /// How the event is abbreviated on the edge between the partners.
+    fn abbreviation(&self, kind: RelationshipKind) -> &'static str {
+        match self {
+            RelationshipEventType::Engagement => "eng.",
+            RelationshipEventType::Union if kind == RelationshipKind::Marriage => "m.",
+            RelationshipEventType::Union => "u.",
+            RelationshipEventType::Divorce => "div.",
+            RelationshipEventType::Annulment => "ann.",
+        }
+    }
+}
+
+impl fmt::Display for RelationshipEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationshipEventType::Engagement => write!(f, "Engagement"),
+            RelationshipEventType::Union => write!(f, "Marriage or union"),
+            RelationshipEventType::Divorce => write!(f, "Divorce"),
+            RelationshipEventType::Annulment => write!(f, "Annulment"),
+        }
+    }
+}
+
+/// When and where the status of a relationship changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelationshipEvent {
+    pub event_type: RelationshipEventType,
+    pub date: Option<String>,
+    /// The id of the place in the tree's `Places`.
+    pub place: Option<u128>,
+    /// The structure this event was imported from, so that its sources, notes and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl RelationshipEvent {
+    pub fn apply(&mut self, edit: RelationshipEdit, places: &mut Places) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            RelationshipEdit::Type(event_type) => self.event_type = event_type,
+            RelationshipEdit::Date(date) => self.date = non_empty(date),
+            RelationshipEdit::Place(place) => self.place = non_empty(place).map(|place| places.intern_exact(&place)),
+        }
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        self.date.as_deref()?.parse::<GedcomDate>().ok()?.year()
+    }
+
+    /// A short form like `m. 1850` for the edge between the partners.
+    pub fn label(&self, kind: RelationshipKind) -> String {
+        let abbreviation = self.event_type.abbreviation(kind);
+        match self.year() {
+            Some(year) => format!("{} {}", abbreviation, year),
+            None => abbreviation.to_string(),
+        }
+    }
+}
+
+/// A change made to one field of a relationship event in the side panel.
+#[derive(Debug, Clone)]
+pub enum RelationshipEdit {
+    Type(RelationshipEventType),
+    Date(String),
+    Place(String),
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::gedcom_io::parser::Structure;
+
+/// Parts of a structured `ADDR`, joined line by line when the address is given only through them.
+const ADDRESS_PARTS: [&str; 7] = ["ADR1", "ADR2", "ADR3", "CITY", "STAE", "POST", "CTRY"];
+
+/// Where sources are kept, e.g. a parish archive, a library or an online collection, GEDCOM's `REPO`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Repository {
+    id: u128,
+    pub xref: Option<String>,
+    pub name: Option<String>,
+    /// The postal address, one line per line of the address.
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub website: Option<String>,
+    /// The `REPO` record this repository was imported from, so that notes, opening hours and the like survive an
+    /// export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Default for Repository {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            xref: None,
+            name: None,
+            address: None,
+            phone: None,
+            email: None,
+            website: None,
+            gedcom_record: None,
+        }
+    }
+}
+
+impl Repository {
+    pub fn from_record(record: &Structure) -> Self {
+        let value = |tag| record.child_value(tag).map(String::from);
+        Self {
+            xref: record.xref.clone(),
+            name: value("NAME"),
+            address: record.child("ADDR").and_then(address_text),
+            phone: value("PHON"),
+            email: value("EMAIL"),
+            website: value("WWW"),
+            gedcom_record: Some(record.clone()),
+            ..Self::default()
+        }
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    /// The name, or a placeholder for a repository that has none yet.
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| "Unnamed repository".to_string())
+    }
+
+    pub fn apply(&mut self, edit: RepositoryEdit) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            RepositoryEdit::Name(name) => self.name = non_empty(name),
+            RepositoryEdit::Address(address) => self.address = non_empty(address),
+            RepositoryEdit::Phone(phone) => self.phone = non_empty(phone),
+            RepositoryEdit::Email(email) => self.email = non_empty(email),
+            RepositoryEdit::Website(website) => self.website = non_empty(website),
+        }
+    }
+
+    /// Whether both are the same archive, regardless of their ids, xrefs and how they can be contacted.
+    pub fn same_content(&self, other: &Repository) -> bool {
+        self.name == other.name && self.address == other.address
+    }
+}
+
+/// A change made to one field of a repository in the side panel.
+#[derive(Debug, Clone)]
+pub enum RepositoryEdit {
+    Name(String),
+    Address(String),
+    Phone(String),
+    Email(String),
+    Website(String),
+}
+
+/// Where a source can be found: the repository holding it and what the repository files it under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryLink {
+    /// The id of the repository in the tree's `Sources`.
+    pub repository: u128,
+    /// The call number or shelf mark of the source in the repository, GEDCOM's `CALN`.
+    pub call_number: Option<String>,
+    /// The `REPO` pointer this link was imported from, so that the medium and further call numbers survive an
+    /// export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl RepositoryLink {
+    pub fn new(repository: u128) -> Self {
+        Self {
+            repository,
+            call_number: None,
+            gedcom_record: None,
+        }
+    }
+
+    /// Reads a `REPO` pointer of a source record pointing at the given repository.
+    pub fn from_structure(structure: &Structure, repository: u128) -> Self {
+        Self {
+            repository,
+            call_number: structure.child_value("CALN").map(String::from),
+            gedcom_record: Some(structure.clone()),
+        }
+    }
+}
+
+/// The address as written in `ADDR`, or else its parts one per line.
+pub fn address_text(structure: &Structure) -> Option<String> {
+    let written = structure.value.clone().filter(|value| !value.trim().is_empty());
+    written.or_else(|| {
+        let parts: Vec<&str> = ADDRESS_PARTS
+            .iter()
+            .filter_map(|tag| structure.child_value(tag))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n"))
+    })
+}
@@ -1,17 +1,120 @@
-use graph::{node::GraphNodeTrait, GraphMessage};
+use std::fmt;
+
+use graph::{edge::GraphEdgeTrait, node::GraphNodeTrait, Graph, GraphMessage, Removal};
 use iced::{
-    widget::{button, checkbox, column, container, text, text_input, Column, Container},
+    widget::{
+        button, checkbox, column, container, pick_list, row, scrollable, text, text_editor, text_input, Column,
+        Container,
+    },
     Background, Border, Color,
     Length::Fill,
     Shadow,
 };
 
 use crate::{
+    association::{Association, AssociationRole, AssociationTarget},
+    family::{Families, Family, Pedigree},
+    gedcom_io::date::GedcomDate,
+    gedcom_io::merge::describe,
+    genealogical_edge::{family_edges_of, GenealogicalEdge, Tie},
     genealogical_node::{GenealogicalNode, Sex},
+    life_event::{EventEdit, EventType, LifeEvent},
+    media::{Media, MediaLink, MediaTarget},
+    name::{NameEdit, NameType, PersonalName},
+    note::{excerpt, search, Note, NoteEditor, NoteTarget, NoteText, Notes},
+    place::{Place, Places},
+    relationship::{RelationshipEdit, RelationshipEventType, RelationshipKind},
+    repository::{Repository, RepositoryEdit},
+    source::{Citation, CitationEdit, CitationTarget, Quality, Source, SourceEdit, Sources},
     Message,
 };
 
-pub fn side_panel<'a>(selected_node: Option<&'a GenealogicalNode>) -> Container<'a, Message> {
+/// A person, source, repository, media object or shared note as offered in the pickers.
+#[derive(Debug, Clone, PartialEq)]
+struct Choice {
+    id: u128,
+    name: String,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Choice {
+    /// Everyone in the tree but the given person.
+    fn persons(graph: &Graph<GenealogicalNode, GenealogicalEdge>, except: u128) -> Vec<Choice> {
+        graph
+            .nodes()
+            .iter()
+            .filter(|node| node.id() != except)
+            .map(|node| Choice {
+                id: node.id(),
+                name: describe(node),
+            })
+            .collect()
+    }
+
+    fn sources(sources: &Sources) -> Vec<Choice> {
+        sources
+            .iter()
+            .map(|source| Choice {
+                id: source.id(),
+                name: source.name(),
+            })
+            .collect()
+    }
+
+    fn repositories(sources: &Sources) -> Vec<Choice> {
+        sources
+            .repositories()
+            .map(|repository| Choice {
+                id: repository.id(),
+                name: repository.name(),
+            })
+            .collect()
+    }
+
+    fn media(media: &Media) -> Vec<Choice> {
+        media
+            .iter()
+            .map(|object| Choice {
+                id: object.id(),
+                name: object.name(),
+            })
+            .collect()
+    }
+
+    fn notes(notes: &Notes) -> Vec<Choice> {
+        notes
+            .iter()
+            .map(|note| Choice {
+                id: note.id(),
+                name: excerpt(&note.text),
+            })
+            .collect()
+    }
+}
+
+/// The notes of the tree as the side panel needs them: all of them, the one being edited and the current search.
+pub struct NotesView<'a> {
+    pub notes: &'a Notes,
+    pub editor: Option<&'a NoteEditor>,
+    pub search: &'a str,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn side_panel<'a>(
+    graph: &'a Graph<GenealogicalNode, GenealogicalEdge>,
+    families: &'a Families,
+    places: &'a Places,
+    sources: &'a Sources,
+    media: &'a Media,
+    notes: NotesView<'a>,
+    selected_source: Option<&'a Source>,
+    selected_repository: Option<&'a Repository>,
+) -> Container<'a, Message> {
     let mut root = column![
         text("Graph").color(Color::BLACK),
         button("Add new")
@@ -19,11 +122,17 @@ pub fn side_panel<'a>(selected_node: Option<&'a GenealogicalNode>) -> Container<
             .on_press(Message::Graph(GraphMessage::InsertNode(None))),
     ]
     .spacing(10);
-    if let Some(selected_node) = selected_node {
-        root = select_node_content(root, selected_node);
+    if let Some(removal) = graph.pending_removal() {
+        root = root.push(removal_content(removal, graph));
+    }
+    root = root.push(search_content(graph, sources, &notes));
+    root = root.push(sources_content(sources, media, &notes, selected_source));
+    root = root.push(repositories_content(sources, selected_repository));
+    if let Some(selected_node) = graph.selected_node() {
+        root = select_node_content(root, selected_node, graph, families, places, sources, media, &notes);
     }
 
-    let root = container(root)
+    let root = container(scrollable(root))
         .width(300)
         .padding(10)
         .height(Fill)
@@ -40,32 +149,781 @@ pub fn side_panel<'a>(selected_node: Option<&'a GenealogicalNode>) -> Container<
     root
 }
 
-fn select_node_content<'a>(mut root: Column<'a, Message>, node: &'a GenealogicalNode) -> Column<'a, Message> {
-    let selected_node_widgets: Column<'a, Message> = column![
-        text(format!("Selected node: {}", node.id())).color(Color::BLACK),
-        button("Add connection")
-            .width(Fill)
-            .on_press(Message::Graph(GraphMessage::DrawEdge(node.id()))),
-        text_input("First name", &node.first_name().unwrap_or("".to_string()))
-            .on_input(move |input| Message::UpdateNodeFirstName((node.id(), input)))
-            .padding(10)
-            .size(20)
+/// Asks before a person or a link is removed, what else goes with it is named.
+fn removal_content<'a>(removal: Removal, graph: &Graph<GenealogicalNode, GenealogicalEdge>) -> Column<'a, Message> {
+    let name = |id: u128| {
+        graph
+            .get_node(Some(id))
+            .map(describe)
+            .unwrap_or_else(|| "Unknown person".to_string())
+    };
+    let (question, confirm) = match removal {
+        Removal::Node(id) => (
+            format!(
+                "Remove {} together with their links, family ties and associations?",
+                name(id)
+            ),
+            GraphMessage::RemoveNode(id),
+        ),
+        Removal::Edge(first, second) => (
+            format!("Remove the link between {} and {}?", name(first), name(second)),
+            GraphMessage::RemoveEdge(first, second),
+        ),
+    };
+    column![
+        text(question).color(Color::from_rgb(0.8, 0.0, 0.0)),
+        row![
+            button("Remove").on_press(Message::Graph(confirm)),
+            button("Cancel").on_press(Message::Graph(GraphMessage::CancelRemoval)),
+        ]
+        .spacing(5),
+    ]
+    .spacing(5)
+}
+
+/// Finds the notes containing the search words, a result opens the note next to what it is attached to.
+fn search_content<'a>(
+    graph: &'a Graph<GenealogicalNode, GenealogicalEdge>,
+    sources: &'a Sources,
+    notes: &NotesView<'a>,
+) -> Column<'a, Message> {
+    let mut content = column![
+        text("Notes").color(Color::BLACK),
+        text_input("Search notes", notes.search)
+            .on_input(Message::SearchNotes)
+            .width(Fill),
+    ]
+    .spacing(5);
+    let matches = search(notes.search, graph, notes.notes, sources);
+    if matches.is_empty() && !notes.search.trim().is_empty() {
+        content = content.push(text("No notes found").size(12).color(Color::from_rgb(0.4, 0.4, 0.4)));
+    }
+    for found in matches {
+        content = content.push(
+            button(column![text(found.owner).size(12), text(found.excerpt).size(14),].spacing(2))
+                .width(Fill)
+                .style(button::secondary)
+                .on_press(Message::OpenNote((found.target, found.index))),
+        );
+    }
+    content
+}
+
+/// Browses the sources of the tree and edits the one picked.
+fn sources_content<'a>(
+    sources: &'a Sources,
+    media: &'a Media,
+    notes: &NotesView<'a>,
+    selected_source: Option<&'a Source>,
+) -> Column<'a, Message> {
+    let selected = selected_source.map(|source| Choice {
+        id: source.id(),
+        name: source.name(),
+    });
+    let mut content = column![
+        text("Sources").color(Color::BLACK),
+        row![
+            pick_list(Choice::sources(sources), selected, |choice| Message::SelectSource(
+                choice.id
+            ))
+            .placeholder("Browse sources")
+            .width(Fill),
+            button("Add").on_press(Message::AddSource),
+        ]
+        .spacing(5),
+    ]
+    .spacing(5);
+    let Some(source) = selected_source else {
+        return content;
+    };
+    let source_id = source.id();
+    let edit = move |edit: fn(String) -> SourceEdit| move |input| Message::UpdateSource((source_id, edit(input)));
+    for (placeholder, value, on_input) in [
+        ("Title", &source.title, edit(SourceEdit::Title)),
+        ("Author", &source.author, edit(SourceEdit::Author)),
+        ("Publication", &source.publication, edit(SourceEdit::Publication)),
+        ("Abbreviation", &source.abbreviation, edit(SourceEdit::Abbreviation)),
+        ("Text", &source.text, edit(SourceEdit::Text)),
+    ] {
+        content = content.push(
+            text_input(placeholder, value.as_deref().unwrap_or_default())
+                .on_input(on_input)
+                .width(Fill),
+        );
+    }
+    content = content.push(text("Held at").color(Color::BLACK));
+    for (index, link) in source.repositories.iter().enumerate() {
+        let name = sources
+            .repository(link.repository)
+            .map(Repository::name)
+            .unwrap_or_default();
+        content = content.push(
+            column![
+                row![
+                    text(name).color(Color::BLACK).width(Fill),
+                    button("Remove").on_press(Message::RemoveRepositoryLink((source_id, index))),
+                ]
+                .spacing(5),
+                text_input("Call number", link.call_number.as_deref().unwrap_or_default())
+                    .on_input(move |call_number| Message::UpdateCallNumber((source_id, index, call_number)))
+                    .width(Fill),
+            ]
+            .spacing(2),
+        );
+    }
+    content
+        .push(
+            pick_list(Choice::repositories(sources), None::<Choice>, move |choice| {
+                Message::LinkRepository((source_id, choice.id))
+            })
+            .placeholder("Add repository")
+            .width(Fill),
+        )
+        .push(notes_content(NoteTarget::Source(source_id), &source.notes, notes))
+        .push(media_content(
+            MediaTarget::Source(source_id),
+            &source.media,
+            media,
+            None,
+        ))
+        .push(button("Done").on_press(Message::CloseSource))
+}
+
+/// Browses the archives and libraries of the tree, edits the one picked and lists what to ask for when visiting it.
+fn repositories_content<'a>(sources: &'a Sources, selected_repository: Option<&'a Repository>) -> Column<'a, Message> {
+    let selected = selected_repository.map(|repository| Choice {
+        id: repository.id(),
+        name: repository.name(),
+    });
+    let mut content = column![
+        text("Repositories").color(Color::BLACK),
+        row![
+            pick_list(Choice::repositories(sources), selected, |choice| {
+                Message::SelectRepository(choice.id)
+            })
+            .placeholder("Browse repositories")
+            .width(Fill),
+            button("Add").on_press(Message::AddRepository),
+        ]
+        .spacing(5),
+    ]
+    .spacing(5);
+    let Some(repository) = selected_repository else {
+        return content;
+    };
+    let repository_id = repository.id();
+    let edit =
+        move |edit: fn(String) -> RepositoryEdit| move |input| Message::UpdateRepository((repository_id, edit(input)));
+    for (placeholder, value, on_input) in [
+        ("Name", &repository.name, edit(RepositoryEdit::Name)),
+        ("Address", &repository.address, edit(RepositoryEdit::Address)),
+        ("Phone", &repository.phone, edit(RepositoryEdit::Phone)),
+        ("Email", &repository.email, edit(RepositoryEdit::Email)),
+        ("Website", &repository.website, edit(RepositoryEdit::Website)),
+    ] {
+        content = content.push(
+            text_input(placeholder, value.as_deref().unwrap_or_default())
+                .on_input(on_input)
+                .width(Fill),
+        );
+    }
+    content = content.push(text("Sources held here").color(Color::BLACK));
+    for (source, link) in sources.held_by(repository_id) {
+        let label = match &link.call_number {
+            Some(call_number) => format!("{} ({})", source.name(), call_number),
+            None => source.name(),
+        };
+        content = content.push(
+            button(text(label).size(14))
+                .width(Fill)
+                .style(button::secondary)
+                .on_press(Message::SelectSource(source.id())),
+        );
+    }
+    content.push(button("Done").on_press(Message::CloseRepository))
+}
+
+/// The notes of a person, event, relationship or source, with buttons to write another one or link a shared one.
+///
+/// The note being edited is shown in a multi-line editor, the others as their text.
+fn notes_content<'a>(target: NoteTarget, attached: &'a [Note], notes: &NotesView<'a>) -> Column<'a, Message> {
+    let mut content = column![].spacing(5);
+    for (index, note) in attached.iter().enumerate() {
+        let shared = matches!(note.text, NoteText::Shared(_));
+        let label = if shared { "Shared note" } else { "Note" };
+        let mut header = row![text(label).size(12).color(Color::BLACK).width(Fill)].spacing(5);
+        let body = match notes.editor.filter(|editor| editor.is_editing(&target, index)) {
+            Some(editor) => {
+                header = header.push(button("Done").on_press(Message::CloseNoteEditor));
+                column![text_editor(&editor.content)
+                    .on_action(Message::NoteEditorAction)
+                    .height(120)]
+            }
+            None => {
+                header = header.push(button("Edit").on_press(Message::EditNote((target.clone(), index))));
+                if !shared {
+                    header = header.push(button("Share").on_press(Message::ShareNote((target.clone(), index))));
+                }
+                let text_of_note = notes.notes.text(note);
+                column![if text_of_note.trim().is_empty() {
+                    text("Empty note").size(14).color(Color::from_rgb(0.4, 0.4, 0.4))
+                } else {
+                    text(text_of_note).size(14).color(Color::BLACK)
+                }]
+            }
+        };
+        header = header.push(button("Remove").on_press(Message::RemoveNote((target.clone(), index))));
+        content = content.push(column![header, body].spacing(2));
+    }
+    let link_target = target.clone();
+    content.push(
+        row![
+            button("Add note").on_press(Message::AddNote(target)),
+            pick_list(Choice::notes(notes.notes), None::<Choice>, move |choice| {
+                Message::LinkNote((link_target.clone(), choice.id))
+            })
+            .placeholder("Link shared note")
             .width(Fill),
-        text_input("Last name", &node.last_name().unwrap_or("".to_string()))
-            .on_input(move |input| Message::UpdateNodeLastName((node.id(), input)))
-            .padding(10)
-            .size(20)
+        ]
+        .spacing(5),
+    )
+}
+
+/// The media linked to a person or source, with buttons to attach a file or link one already in the tree.
+///
+/// A person's images can be picked as the portrait shown on the card.
+fn media_content<'a>(
+    target: MediaTarget,
+    links: &'a [MediaLink],
+    media: &'a Media,
+    portrait: Option<(u128, Option<u128>)>,
+) -> Column<'a, Message> {
+    let mut content = column![].spacing(5);
+    for (index, link) in links.iter().enumerate() {
+        let Some(object) = media.get(link.object) else {
+            continue;
+        };
+        let object_id = object.id();
+        let mut entry = column![
+            row![
+                text(object.name()).color(Color::BLACK).width(Fill),
+                button("Remove").on_press(Message::RemoveMedia((target.clone(), index))),
+            ]
+            .spacing(5),
+            text_input("Title", object.title.as_deref().unwrap_or_default())
+                .on_input(move |title| Message::UpdateMediaTitle((object_id, title)))
+                .width(Fill),
+        ]
+        .spacing(2);
+        if media.path(object_id).is_some_and(|path| !path.exists()) {
+            entry = entry.push(text("File not found").size(12).color(Color::from_rgb(0.8, 0.0, 0.0)));
+        }
+        if let Some((node_id, portrait)) = portrait.filter(|_| object.is_image()) {
+            entry = entry.push(
+                checkbox("Portrait", portrait == Some(object_id))
+                    .on_toggle(move |checked| Message::SetPortrait((node_id, checked.then_some(object_id)))),
+            );
+        }
+        content = content.push(entry);
+    }
+    let link_target = target.clone();
+    content.push(
+        row![
+            button("Attach file").on_press(Message::AttachMedia(target)),
+            pick_list(Choice::media(media), None::<Choice>, move |choice| {
+                Message::LinkMedia((link_target.clone(), choice.id))
+            })
+            .placeholder("Link media")
             .width(Fill),
+        ]
+        .spacing(5),
+    )
+}
+
+/// The citations of a person, event or relationship, with a button to cite another source.
+fn citations_content<'a>(
+    target: CitationTarget,
+    citations: &'a [Citation],
+    sources: &'a Sources,
+) -> Column<'a, Message> {
+    let mut content = column![].spacing(5);
+    for (index, citation) in citations.iter().enumerate() {
+        let selected = sources.get(citation.source).map(|source| Choice {
+            id: source.id(),
+            name: source.name(),
+        });
+        content = content.push(
+            column![
+                row![
+                    pick_list(
+                        Choice::sources(sources),
+                        selected,
+                        update_citation(&target, index, |choice: Choice| CitationEdit::Source(choice.id)),
+                    )
+                    .width(Fill),
+                    button("Remove").on_press(Message::RemoveCitation((target.clone(), index))),
+                ]
+                .spacing(5),
+                text_input("Page", citation.page.as_deref().unwrap_or_default())
+                    .on_input(update_citation(&target, index, CitationEdit::Page))
+                    .width(Fill),
+                pick_list(
+                    Quality::ALL,
+                    citation.quality,
+                    update_citation(&target, index, CitationEdit::Quality),
+                )
+                .placeholder("Quality")
+                .width(Fill),
+                text_input("Text from the source", citation.text.as_deref().unwrap_or_default())
+                    .on_input(update_citation(&target, index, CitationEdit::Text))
+                    .width(Fill),
+            ]
+            .spacing(2),
+        );
+    }
+    let has_sources = sources.iter().next().is_some();
+    content.push(
+        button("Cite source")
+            .width(Fill)
+            .on_press_maybe(has_sources.then_some(Message::AddCitation(target))),
+    )
+}
+
+/// Every widget of a citation needs a target of its own to send its edits with.
+fn update_citation<T>(target: &CitationTarget, index: usize, edit: fn(T) -> CitationEdit) -> impl Fn(T) -> Message {
+    let target = target.clone();
+    move |value| Message::UpdateCitation((target.clone(), index, edit(value)))
+}
+
+/// The people linked to a person or event outside the family and their roles, with a picker to link another one.
+fn associations_content<'a>(
+    target: AssociationTarget,
+    associations: &'a [Association],
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+) -> Column<'a, Message> {
+    let node_id = match target {
+        AssociationTarget::Person(node_id) | AssociationTarget::Event((node_id, _)) => node_id,
+    };
+    let mut content = column![].spacing(5);
+    for (index, association) in associations.iter().enumerate() {
+        let person = graph
+            .get_node(Some(association.person))
+            .map(describe)
+            .unwrap_or_else(|| "Unknown person".to_string());
+        // Every custom role is listed as a plain `Other`, its name is edited in a field of its own.
+        let selected = match &association.role {
+            AssociationRole::Other(_) => AssociationRole::Other(String::new()),
+            role => role.clone(),
+        };
+        let mut entry = column![
+            text(person).color(Color::BLACK),
+            row![
+                pick_list(AssociationRole::ALL, Some(selected), move |role| {
+                    Message::UpdateAssociationRole((target, index, role))
+                })
+                .width(Fill),
+                button("Remove").on_press(Message::RemoveAssociation((target, index))),
+            ]
+            .spacing(5),
+        ]
+        .spacing(2);
+        if let AssociationRole::Other(name) = &association.role {
+            entry = entry.push(
+                text_input("Role", name)
+                    .on_input(move |name| Message::UpdateAssociationRole((target, index, AssociationRole::Other(name))))
+                    .width(Fill),
+            );
+        }
+        content = content.push(entry);
+    }
+    content.push(
+        pick_list(Choice::persons(graph, node_id), None::<Choice>, move |choice| {
+            Message::AddAssociation((target, choice.id))
+        })
+        .placeholder("Add association")
+        .width(Fill),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn select_node_content<'a>(
+    mut root: Column<'a, Message>,
+    node: &'a GenealogicalNode,
+    graph: &'a Graph<GenealogicalNode, GenealogicalEdge>,
+    families: &'a Families,
+    places: &'a Places,
+    sources: &'a Sources,
+    media: &'a Media,
+    notes: &NotesView<'a>,
+) -> Column<'a, Message> {
+    let selected_node_widgets: Column<'a, Message> = column![
+        text(format!("Selected node: {}", node.id())).color(Color::BLACK),
+        row![
+            button("Link partner")
+                .width(Fill)
+                .on_press(Message::DrawTie((node.id(), Tie::Partner))),
+            button("Link parent")
+                .width(Fill)
+                .on_press(Message::DrawTie((node.id(), Tie::Parent))),
+            button("Link child")
+                .width(Fill)
+                .on_press(Message::DrawTie((node.id(), Tie::Child))),
+        ]
+        .spacing(5),
+        text("Names").color(Color::BLACK),
+        names_content(node),
         text("Sex").color(Color::BLACK),
-        checkbox("Male", node.sex().map_or(false, |sex| sex == Sex::Male))
-            .on_toggle(|checked| { Message::SetNodeSex((node.id(), if checked { Sex::Male } else { Sex::Female })) }),
-        checkbox("Female", node.sex().map_or(false, |sex| sex == Sex::Female))
-            .on_toggle(|checked| { Message::SetNodeSex((node.id(), if checked { Sex::Female } else { Sex::Male })) }),
+        pick_list(Sex::ALL, node.sex(), |sex| Message::SetNodeSex((node.id(), sex)))
+            .placeholder("Not recorded")
+            .width(Fill),
         button("Add offspring")
             .width(Fill)
             .on_press(Message::Graph(GraphMessage::InsertNode(Some(node.id())))),
+        button("Remove person")
+            .width(Fill)
+            .on_press(Message::Graph(GraphMessage::RequestRemoval(Removal::Node(node.id())))),
     ]
     .spacing(10);
     root = root.push(selected_node_widgets);
-    root
+    root = root.push(text("Connections").color(Color::BLACK));
+    root = root.push(connections_content(node.id(), graph));
+    root = root.push(text("Notes on this person").color(Color::BLACK));
+    root = root.push(notes_content(NoteTarget::Person(node.id()), node.notes(), notes));
+    root = root.push(text("Media").color(Color::BLACK));
+    root = root.push(media_content(
+        MediaTarget::Person(node.id()),
+        node.media(),
+        media,
+        Some((node.id(), node.portrait())),
+    ));
+    root = root.push(text("Sources of this person").color(Color::BLACK));
+    root = root.push(citations_content(
+        CitationTarget::Person(node.id()),
+        node.citations(),
+        sources,
+    ));
+    root = root.push(text("Associated people").color(Color::BLACK));
+    root = root.push(associations_content(
+        AssociationTarget::Person(node.id()),
+        node.associations(),
+        graph,
+    ));
+    for (partner, family) in families.partnerships_of(node.id()) {
+        let Some(partner) = graph.get_node(Some(partner)) else {
+            continue;
+        };
+        let partners = vec![node.id(), partner.id()];
+        root = root.push(text(format!("Relationship with {}", describe(partner))).color(Color::BLACK));
+        root = root.push(relationship_content(family, places));
+        root = root.push(citations_content(
+            CitationTarget::Relationship(partners.clone()),
+            sources.relationship(&partners),
+            sources,
+        ));
+        root = root.push(notes_content(
+            NoteTarget::Relationship(partners.clone()),
+            notes.notes.relationship(&partners),
+            notes,
+        ));
+    }
+    for family in families.parents_of(node.id()) {
+        root = root.push(parents_content(family, node.id(), graph));
+    }
+    root = root.push(
+        row![
+            text("Events").color(Color::BLACK).width(Fill),
+            button("Sort by date").on_press(Message::SortNodeEvents(node.id())),
+        ]
+        .spacing(5),
+    );
+    for (index, event) in node.events().iter().enumerate() {
+        root = root.push(event_content(node.id(), index, event, graph, places, sources, notes));
+    }
+    root.push(
+        button("Add event")
+            .width(Fill)
+            .on_press(Message::AddNodeEvent(node.id())),
+    )
+}
+
+/// The people a person is linked to by an edge, each with a button to remove the link.
+fn connections_content<'a>(node_id: u128, graph: &Graph<GenealogicalNode, GenealogicalEdge>) -> Column<'a, Message> {
+    let mut content = column![].spacing(5);
+    for edge in family_edges_of(graph, node_id) {
+        let other = match (edge.start(), edge.end()) {
+            (start, other) if start == node_id => other,
+            (other, end) if end == node_id => other,
+            _ => continue,
+        };
+        let Some(person) = graph.get_node(Some(other)) else {
+            continue;
+        };
+        content = content.push(
+            row![
+                text(describe(person)).color(Color::BLACK).width(Fill),
+                button("Remove").on_press(Message::Graph(GraphMessage::RequestRemoval(Removal::Edge(
+                    node_id, other
+                )))),
+            ]
+            .spacing(5),
+        );
+    }
+    content
+}
+
+/// The parents of a family the person is a child of and how the person belongs to it.
+fn parents_content<'a>(
+    family: &'a Family,
+    child: u128,
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+) -> Column<'a, Message> {
+    let family_id = family.id();
+    let parents: Vec<String> = family
+        .partners
+        .iter()
+        .filter_map(|id| graph.get_node(Some(*id)))
+        .map(describe)
+        .collect();
+    let parents = match parents.is_empty() {
+        true => "unknown parents".to_string(),
+        false => parents.join(" and "),
+    };
+    let pedigree = family.pedigree(child).cloned().unwrap_or_default();
+    // Every custom pedigree is listed as a plain `Other`, its name is edited in a field of its own.
+    let selected = match &pedigree {
+        Pedigree::Other(_) => Pedigree::Other(String::new()),
+        pedigree => pedigree.clone(),
+    };
+    let mut content = column![
+        text(format!("Child of {}", parents)).color(Color::BLACK),
+        pick_list(Pedigree::ALL, Some(selected), move |pedigree| {
+            Message::SetPedigree((family_id, child, pedigree))
+        })
+        .width(Fill),
+    ]
+    .spacing(5);
+    if let Pedigree::Other(name) = pedigree {
+        content = content.push(
+            text_input("Relation", &name)
+                .on_input(move |name| Message::SetPedigree((family_id, child, Pedigree::Other(name))))
+                .width(Fill),
+        );
+    }
+    content
+}
+
+/// The kind of a relationship and the changes in its status. Neither says anything about the sex of the partners.
+fn relationship_content<'a>(family: &'a Family, places: &'a Places) -> Column<'a, Message> {
+    let family_id = family.id();
+    let mut content = column![pick_list(RelationshipKind::ALL, Some(family.kind), move |kind| {
+        Message::SetRelationshipKind((family_id, kind))
+    })
+    .width(Fill)]
+    .spacing(5);
+    for (index, event) in family.events.iter().enumerate() {
+        let edit = move |edit: fn(String) -> RelationshipEdit| {
+            move |input| Message::UpdateRelationshipEvent((family_id, index, edit(input)))
+        };
+        content = content
+            .push(
+                row![
+                    pick_list(
+                        RelationshipEventType::ALL,
+                        Some(event.event_type.clone()),
+                        move |event_type| {
+                            Message::UpdateRelationshipEvent((family_id, index, RelationshipEdit::Type(event_type)))
+                        }
+                    )
+                    .width(Fill),
+                    button("Remove").on_press(Message::RemoveRelationshipEvent((family_id, index))),
+                ]
+                .spacing(5),
+            )
+            .push(date_input(
+                event.date.as_deref().unwrap_or_default(),
+                edit(RelationshipEdit::Date),
+            ))
+            .push(place_input(
+                event.place.and_then(|id| places.get(id)),
+                edit(RelationshipEdit::Place),
+            ));
+    }
+    content.push(
+        button("Add status change")
+            .width(Fill)
+            .on_press(Message::AddRelationshipEvent(family_id)),
+    )
+}
+
+fn names_content(node: &GenealogicalNode) -> Column<'_, Message> {
+    let node_id = node.id();
+    let mut content = column![].spacing(10);
+    for (index, name) in node.names().iter().enumerate() {
+        content = content.push(name_content(node_id, index, name));
+    }
+    content.push(button("Add name").width(Fill).on_press(Message::AddNodeName(node_id)))
+}
+
+fn name_content(node_id: u128, index: usize, name: &PersonalName) -> Column<'_, Message> {
+    let edit = move |edit: fn(String) -> NameEdit| move |input| Message::UpdateNodeName((node_id, index, edit(input)));
+    // Every custom type is listed as a plain `Other`, its name is edited in a field of its own.
+    let selected = match &name.name_type {
+        Some(NameType::Other(_)) => Some(NameType::Other(String::new())),
+        name_type => name_type.clone(),
+    };
+    let shown = match name.preferred {
+        true => button("On card"),
+        false => button("Show on card").on_press(Message::SetPreferredName((node_id, index))),
+    };
+    let mut content = column![row![
+        pick_list(NameType::ALL, selected, move |name_type| {
+            Message::UpdateNodeName((node_id, index, NameEdit::Type(Some(name_type))))
+        })
+        .placeholder("Name type")
+        .width(Fill),
+        shown,
+        button("Remove").on_press(Message::RemoveNodeName((node_id, index))),
+    ]
+    .spacing(5)]
+    .spacing(5);
+    if let Some(NameType::Other(other)) = &name.name_type {
+        content = content.push(
+            text_input("Name type", other)
+                .on_input(edit(|other| NameEdit::Type(Some(NameType::Other(other)))))
+                .width(Fill),
+        );
+    }
+    let input = |placeholder, value: &Option<String>, on_input| {
+        text_input(placeholder, value.as_deref().unwrap_or_default())
+            .on_input(on_input)
+            .width(Fill)
+    };
+    content
+        .push(
+            row![
+                input("Title", &name.prefix, edit(NameEdit::Prefix)),
+                input("Nickname", &name.nickname, edit(NameEdit::Nickname))
+            ]
+            .spacing(5),
+        )
+        .push(
+            input("Given names", &name.given, edit(NameEdit::Given))
+                .padding(10)
+                .size(20),
+        )
+        .push(
+            row![
+                input("Prefix", &name.surname_prefix, edit(NameEdit::SurnamePrefix)).width(80),
+                input("Surname", &name.surname, edit(NameEdit::Surname)),
+            ]
+            .spacing(5),
+        )
+        .push(input("Suffix", &name.suffix, edit(NameEdit::Suffix)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn event_content<'a>(
+    node_id: u128,
+    index: usize,
+    event: &'a LifeEvent,
+    graph: &Graph<GenealogicalNode, GenealogicalEdge>,
+    places: &'a Places,
+    sources: &'a Sources,
+    notes: &NotesView<'a>,
+) -> Column<'a, Message> {
+    let edit =
+        move |edit: fn(String) -> EventEdit| move |input| Message::UpdateNodeEvent((node_id, index, edit(input)));
+    // Every custom type is listed as a plain `Other`, its name is edited in a field of its own.
+    let selected = match &event.event_type {
+        EventType::Other(_) => EventType::Other(String::new()),
+        event_type => event_type.clone(),
+    };
+    let mut content = column![row![
+        pick_list(EventType::ALL, Some(selected), move |event_type| {
+            Message::UpdateNodeEvent((node_id, index, EventEdit::Type(event_type)))
+        })
+        .width(Fill),
+        button("Remove").on_press(Message::RemoveNodeEvent((node_id, index))),
+    ]
+    .spacing(5)]
+    .spacing(5);
+    if let EventType::Other(name) = &event.event_type {
+        content = content.push(
+            text_input("Event type", name)
+                .on_input(edit(|name| EventEdit::Type(EventType::Other(name))))
+                .width(Fill),
+        );
+    }
+    content = content.push(date_input(
+        event.date.as_deref().unwrap_or_default(),
+        edit(EventEdit::Date),
+    ));
+    content = content.push(place_input(
+        event.place.and_then(|id| places.get(id)),
+        edit(EventEdit::Place),
+    ));
+    for (placeholder, value, on_input) in [
+        ("Description", &event.description, edit(EventEdit::Description)),
+        ("Age", &event.age, edit(EventEdit::Age)),
+    ] {
+        content = content.push(
+            text_input(placeholder, value.as_deref().unwrap_or_default())
+                .on_input(on_input)
+                .width(Fill),
+        );
+    }
+    content
+        .push(citations_content(
+            CitationTarget::Event((node_id, index)),
+            &event.citations,
+            sources,
+        ))
+        .push(notes_content(NoteTarget::Event((node_id, index)), &event.notes, notes))
+        .push(associations_content(
+            AssociationTarget::Event((node_id, index)),
+            &event.associations,
+            graph,
+        ))
+}
+
+/// Shows where the place lies in the jurisdiction hierarchy and its coordinates below the name.
+fn place_input<'a>(place: Option<&'a Place>, on_input: impl Fn(String) -> Message + 'a) -> Column<'a, Message> {
+    let input = text_input("Place", &place.map(Place::name).unwrap_or_default())
+        .on_input(on_input)
+        .width(Fill);
+    let details = place.and_then(|place| {
+        let mut details = vec![];
+        if let Some(parent) = place.parent_name() {
+            details.push(format!("in {}", parent));
+        }
+        if let Some((latitude, longitude)) = place.coordinates() {
+            details.push(format!("{:.4}, {:.4}", latitude, longitude));
+        }
+        (!details.is_empty()).then(|| {
+            text(details.join(" at "))
+                .size(12)
+                .color(Color::from_rgb(0.4, 0.4, 0.4))
+        })
+    });
+    column![input].push_maybe(details).spacing(2)
+}
+
+/// Takes dates as typed, on Enter a date like `12.3.1850` is turned into its GEDCOM form `12 MAR 1850`.
+fn date_input<'a>(date: &'a str, on_input: impl Fn(String) -> Message + 'a) -> Column<'a, Message> {
+    let (hint, submit) = match date.parse::<GedcomDate>() {
+        _ if date.trim().is_empty() => (None, None),
+        Ok(_) => (None, None),
+        Err(error) => match GedcomDate::parse_input(date) {
+            Ok(parsed) => (
+                Some(text(format!("Press Enter to use {}", parsed)).color(Color::from_rgb(0.4, 0.4, 0.4))),
+                Some(on_input(parsed.to_string())),
+            ),
+            Err(_) => (Some(text(error).color(Color::from_rgb(0.8, 0.0, 0.0))), None),
+        },
+    };
+    let input = text_input("Date", date)
+        .on_input(on_input)
+        .on_submit_maybe(submit)
+        .width(Fill);
+    column![input].push_maybe(hint.map(|hint| hint.size(12))).spacing(2)
 }
@@ -0,0 +1,363 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    family::same_members,
+    gedcom_io::parser::Structure,
+    media::MediaLink,
+    note::Note,
+    repository::{Repository, RepositoryLink},
+};
+
+/// Where information was found, e.g. a parish register, a census or a book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+    id: u128,
+    pub xref: Option<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// When, where and by whom the source was published.
+    pub publication: Option<String>,
+    /// A short title to sort and find the source by.
+    pub abbreviation: Option<String>,
+    /// A transcript of the source itself.
+    pub text: Option<String>,
+    /// The archives and libraries holding the source.
+    #[serde(default)]
+    pub repositories: Vec<RepositoryLink>,
+    /// Scans and photos of the source.
+    #[serde(default)]
+    pub media: Vec<MediaLink>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// The `SOUR` record this source was imported from, so that identifiers, dates and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().as_u128(),
+            xref: None,
+            title: None,
+            author: None,
+            publication: None,
+            abbreviation: None,
+            text: None,
+            repositories: vec![],
+            media: vec![],
+            notes: vec![],
+            gedcom_record: None,
+        }
+    }
+}
+
+impl Source {
+    pub fn from_record(record: &Structure) -> Self {
+        let value = |tag| record.child_value(tag).map(String::from);
+        Self {
+            xref: record.xref.clone(),
+            title: value("TITL"),
+            author: value("AUTH"),
+            publication: value("PUBL"),
+            abbreviation: value("ABBR"),
+            text: value("TEXT"),
+            gedcom_record: Some(record.clone()),
+            ..Self::default()
+        }
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    /// The title, or the abbreviation when a source has no title.
+    pub fn name(&self) -> String {
+        self.title
+            .clone()
+            .or_else(|| self.abbreviation.clone())
+            .unwrap_or_else(|| "Untitled source".to_string())
+    }
+
+    pub fn apply(&mut self, edit: SourceEdit) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            SourceEdit::Title(title) => self.title = non_empty(title),
+            SourceEdit::Author(author) => self.author = non_empty(author),
+            SourceEdit::Publication(publication) => self.publication = non_empty(publication),
+            SourceEdit::Abbreviation(abbreviation) => self.abbreviation = non_empty(abbreviation),
+            SourceEdit::Text(text) => self.text = non_empty(text),
+        }
+    }
+
+    /// Whether both say the same, regardless of their ids and xrefs.
+    pub fn same_content(&self, other: &Source) -> bool {
+        self.title == other.title
+            && self.author == other.author
+            && self.publication == other.publication
+            && self.abbreviation == other.abbreviation
+            && self.text == other.text
+    }
+}
+
+/// A change made to one field of a source in the side panel.
+#[derive(Debug, Clone)]
+pub enum SourceEdit {
+    Title(String),
+    Author(String),
+    Publication(String),
+    Abbreviation(String),
+    Text(String),
+}
+
+/// How much a citation can be relied on, GEDCOM's `QUAY` from 0 to 3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quality {
+    Unreliable,
+    Questionable,
+    Secondary,
+    Primary,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 4] = [
+        Quality::Unreliable,
+        Quality::Questionable,
+        Quality::Secondary,
+        Quality::Primary,
+    ];
+
+    pub fn from_gedcom(value: &str) -> Option<Self> {
+        match value.trim() {
+            "0" => Some(Quality::Unreliable),
+            "1" => Some(Quality::Questionable),
+            "2" => Some(Quality::Secondary),
+            "3" => Some(Quality::Primary),
+            _ => None,
+        }
+    }
+
+    pub fn gedcom_value(&self) -> &'static str {
+        match self {
+            Quality::Unreliable => "0",
+            Quality::Questionable => "1",
+            Quality::Secondary => "2",
+            Quality::Primary => "3",
+        }
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quality::Unreliable => write!(f, "Unreliable"),
+            Quality::Questionable => write!(f, "Questionable"),
+            Quality::Secondary => write!(f, "Secondary evidence"),
+            Quality::Primary => write!(f, "Primary evidence"),
+        }
+    }
+}
+
+/// A reference to a source backing a person, an event or a relationship.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Citation {
+    /// The id of the source in the tree's `Sources`.
+    pub source: u128,
+    /// Where in the source the information is, e.g. a page or an entry number.
+    pub page: Option<String>,
+    pub quality: Option<Quality>,
+    /// The words of the source the citation relies on.
+    pub text: Option<String>,
+    /// The structure this citation was imported from, so that its notes, dates and the like survive an export.
+    pub gedcom_record: Option<Structure>,
+}
+
+impl Citation {
+    pub fn new(source: u128) -> Self {
+        Self {
+            source,
+            page: None,
+            quality: None,
+            text: None,
+            gedcom_record: None,
+        }
+    }
+
+    /// Reads a `SOUR` citation pointing at the given source.
+    pub fn from_structure(structure: &Structure, source: u128) -> Self {
+        Self {
+            source,
+            page: structure.child_value("PAGE").map(String::from),
+            quality: structure.child_value("QUAY").and_then(Quality::from_gedcom),
+            text: structure
+                .child("DATA")
+                .and_then(|data| data.child_value("TEXT"))
+                .map(String::from),
+            gedcom_record: Some(structure.clone()),
+        }
+    }
+
+    pub fn apply(&mut self, edit: CitationEdit) {
+        let non_empty = |value: String| Some(value).filter(|value| !value.trim().is_empty());
+        match edit {
+            CitationEdit::Source(source) => self.source = source,
+            CitationEdit::Page(page) => self.page = non_empty(page),
+            CitationEdit::Quality(quality) => self.quality = Some(quality),
+            CitationEdit::Text(text) => self.text = non_empty(text),
+        }
+    }
+
+    /// Whether both point at the same place in the same source.
+    pub fn cites_same(&self, other: &Citation) -> bool {
+        self.source == other.source && self.page == other.page && self.text == other.text
+    }
+}
+
+/// A change made to one field of a citation in the side panel.
+#[derive(Debug, Clone)]
+pub enum CitationEdit {
+    Source(u128),
+    Page(String),
+    Quality(Quality),
+    Text(String),
+}
+
+/// What a citation is attached to.
+#[derive(Debug, Clone)]
+pub enum CitationTarget {
+    Person(u128),
+    /// An event of a person, by its index.
+    Event((u128, usize)),
+    /// The relationship between the partners of a family.
+    Relationship(Vec<u128>),
+}
+
+/// The citations of a relationship. The tree has no family records of its own, so they are kept by the partners,
+/// which is also how the exporter finds the family they belong to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationshipCitations {
+    pub partners: Vec<u128>,
+    pub citations: Vec<Citation>,
+}
+
+/// Every source of the tree together with the repositories holding them and the citations of its relationships.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Sources {
+    sources: Vec<Source>,
+    #[serde(default)]
+    repositories: Vec<Repository>,
+    relationships: Vec<RelationshipCitations>,
+}
+
+impl Sources {
+    pub fn get(&self, id: u128) -> Option<&Source> {
+        self.sources.iter().find(|source| source.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u128) -> Option<&mut Source> {
+        self.sources.iter_mut().find(|source| source.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Source> {
+        self.sources.iter()
+    }
+
+    pub fn insert(&mut self, source: Source) -> u128 {
+        let id = source.id;
+        self.sources.push(source);
+        id
+    }
+
+    /// The source imported with this xref.
+    pub fn find_xref(&self, xref: &str) -> Option<u128> {
+        self.sources
+            .iter()
+            .find(|source| source.xref.as_deref() == Some(xref))
+            .map(|source| source.id)
+    }
+
+    /// The xrefs of the sources and repositories.
+    pub fn xrefs(&self) -> impl Iterator<Item = &String> {
+        self.sources.iter().filter_map(|source| source.xref.as_ref()).chain(
+            self.repositories
+                .iter()
+                .filter_map(|repository| repository.xref.as_ref()),
+        )
+    }
+
+    pub fn repository(&self, id: u128) -> Option<&Repository> {
+        self.repositories.iter().find(|repository| repository.id() == id)
+    }
+
+    pub fn repository_mut(&mut self, id: u128) -> Option<&mut Repository> {
+        self.repositories.iter_mut().find(|repository| repository.id() == id)
+    }
+
+    pub fn repositories(&self) -> impl Iterator<Item = &Repository> {
+        self.repositories.iter()
+    }
+
+    pub fn insert_repository(&mut self, repository: Repository) -> u128 {
+        let id = repository.id();
+        self.repositories.push(repository);
+        id
+    }
+
+    /// The repository imported with this xref.
+    pub fn find_repository_xref(&self, xref: &str) -> Option<u128> {
+        self.repositories
+            .iter()
+            .find(|repository| repository.xref.as_deref() == Some(xref))
+            .map(Repository::id)
+    }
+
+    /// The sources a repository holds, together with what it files each of them under.
+    pub fn held_by(&self, repository: u128) -> impl Iterator<Item = (&Source, &RepositoryLink)> {
+        self.sources.iter().flat_map(move |source| {
+            source
+                .repositories
+                .iter()
+                .filter(move |link| link.repository == repository)
+                .map(move |link| (source, link))
+        })
+    }
+
+    /// Drops the citations of the relationships a removed person was a partner in.
+    pub fn remove_relationships_of(&mut self, person: u128) {
+        self.relationships
+            .retain(|relationship| !relationship.partners.contains(&person));
+    }
+
+    pub fn relationship(&self, partners: &[u128]) -> &[Citation] {
+        self.relationships
+            .iter()
+            .find(|relationship| same_members(&relationship.partners, partners))
+            .map(|relationship| relationship.citations.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn relationship_mut(&mut self, partners: &[u128]) -> &mut Vec<Citation> {
+        let index = match self
+            .relationships
+            .iter()
+            .position(|relationship| same_members(&relationship.partners, partners))
+        {
+            Some(index) => index,
+            None => {
+                self.relationships.push(RelationshipCitations {
+                    partners: partners.to_vec(),
+                    citations: vec![],
+                });
+                self.relationships.len() - 1
+            }
+        };
+        &mut self.relationships[index].citations
+    }
+
+    /// Splits off the sources, repositories and relationship citations, e.g. to merge them into another tree.
+    pub fn into_parts(self) -> (Vec<Source>, Vec<Repository>, Vec<RelationshipCitations>) {
+        (self.sources, self.repositories, self.relationships)
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use graph::Graph;
+use iced::{widget::image::Handle, Task};
+use image::imageops::FilterType;
+use log::warn;
+
+use crate::{genealogical_edge::GenealogicalEdge, genealogical_node::GenealogicalNode, media::Media, Message};
+
+/// The size thumbnails are decoded at, twice what the card shows so that they stay sharp when zoomed in.
+const THUMBNAIL_PIXELS: u32 = 72;
+
+/// Portraits scaled down to card size. Every file is decoded once, in the background, and its thumbnail shared by
+/// all cards showing it, so the canvas never has to touch the full-size images.
+#[derive(Default)]
+pub struct Thumbnails {
+    /// Thumbnails by file, `None` for a file that could not be read as an image.
+    loaded: HashMap<PathBuf, Option<Handle>>,
+    loading: HashSet<PathBuf>,
+}
+
+impl Thumbnails {
+    /// Gives every card the thumbnail of its portrait and starts decoding the files not seen before.
+    pub fn refresh(&mut self, graph: &mut Graph<GenealogicalNode, GenealogicalEdge>, media: &Media) -> Task<Message> {
+        let mut tasks = vec![];
+        for node in graph.nodes_mut() {
+            let Some(path) = node.portrait().and_then(|portrait| media.path(portrait)) else {
+                node.set_thumbnail(None);
+                continue;
+            };
+            match self.loaded.get(&path) {
+                Some(thumbnail) => node.set_thumbnail(thumbnail.clone()),
+                None => {
+                    if self.loading.insert(path.clone()) {
+                        tasks.push(Task::perform(load(path), Message::ThumbnailLoaded));
+                    }
+                }
+            }
+        }
+        graph.redraw();
+        Task::batch(tasks)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, thumbnail: Option<Handle>) {
+        self.loading.remove(&path);
+        self.loaded.insert(path, thumbnail);
+    }
+}
+
+async fn load(path: PathBuf) -> (PathBuf, Option<Handle>) {
+    let thumbnail = match image::open(&path) {
+        Ok(image) => {
+            let thumbnail = image
+                .resize_to_fill(THUMBNAIL_PIXELS, THUMBNAIL_PIXELS, FilterType::Triangle)
+                .into_rgba8();
+            Some(Handle::from_rgba(
+                thumbnail.width(),
+                thumbnail.height(),
+                thumbnail.into_raw(),
+            ))
+        }
+        Err(error) => {
+            warn!("Could not load a thumbnail of {}: {}", path.display(), error);
+            None
+        }
+    };
+    (path, thumbnail)
+}